@@ -33,6 +33,7 @@ use sp_domains::{
 };
 use sp_domains_fraud_proof::fraud_proof::FraudProof;
 use sp_runtime::traits::{CheckedAdd, One, Zero};
+use sp_runtime::Percent;
 use sp_std::collections::btree_set::BTreeSet;
 
 const SEED: u32 = 0;
@@ -698,6 +699,7 @@ mod benchmarks {
             RawOrigin::Signed(nominator.clone()).into(),
             operator_id,
             withdraw_amount.into(),
+            false,
         ));
         assert_ok!(Domains::<T>::nominate_operator(
             RawOrigin::Signed(nominator.clone()).into(),
@@ -712,6 +714,7 @@ mod benchmarks {
             RawOrigin::Signed(nominator.clone()),
             operator_id,
             withdraw_amount.into(),
+            false,
         );
 
         let operator = Operators::<T>::get(operator_id).expect("operator must exist");
@@ -749,6 +752,7 @@ mod benchmarks {
             RawOrigin::Signed(nominator.clone()).into(),
             operator_id,
             withdraw_amount,
+            false,
         ));
         do_finalize_domain_epoch_staking::<T>(domain_id)
             .expect("finalize domain staking should success");
@@ -860,6 +864,148 @@ mod benchmarks {
         assert_eq!(domain_obj.domain_config.operator_allow_list, new_allow_list);
     }
 
+    /// Benchmark `transfer_nomination` extrinsic with the worst possible conditions:
+    /// - There is a pending deposit of the sender from the previous epoch that need to convert
+    ///   into share
+    /// - Only transfer partial of the sender's shares to an account that is not nominating yet
+    #[benchmark]
+    fn transfer_nomination() {
+        let nominator = account("nominator", 1, SEED);
+        let receiver = account("receiver", 1, SEED);
+        let minimum_nominator_stake = T::MinNominatorStake::get();
+        let staking_amount = T::MinOperatorStake::get();
+        T::Currency::set_balance(
+            &nominator,
+            staking_amount * 2u32.into() + T::MinNominatorStake::get(),
+        );
+        T::Currency::set_balance(&receiver, T::MinNominatorStake::get());
+
+        let domain_id = register_domain::<T>();
+        let (_, operator_id) = register_helper_operator::<T>(domain_id, minimum_nominator_stake);
+        assert_ok!(Domains::<T>::nominate_operator(
+            RawOrigin::Signed(nominator.clone()).into(),
+            operator_id,
+            staking_amount,
+        ));
+        do_finalize_domain_epoch_staking::<T>(domain_id)
+            .expect("finalize domain staking should success");
+
+        // Add one more deposit to the previous epoch
+        assert_ok!(Domains::<T>::nominate_operator(
+            RawOrigin::Signed(nominator.clone()).into(),
+            operator_id,
+            staking_amount,
+        ));
+        do_finalize_domain_epoch_staking::<T>(domain_id)
+            .expect("finalize domain staking should success");
+
+        let transfer_shares: T::Share = minimum_nominator_stake.into();
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(nominator.clone()),
+            operator_id,
+            receiver.clone(),
+            transfer_shares,
+        );
+
+        let deposit = Deposits::<T>::get(operator_id, receiver).expect("deposit must exist");
+        assert_eq!(deposit.known.shares, transfer_shares);
+    }
+
+    #[benchmark]
+    fn set_accepts_nominations() {
+        let domain_id = register_domain::<T>();
+        let (operator_owner, operator_id) =
+            register_helper_operator::<T>(domain_id, T::MinNominatorStake::get());
+        do_finalize_domain_epoch_staking::<T>(domain_id)
+            .expect("finalize domain staking should success");
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(operator_owner), operator_id, false);
+
+        let operator = Operators::<T>::get(operator_id).expect("operator must exist");
+        assert!(!operator.accepts_nominations);
+    }
+
+    /// Benchmark `claim_rewards` extrinsic with the worst possible conditions:
+    /// - The nominator already claimed rewards in the current epoch, so the checkpoint is read
+    ///   and updated
+    #[benchmark]
+    fn claim_rewards() {
+        let nominator = account("nominator", 1, SEED);
+        let minimum_nominator_stake = T::MinNominatorStake::get();
+        let staking_amount = T::MinOperatorStake::get();
+        let operator_rewards =
+            T::Currency::minimum_balance().saturating_mul(BalanceOf::<T>::from(1000u32));
+        T::Currency::set_balance(&nominator, staking_amount + T::MinNominatorStake::get());
+
+        let domain_id = register_domain::<T>();
+        let (_, operator_id) = register_helper_operator::<T>(domain_id, minimum_nominator_stake);
+        assert_ok!(Domains::<T>::nominate_operator(
+            RawOrigin::Signed(nominator.clone()).into(),
+            operator_id,
+            staking_amount,
+        ));
+        do_finalize_domain_epoch_staking::<T>(domain_id)
+            .expect("finalize domain staking should success");
+
+        // Claim once to leave a checkpoint in the current epoch
+        do_reward_operators::<T>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+            .expect("reward operator should success");
+        assert_ok!(Domains::<T>::claim_rewards(
+            RawOrigin::Signed(nominator.clone()).into(),
+            operator_id,
+        ));
+        do_reward_operators::<T>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+            .expect("reward operator should success");
+        let balance_before_claim = T::Currency::balance(&nominator);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(nominator.clone()), operator_id);
+
+        assert!(T::Currency::balance(&nominator) > balance_before_claim);
+    }
+
+    /// Benchmark `withdraw_operator_tax` extrinsic with the worst possible conditions:
+    /// - The operator owner already withdrew the tax in the current epoch
+    #[benchmark]
+    fn withdraw_operator_tax() {
+        let operator_rewards =
+            T::Currency::minimum_balance().saturating_mul(BalanceOf::<T>::from(1000u32));
+
+        let domain_id = register_domain::<T>();
+        let (operator_owner, operator_id) =
+            register_helper_operator::<T>(domain_id, T::MinNominatorStake::get());
+        Operators::<T>::mutate(operator_id, |maybe_operator| {
+            maybe_operator
+                .as_mut()
+                .expect("operator must exist")
+                .nomination_tax = Percent::from_percent(10);
+        });
+        do_finalize_domain_epoch_staking::<T>(domain_id)
+            .expect("finalize domain staking should success");
+
+        // Withdraw once so the withdrawn tax is taken into account
+        do_reward_operators::<T>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+            .expect("reward operator should success");
+        assert_ok!(Domains::<T>::withdraw_operator_tax(
+            RawOrigin::Signed(operator_owner.clone()).into(),
+            operator_id,
+        ));
+        do_reward_operators::<T>(domain_id, vec![operator_id].into_iter(), operator_rewards)
+            .expect("reward operator should success");
+        let tax_withdrawn = Operators::<T>::get(operator_id)
+            .expect("operator must exist")
+            .tax_withdrawn_in_epoch;
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(operator_owner), operator_id);
+
+        let operator = Operators::<T>::get(operator_id).expect("operator must exist");
+        assert!(operator.tax_withdrawn_in_epoch > tax_withdrawn);
+    }
+
     fn register_runtime<T: Config>() -> RuntimeId {
         let genesis_storage = include_bytes!("../res/evm-domain-genesis-storage").to_vec();
         let runtime_id = NextRuntimeId::<T>::get();
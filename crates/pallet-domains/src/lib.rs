@@ -41,7 +41,7 @@ use crate::domain_registry::Error as DomainRegistryError;
 use crate::runtime_registry::into_complete_raw_genesis;
 #[cfg(feature = "runtime-benchmarks")]
 pub use crate::staking::do_register_operator;
-use crate::staking::{do_reward_operators, OperatorStatus};
+use crate::staking::{do_reward_operators, DomainEpoch, EpochStakeDelta, OperatorStatus};
 use crate::staking_epoch::EpochTransitionResult;
 use crate::weights::WeightInfo;
 #[cfg(not(feature = "std"))]
@@ -65,9 +65,9 @@ use sp_consensus_subspace::WrappedPotOutput;
 use sp_core::H256;
 use sp_domains::bundle_producer_election::BundleProducerElectionParams;
 use sp_domains::{
-    DomainBlockLimit, DomainBundleLimit, DomainId, DomainInstanceData, ExecutionReceipt,
-    OpaqueBundle, OperatorId, OperatorPublicKey, RuntimeId,
-    DOMAIN_EXTRINSICS_SHUFFLING_SEED_SUBJECT, EMPTY_EXTRINSIC_ROOT,
+    DomainBlockLimit, DomainBundleLimit, DomainId, DomainInstanceData, EpochIndex,
+    ExecutionReceipt, OpaqueBundle, OperatorId, OperatorPublicKey, OperatorStatusInfo,
+    PendingWithdrawal, RuntimeId, DOMAIN_EXTRINSICS_SHUFFLING_SEED_SUBJECT, EMPTY_EXTRINSIC_ROOT,
 };
 use sp_domains_fraud_proof::fraud_proof::{
     DomainRuntimeCodeAt, FraudProof, FraudProofVariant, InvalidBlockFeesProof,
@@ -82,7 +82,7 @@ use sp_domains_fraud_proof::verification::{
 };
 use sp_runtime::traits::{BlockNumberProvider, CheckedSub, Hash, Header, One, Zero};
 use sp_runtime::transaction_validity::TransactionPriority;
-use sp_runtime::{RuntimeAppPublic, SaturatedConversion, Saturating};
+use sp_runtime::{Perbill, RuntimeAppPublic, SaturatedConversion, Saturating};
 use sp_subspace_mmr::{ConsensusChainMmrLeafProof, MmrProofVerifier};
 pub use staking::OperatorConfig;
 use subspace_core_primitives::{BlockHash, PotOutput, SlotNumber, U256};
@@ -90,6 +90,12 @@ use subspace_core_primitives::{BlockHash, PotOutput, SlotNumber, U256};
 /// Maximum number of nominators to slash within a give operator at a time.
 pub const MAX_NOMINATORS_TO_SLASH: u32 = 10;
 
+/// Maximum number of operators returned by `Pallet::operator_rankings`.
+pub const MAX_OPERATOR_RANKINGS: u32 = 256;
+
+/// Number of past epochs for which the operator stake is retained in `OperatorEpochStake`.
+pub const OPERATOR_STAKE_HISTORY_DEPTH: EpochIndex = 32;
+
 pub(crate) type BalanceOf<T> = <T as Config>::Balance;
 
 pub(crate) type FungibleHoldId<T> =
@@ -153,7 +159,7 @@ pub type BlockTreeNodeFor<T> = crate::block_tree::BlockTreeNode<
 >;
 
 /// The current storage version.
-const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
 
 /// The number of bundle of a particular domain to be included in the block is probabilistic
 /// and based on the consensus chain slot probability and domain bundle slot probability, usually
@@ -189,9 +195,11 @@ mod pallet {
     #[cfg(not(feature = "runtime-benchmarks"))]
     use crate::staking::do_reward_operators;
     use crate::staking::{
-        do_deregister_operator, do_nominate_operator, do_register_operator, do_unlock_funds,
-        do_unlock_nominator, do_withdraw_stake, Deposit, DomainEpoch, Error as StakingError,
-        Operator, OperatorConfig, SharePrice, StakingSummary, Withdrawal,
+        do_claim_rewards, do_deregister_operator, do_nominate_operator, do_register_operator,
+        do_set_accepts_nominations, do_transfer_nomination, do_unlock_funds, do_unlock_nominator,
+        do_withdraw_operator_tax, do_withdraw_stake, Deposit, DomainEpoch, EpochStakeDelta,
+        Error as StakingError, Operator, OperatorConfig, RewardCheckpoint, SharePrice,
+        StakingSummary, Withdrawal,
     };
     #[cfg(not(feature = "runtime-benchmarks"))]
     use crate::staking_epoch::do_slash_operator;
@@ -476,6 +484,11 @@ mod pallet {
     pub(super) type OperatorIdOwner<T: Config> =
         StorageMap<_, Identity, OperatorId, T::AccountId, OptionQuery>;
 
+    /// Indexes the operators registered by an account, the reverse of `OperatorIdOwner`.
+    #[pallet::storage]
+    pub(super) type OperatorIdsByOwner<T: Config> =
+        StorageMap<_, Identity, T::AccountId, BTreeSet<OperatorId>, ValueQuery>;
+
     /// Indexes operator signing key against OperatorId.
     #[pallet::storage]
     #[pallet::getter(fn operator_signing_key)]
@@ -487,6 +500,12 @@ mod pallet {
     pub(super) type DomainStakingSummary<T: Config> =
         StorageMap<_, Identity, DomainId, StakingSummary<OperatorId, BalanceOf<T>>, OptionQuery>;
 
+    /// Stake deposited, withdrawn and rewarded in the domain's current epoch, reset when the epoch
+    /// is finalized.
+    #[pallet::storage]
+    pub(super) type DomainEpochStakeDelta<T: Config> =
+        StorageMap<_, Identity, DomainId, EpochStakeDelta<BalanceOf<T>>, ValueQuery>;
+
     /// List of all registered operators and their configuration.
     #[pallet::storage]
     pub(super) type Operators<T: Config> = StorageMap<
@@ -514,6 +533,13 @@ mod pallet {
     pub type OperatorEpochSharePrice<T: Config> =
         StorageDoubleMap<_, Identity, OperatorId, Identity, DomainEpoch, SharePrice, OptionQuery>;
 
+    /// Stake of the operator used for the bundle election during a past Domain epoch.
+    ///
+    /// Only the last `OPERATOR_STAKE_HISTORY_DEPTH` epochs are retained.
+    #[pallet::storage]
+    pub(super) type OperatorEpochStake<T: Config> =
+        StorageDoubleMap<_, Identity, OperatorId, Identity, DomainEpoch, BalanceOf<T>, OptionQuery>;
+
     /// List of all deposits for given Operator.
     #[pallet::storage]
     pub(super) type Deposits<T: Config> = StorageDoubleMap<
@@ -538,6 +564,24 @@ mod pallet {
         OptionQuery,
     >;
 
+    /// Checkpoint of the nominator's last reward claim under given operator.
+    #[pallet::storage]
+    pub(super) type NominatorRewardCheckpoints<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        OperatorId,
+        Identity,
+        NominatorId<T>,
+        RewardCheckpoint<BalanceOf<T>, T::Share>,
+        OptionQuery,
+    >;
+
+    /// Total stake and shares of the operator pool at the start of the current domain epoch,
+    /// recorded by the first reward claim of the epoch before it changes the pool.
+    #[pallet::storage]
+    pub(super) type OperatorRewardBaselines<T: Config> =
+        StorageMap<_, Identity, OperatorId, RewardCheckpoint<BalanceOf<T>, T::Share>, OptionQuery>;
+
     /// Tracks the nominator count under given operator.
     /// This storage is necessary since CountedStorageNMap does not support prefix key count, so
     /// cannot use that storage type for `Nominators` storage.
@@ -926,11 +970,30 @@ mod pallet {
             operator_id: OperatorId,
             nominator_id: NominatorId<T>,
         },
+        NominationTransferred {
+            operator_id: OperatorId,
+            from: NominatorId<T>,
+            to: NominatorId<T>,
+            shares: T::Share,
+        },
+        OperatorAcceptsNominationsUpdated {
+            operator_id: OperatorId,
+            accepts_nominations: bool,
+        },
         FundsUnlocked {
             operator_id: OperatorId,
             nominator_id: NominatorId<T>,
             amount: BalanceOf<T>,
         },
+        NominatorRewardsClaimed {
+            operator_id: OperatorId,
+            nominator_id: NominatorId<T>,
+            amount: BalanceOf<T>,
+        },
+        OperatorTaxWithdrawn {
+            operator_id: OperatorId,
+            amount: BalanceOf<T>,
+        },
         PreferredOperator {
             operator_id: OperatorId,
             nominator_id: NominatorId<T>,
@@ -967,6 +1030,10 @@ mod pallet {
             nominator_id: NominatorId<T>,
             amount: BalanceOf<T>,
         },
+        OperatorStakeBelowMinimum {
+            operator_id: OperatorId,
+            owner_stake: BalanceOf<T>,
+        },
     }
 
     /// Per-domain state for tx range calculation.
@@ -1389,16 +1456,22 @@ mod pallet {
             Ok(())
         }
 
+        /// Withdraws the given shares from the operator pool.
+        ///
+        /// If the remaining stake of a nominator would fall below the minimum nominator stake, all
+        /// of the shares are withdrawn, or the withdrawal is rejected if `strict` is set.
         #[pallet::call_index(9)]
         #[pallet::weight(T::WeightInfo::withdraw_stake())]
         pub fn withdraw_stake(
             origin: OriginFor<T>,
             operator_id: OperatorId,
             shares: T::Share,
+            strict: bool,
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
-            do_withdraw_stake::<T>(operator_id, who.clone(), shares).map_err(Error::<T>::from)?;
+            do_withdraw_stake::<T>(operator_id, who.clone(), shares, strict)
+                .map_err(Error::<T>::from)?;
 
             Self::deposit_event(Event::WithdrewStake {
                 operator_id,
@@ -1408,6 +1481,91 @@ mod pallet {
             Ok(())
         }
 
+        /// Transfers the given shares of the caller under the operator to another account,
+        /// without going through the withdrawal and unlocking period.
+        /// The receiving account can only become a new nominator if the operator accepts
+        /// nominations.
+        #[pallet::call_index(17)]
+        #[pallet::weight(T::WeightInfo::transfer_nomination())]
+        pub fn transfer_nomination(
+            origin: OriginFor<T>,
+            operator_id: OperatorId,
+            to: NominatorId<T>,
+            shares: T::Share,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            do_transfer_nomination::<T>(operator_id, who.clone(), to.clone(), shares)
+                .map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::NominationTransferred {
+                operator_id,
+                from: who,
+                to,
+                shares,
+            });
+
+            Ok(())
+        }
+
+        /// Opens or closes the operator for new nominators, existing nominators can still top up
+        /// their stake.
+        #[pallet::call_index(18)]
+        #[pallet::weight(T::WeightInfo::set_accepts_nominations())]
+        pub fn set_accepts_nominations(
+            origin: OriginFor<T>,
+            operator_id: OperatorId,
+            accepts_nominations: bool,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            do_set_accepts_nominations::<T>(who, operator_id, accepts_nominations)
+                .map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::OperatorAcceptsNominationsUpdated {
+                operator_id,
+                accepts_nominations,
+            });
+
+            Ok(())
+        }
+
+        /// Claims the nominator's share of the operator's current epoch rewards accrued since the
+        /// last claim into their free balance, rewards that are not claimed before the epoch
+        /// transition are compounded into the stake as usual.
+        #[pallet::call_index(19)]
+        #[pallet::weight(T::WeightInfo::claim_rewards())]
+        pub fn claim_rewards(origin: OriginFor<T>, operator_id: OperatorId) -> DispatchResult {
+            let nominator_id = ensure_signed(origin)?;
+            let amount = do_claim_rewards::<T>(operator_id, nominator_id.clone())
+                .map_err(Error::<T>::from)?;
+            Self::deposit_event(Event::NominatorRewardsClaimed {
+                operator_id,
+                nominator_id,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Withdraws the operator tax on the operator's current epoch rewards into the free
+        /// balance of the operator owner, leaving the stake and the shares of the nominators
+        /// untouched. Tax that is not withdrawn before the epoch transition is staked as usual.
+        #[pallet::call_index(20)]
+        #[pallet::weight(T::WeightInfo::withdraw_operator_tax())]
+        pub fn withdraw_operator_tax(
+            origin: OriginFor<T>,
+            operator_id: OperatorId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let amount =
+                do_withdraw_operator_tax::<T>(who, operator_id).map_err(Error::<T>::from)?;
+            Self::deposit_event(Event::OperatorTaxWithdrawn {
+                operator_id,
+                amount,
+            });
+            Ok(())
+        }
+
         /// Unlocks the first withdrawal given the unlocking period is complete.
         /// Even if rest of the withdrawals are out of unlocking period, nominator
         /// should call this extrinsic to unlock each withdrawal
@@ -1854,6 +2012,138 @@ impl<T: Config> Pallet<T> {
             .map(|operator| (operator.signing_key, operator.current_total_stake))
     }
 
+    /// Returns the current epoch operators of the given domain sorted by stake in descending
+    /// order (ties broken by operator id), along with each operator's share of the domain total
+    /// stake.
+    ///
+    /// At most `MAX_OPERATOR_RANKINGS` operators are returned.
+    pub fn operator_rankings(domain_id: DomainId) -> Vec<(OperatorId, BalanceOf<T>, Perbill)> {
+        let Some(stake_summary) = DomainStakingSummary::<T>::get(domain_id) else {
+            return Vec::new();
+        };
+
+        let total_stake = stake_summary.current_total_stake;
+        let mut rankings = stake_summary
+            .current_operators
+            .into_iter()
+            .map(|(operator_id, stake)| {
+                (
+                    operator_id,
+                    stake,
+                    Perbill::from_rational(stake, total_stake),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        rankings.sort_by(|(id_a, stake_a, _), (id_b, stake_b, _)| {
+            stake_b.cmp(stake_a).then_with(|| id_a.cmp(id_b))
+        });
+        rankings.truncate(MAX_OPERATOR_RANKINGS as usize);
+        rankings
+    }
+
+    /// Returns the stake of the operator used for the bundle election at the given epoch of
+    /// the operator's domain.
+    ///
+    /// Returns `None` if the operator was not part of the epoch or if the epoch is older than
+    /// the last `OPERATOR_STAKE_HISTORY_DEPTH` epochs.
+    pub fn operator_stake_at(
+        operator_id: OperatorId,
+        epoch_index: EpochIndex,
+    ) -> Option<BalanceOf<T>> {
+        let domain_id = Operators::<T>::get(operator_id)?.current_domain_id;
+        let stake_summary = DomainStakingSummary::<T>::get(domain_id)?;
+        let current_epoch_index = stake_summary.current_epoch_index;
+
+        if epoch_index == current_epoch_index {
+            return stake_summary.current_operators.get(&operator_id).copied();
+        }
+
+        if epoch_index > current_epoch_index
+            || current_epoch_index - epoch_index > OPERATOR_STAKE_HISTORY_DEPTH
+        {
+            return None;
+        }
+
+        OperatorEpochStake::<T>::get(operator_id, DomainEpoch::from((domain_id, epoch_index)))
+    }
+
+    /// Returns the lowest stake among the current epoch operators of the given domain, i.e. the
+    /// stake a newcomer needs to be on par with the weakest operator of the domain.
+    ///
+    /// The operator set of a domain is not capped, so zero is returned when the domain has no
+    /// operators.
+    pub fn min_stake_to_enter(domain_id: DomainId) -> BalanceOf<T> {
+        DomainStakingSummary::<T>::get(domain_id)
+            .and_then(|stake_summary| stake_summary.current_operators.into_values().min())
+            .unwrap_or_else(Zero::zero)
+    }
+
+    /// Returns the stake deposited, withdrawn and rewarded in the given domain's current epoch.
+    pub fn epoch_stake_delta(domain_id: DomainId) -> EpochStakeDelta<BalanceOf<T>> {
+        DomainEpochStakeDelta::<T>::get(domain_id)
+    }
+
+    /// Returns the ids of the operators owned by the given account in ascending order.
+    pub fn operators_of_owner(owner: T::AccountId) -> Vec<OperatorId> {
+        OperatorIdsByOwner::<T>::get(owner).into_iter().collect()
+    }
+
+    /// Returns the nominator's deposit, including the storage fee deposit, to the operator that
+    /// is not yet converted to shares since the domain epoch of the deposit has not completed.
+    pub fn pending_deposit(
+        operator_id: OperatorId,
+        nominator_id: NominatorId<T>,
+    ) -> Option<BalanceOf<T>> {
+        let pending_deposit = Deposits::<T>::get(operator_id, nominator_id)?.pending?;
+        if OperatorEpochSharePrice::<T>::contains_key(
+            operator_id,
+            pending_deposit.effective_domain_epoch,
+        ) {
+            return None;
+        }
+
+        pending_deposit.total().ok()
+    }
+
+    /// Returns the nominator's withdrawal from the operator that is not yet converted to balance
+    /// since the domain epoch of the withdrawal has not completed.
+    pub fn pending_withdrawal(
+        operator_id: OperatorId,
+        nominator_id: NominatorId<T>,
+    ) -> Option<PendingWithdrawal<BalanceOf<T>, T::Share>> {
+        let withdrawal_in_shares =
+            Withdrawals::<T>::get(operator_id, nominator_id)?.withdrawal_in_shares?;
+        if OperatorEpochSharePrice::<T>::contains_key(
+            operator_id,
+            withdrawal_in_shares.domain_epoch,
+        ) {
+            return None;
+        }
+
+        let (_, epoch_index) = withdrawal_in_shares.domain_epoch.deconstruct();
+        Some(PendingWithdrawal {
+            epoch_index,
+            shares: withdrawal_in_shares.shares,
+            storage_fee_refund: withdrawal_in_shares.storage_fee_refund,
+        })
+    }
+
+    /// Returns the status of the operator, `None` if the operator doesn't exist.
+    pub fn operator_status(operator_id: OperatorId) -> Option<OperatorStatusInfo> {
+        let operator = Operators::<T>::get(operator_id)?;
+        let status = operator.status::<T>(operator_id);
+        Some(OperatorStatusInfo {
+            is_frozen: matches!(
+                status,
+                OperatorStatus::Slashed | OperatorStatus::PendingSlash
+            ),
+            current_domain_id: operator.current_domain_id,
+            next_domain_id: operator.next_domain_id,
+            pending_deregistration: matches!(status, OperatorStatus::Deregistered(_)),
+        })
+    }
+
     fn check_extrinsics_root(opaque_bundle: &OpaqueBundleOf<T>) -> Result<(), BundleError> {
         let expected_extrinsics_root = <T::DomainHeader as Header>::Hashing::ordered_trie_root(
             opaque_bundle
@@ -11,6 +11,14 @@ impl<T: Config> UncheckedOnRuntimeUpgrade for VersionUncheckedMigrateV0ToV1<T> {
     }
 }
 
+pub struct VersionUncheckedMigrateV1ToV2<T>(sp_std::marker::PhantomData<T>);
+impl<T: Config> UncheckedOnRuntimeUpgrade for VersionUncheckedMigrateV1ToV2<T> {
+    fn on_runtime_upgrade() -> Weight {
+        operator_structure_migration::migrate_operator_structure::<T>()
+            .saturating_add(operator_structure_migration::index_operators_by_owner::<T>())
+    }
+}
+
 pub(super) mod runtime_registry_instance_count_migration {
     use crate::pallet::{
         DomainRegistry, LatestConfirmedDomainExecutionReceipt, RuntimeRegistry as RuntimeRegistryV1,
@@ -156,25 +164,119 @@ pub(super) mod runtime_registry_instance_count_migration {
     }
 }
 
+pub(super) mod operator_structure_migration {
+    use crate::pallet::{OperatorIdOwner, OperatorIdsByOwner, Operators as OperatorsV2};
+    use crate::staking::{Operator as OperatorV2, OperatorStatus};
+    use crate::{BalanceOf, Config, DomainBlockNumberFor};
+    use codec::{Decode, Encode};
+    use frame_support::pallet_prelude::{OptionQuery, TypeInfo, Weight};
+    use frame_support::{storage_alias, Identity};
+    use sp_core::Get;
+    use sp_domains::{DomainId, OperatorId, OperatorPublicKey};
+    use sp_runtime::traits::Zero;
+    use sp_runtime::Percent;
+
+    #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
+    pub struct Operator<Balance, Share, DomainBlockNumber> {
+        pub signing_key: OperatorPublicKey,
+        pub current_domain_id: DomainId,
+        pub next_domain_id: DomainId,
+        pub minimum_nominator_stake: Balance,
+        pub nomination_tax: Percent,
+        pub current_total_stake: Balance,
+        pub current_epoch_rewards: Balance,
+        pub current_total_shares: Share,
+        pub status: OperatorStatus<DomainBlockNumber>,
+        pub deposits_in_epoch: Balance,
+        pub withdrawals_in_epoch: Share,
+        pub total_storage_fee_deposit: Balance,
+    }
+
+    #[storage_alias]
+    pub(super) type Operators<T: Config> = StorageMap<
+        crate::Pallet<T>,
+        Identity,
+        OperatorId,
+        Operator<BalanceOf<T>, <T as Config>::Share, DomainBlockNumberFor<T>>,
+        OptionQuery,
+    >;
+
+    /// Adds the fields introduced in v2 to all the operators, the existing operators keep
+    /// accepting new nominators and haven't withdrawn any tax in the current epoch yet.
+    pub(super) fn migrate_operator_structure<T: Config>() -> Weight {
+        let mut count = 0;
+        OperatorsV2::<T>::translate_values::<
+            Operator<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+            _,
+        >(|operator_v1| {
+            count += 1;
+            Some(OperatorV2 {
+                signing_key: operator_v1.signing_key,
+                current_domain_id: operator_v1.current_domain_id,
+                next_domain_id: operator_v1.next_domain_id,
+                minimum_nominator_stake: operator_v1.minimum_nominator_stake,
+                nomination_tax: operator_v1.nomination_tax,
+                current_total_stake: operator_v1.current_total_stake,
+                current_epoch_rewards: operator_v1.current_epoch_rewards,
+                current_total_shares: operator_v1.current_total_shares,
+                status: operator_v1.status,
+                deposits_in_epoch: operator_v1.deposits_in_epoch,
+                withdrawals_in_epoch: operator_v1.withdrawals_in_epoch,
+                total_storage_fee_deposit: operator_v1.total_storage_fee_deposit,
+                accepts_nominations: true,
+                tax_withdrawn_in_epoch: Zero::zero(),
+            })
+        });
+
+        T::DbWeight::get().reads_writes(count, count)
+    }
+
+    /// Indexes the existing operators against their owners in `OperatorIdsByOwner` introduced in
+    /// v2.
+    pub(super) fn index_operators_by_owner<T: Config>() -> Weight {
+        let mut count = 0;
+        OperatorIdOwner::<T>::iter().for_each(|(operator_id, owner)| {
+            count += 1;
+            OperatorIdsByOwner::<T>::mutate(owner, |operator_ids| {
+                operator_ids.insert(operator_id);
+            });
+        });
+
+        // 1 read of the owner and 1 read and 1 write of the owner's operators for each operator
+        T::DbWeight::get().reads_writes(count * 2, count)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::domain_registry::{do_instantiate_domain, DomainConfig};
+    use crate::migrations::operator_structure_migration::{
+        index_operators_by_owner, migrate_operator_structure, Operator as OperatorV1,
+        Operators as OperatorsV1,
+    };
     use crate::migrations::runtime_registry_instance_count_migration::{
         ConfirmedDomainBlock, LatestConfirmedDomainBlock, RuntimeObject, RuntimeRegistry,
     };
     use crate::pallet::{
-        LatestConfirmedDomainExecutionReceipt, RuntimeRegistry as RuntimeRegistryV1,
+        LatestConfirmedDomainExecutionReceipt, OperatorIdOwner, OperatorIdsByOwner,
+        Operators as OperatorsV2, RuntimeRegistry as RuntimeRegistryV1,
     };
-    use crate::tests::{new_test_ext, Balances, Test};
+    use crate::staking::OperatorStatus;
+    use crate::tests::{new_test_ext, Balances, Domains, Test};
     use crate::{Config, DomainSudoCalls};
     use domain_runtime_primitives::{AccountId20, AccountId20Converter};
     use frame_support::pallet_prelude::Weight;
     use frame_support::traits::Currency;
     use hex_literal::hex;
+    use sp_core::crypto::UncheckedFrom;
     use sp_domains::storage::RawGenesis;
-    use sp_domains::{DomainId, OperatorAllowList, RuntimeObject as RuntimeObjectV1};
-    use sp_runtime::traits::Convert;
+    use sp_domains::{
+        DomainId, OperatorAllowList, OperatorPublicKey, RuntimeObject as RuntimeObjectV1,
+    };
+    use sp_runtime::traits::{Convert, Zero};
+    use sp_runtime::Percent;
     use sp_version::RuntimeVersion;
+    use std::collections::BTreeSet;
     use subspace_runtime_primitives::SSC;
 
     #[test]
@@ -291,4 +393,97 @@ mod tests {
             assert_eq!(er.parent_domain_block_receipt_hash, expected_er.parent_domain_block_receipt_hash);
         });
     }
+
+    #[test]
+    fn test_migrate_operator_structure() {
+        let mut ext = new_test_ext();
+        let operator_id = 0;
+        let operator_v1 = OperatorV1 {
+            signing_key: OperatorPublicKey::unchecked_from([1u8; 32]),
+            current_domain_id: DomainId::new(0),
+            next_domain_id: DomainId::new(0),
+            minimum_nominator_stake: 100 * SSC,
+            nomination_tax: Percent::from_percent(5),
+            current_total_stake: 1000 * SSC,
+            current_epoch_rewards: 10 * SSC,
+            current_total_shares: 1000 * SSC,
+            status: OperatorStatus::Registered,
+            deposits_in_epoch: 50 * SSC,
+            withdrawals_in_epoch: 20 * SSC,
+            total_storage_fee_deposit: 200 * SSC,
+        };
+
+        ext.execute_with(|| OperatorsV1::<Test>::insert(operator_id, operator_v1.clone()));
+        ext.commit_all().unwrap();
+
+        ext.execute_with(|| {
+            let weights = migrate_operator_structure::<Test>();
+            assert_eq!(
+                weights,
+                <Test as frame_system::Config>::DbWeight::get().reads_writes(1, 1),
+            );
+
+            let operator = OperatorsV2::<Test>::get(operator_id).unwrap();
+            assert_eq!(operator.signing_key, operator_v1.signing_key);
+            assert_eq!(
+                operator.minimum_nominator_stake,
+                operator_v1.minimum_nominator_stake
+            );
+            assert_eq!(operator.nomination_tax, operator_v1.nomination_tax);
+            assert_eq!(
+                operator.current_total_stake,
+                operator_v1.current_total_stake
+            );
+            assert_eq!(
+                operator.current_epoch_rewards,
+                operator_v1.current_epoch_rewards
+            );
+            assert_eq!(
+                operator.current_total_shares,
+                operator_v1.current_total_shares
+            );
+            assert_eq!(
+                *operator.status::<Test>(operator_id),
+                OperatorStatus::Registered
+            );
+            assert_eq!(
+                operator.total_storage_fee_deposit,
+                operator_v1.total_storage_fee_deposit
+            );
+            assert!(operator.accepts_nominations);
+            assert!(operator.tax_withdrawn_in_epoch.is_zero());
+        });
+    }
+
+    #[test]
+    fn test_index_operators_by_owner() {
+        let mut ext = new_test_ext();
+        let owner_a = 1u128;
+        let owner_b = 2u128;
+
+        ext.execute_with(|| {
+            OperatorIdOwner::<Test>::insert(0, owner_a);
+            OperatorIdOwner::<Test>::insert(1, owner_b);
+            OperatorIdOwner::<Test>::insert(2, owner_a);
+        });
+        ext.commit_all().unwrap();
+
+        ext.execute_with(|| {
+            let weights = index_operators_by_owner::<Test>();
+            assert_eq!(
+                weights,
+                <Test as frame_system::Config>::DbWeight::get().reads_writes(6, 3),
+            );
+
+            assert_eq!(
+                OperatorIdsByOwner::<Test>::get(owner_a),
+                BTreeSet::from([0, 2])
+            );
+            assert_eq!(
+                OperatorIdsByOwner::<Test>::get(owner_b),
+                BTreeSet::from([1])
+            );
+            assert_eq!(Domains::operators_of_owner(owner_a), vec![0, 2]);
+        });
+    }
 }
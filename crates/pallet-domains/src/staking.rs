@@ -5,9 +5,10 @@ extern crate alloc;
 
 use crate::bundle_storage_fund::{self, deposit_reserve_for_storage_fund};
 use crate::pallet::{
-    Deposits, DomainRegistry, DomainStakingSummary, NextOperatorId, NominatorCount,
-    OperatorIdOwner, OperatorSigningKey, Operators, PendingSlashes, PendingStakingOperationCount,
-    Withdrawals,
+    Deposits, DomainEpochStakeDelta, DomainRegistry, DomainStakingSummary, NextOperatorId,
+    NominatorCount, NominatorRewardCheckpoints, OperatorEpochStake, OperatorIdOwner,
+    OperatorIdsByOwner, OperatorRewardBaselines, OperatorSigningKey, Operators, PendingSlashes,
+    PendingStakingOperationCount, Withdrawals,
 };
 use crate::staking_epoch::{mint_funds, mint_into_treasury};
 use crate::{
@@ -16,7 +17,7 @@ use crate::{
 };
 use codec::{Decode, Encode};
 use frame_support::traits::fungible::{Inspect, InspectHold, MutateHold};
-use frame_support::traits::tokens::{Fortitude, Precision, Preservation};
+use frame_support::traits::tokens::{Fortitude, Precision, Preservation, Restriction};
 use frame_support::{ensure, PalletError};
 use scale_info::TypeInfo;
 use sp_core::{sr25519, Get};
@@ -40,6 +41,15 @@ pub(crate) struct Deposit<Share: Copy, Balance: Copy> {
 
 /// A share price is parts per billion of shares/ssc.
 /// Note: Shares must always be equal to or lower than ssc.
+///
+/// Conversions in either direction always round down, so a nominator never gets more shares for
+/// their deposit, or more stake for their shares, than the pool holds for them. Since each deposit
+/// is converted on its own while the pool converts the epoch's total deposits at once, the pool may
+/// mint up to one more share per deposit than the nominators hold. These unowned shares stay in the
+/// pool as dust, so the sum of the nominator shares never exceeds the operator's total shares and
+/// the stake the nominators can redeem never exceeds the operator's total stake. The dust is
+/// credited to the treasury, its stake is minted into the treasury along with any other stake
+/// left in the pool once the operator is cleaned up.
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq, Default)]
 pub struct SharePrice(Perbill);
 
@@ -53,7 +63,7 @@ impl SharePrice {
         })
     }
 
-    /// Converts stake to shares based on the share price
+    /// Converts stake to shares based on the share price, rounding down.
     pub(crate) fn stake_to_shares<T: Config>(&self, stake: BalanceOf<T>) -> T::Share {
         if self.0.is_one() {
             stake.into()
@@ -62,7 +72,7 @@ impl SharePrice {
         }
     }
 
-    /// Converts shares to stake based on the share price
+    /// Converts shares to stake based on the share price, rounding down.
     pub(crate) fn shares_to_stake<T: Config>(&self, shares: T::Share) -> BalanceOf<T> {
         if self.0.is_one() {
             shares.into()
@@ -109,7 +119,7 @@ pub(crate) struct PendingDeposit<Balance: Copy> {
 }
 
 impl<Balance: Copy + CheckedAdd> PendingDeposit<Balance> {
-    fn total(&self) -> Result<Balance, Error> {
+    pub(crate) fn total(&self) -> Result<Balance, Error> {
         self.amount
             .checked_add(&self.storage_fee_deposit)
             .ok_or(Error::BalanceOverflow)
@@ -145,6 +155,18 @@ pub(crate) struct WithdrawalInShares<DomainBlockNumber, Share, Balance> {
     pub(crate) storage_fee_refund: Balance,
 }
 
+/// Total stake and shares of the operator pool right after the nominator's last reward claim, or
+/// at the start of the epoch for the operator's reward baseline.
+///
+/// Only rewards accrued after the checkpoint within the same domain epoch can be claimed, rewards
+/// that are not claimed by the end of the epoch are compounded into the stake.
+#[derive(TypeInfo, Debug, Encode, Decode, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct RewardCheckpoint<Balance, Share> {
+    pub(crate) domain_epoch: DomainEpoch,
+    pub(crate) total_stake: Balance,
+    pub(crate) total_shares: Share,
+}
+
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
 pub struct OperatorDeregisteredInfo<DomainBlockNumber> {
     pub domain_epoch: DomainEpoch,
@@ -190,13 +212,17 @@ pub struct Operator<Balance, Share, DomainBlockNumber> {
     /// not assigned to this field directly, thus MUST use the `status()` method to query the status
     /// instead.
     /// TODO: update the filed to `_status` to avoid accidental access in next network reset
-    status: OperatorStatus<DomainBlockNumber>,
+    pub(crate) status: OperatorStatus<DomainBlockNumber>,
     /// Total deposits during the previous epoch
     pub deposits_in_epoch: Balance,
     /// Total withdrew shares during the previous epoch
     pub withdrawals_in_epoch: Share,
     /// Total balance deposited to the bundle storage fund
     pub total_storage_fee_deposit: Balance,
+    /// Whether new nominators are accepted, existing nominators can still top up their stake
+    pub accepts_nominations: bool,
+    /// Operator tax on the current epoch rewards already withdrawn by the operator owner
+    pub tax_withdrawn_in_epoch: Balance,
 }
 
 impl<Balance, Share, DomainBlockNumber> Operator<Balance, Share, DomainBlockNumber> {
@@ -235,6 +261,8 @@ impl<Balance: Zero, Share: Zero, DomainBlockNumber> Operator<Balance, Share, Dom
             deposits_in_epoch: Zero::zero(),
             withdrawals_in_epoch: Zero::zero(),
             total_storage_fee_deposit: Zero::zero(),
+            accepts_nominations: true,
+            tax_withdrawn_in_epoch: Zero::zero(),
         }
     }
 }
@@ -253,6 +281,18 @@ pub struct StakingSummary<OperatorId, Balance> {
     pub current_epoch_rewards: BTreeMap<OperatorId, Balance>,
 }
 
+/// Stake flowing in and out of a domain during its current epoch.
+#[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq, Default)]
+pub struct EpochStakeDelta<Balance> {
+    /// Stake deposited by operators and nominators, excluding the storage fee deposit.
+    pub deposits: Balance,
+    /// Stake withdrawn by operators and nominators, valued at the share price at the time of
+    /// withdrawal and excluding the storage fee refund.
+    pub withdrawals: Balance,
+    /// Rewards distributed to the operators of the domain.
+    pub rewards: Balance,
+}
+
 #[derive(TypeInfo, Debug, Encode, Decode, Clone, PartialEq, Eq)]
 pub struct OperatorConfig<Balance> {
     pub signing_key: OperatorPublicKey,
@@ -293,10 +333,16 @@ pub enum Error {
     EpochNotComplete,
     UnlockPeriodNotComplete,
     OperatorNotDeregistered,
+    OperatorFrozen,
+    WouldFallBelowMinimum,
     BundleStorageFund(bundle_storage_fund::Error),
     UnconfirmedER,
     /// Invalid signature from Signing key owner.
     InvalidSigningKeySignature,
+    TransferToSelf,
+    ZeroTransferShares,
+    PendingWithdrawal,
+    NominationsClosed,
 }
 
 // Increase `PendingStakingOperationCount` by one and check if the `MaxPendingStakingOperation`
@@ -366,6 +412,9 @@ pub fn do_register_operator<T: Config>(
         NextOperatorId::<T>::set(next_operator_id);
 
         OperatorIdOwner::<T>::insert(operator_id, operator_owner.clone());
+        OperatorIdsByOwner::<T>::mutate(operator_owner.clone(), |operator_ids| {
+            operator_ids.insert(operator_id);
+        });
 
         // reserve stake balance
         ensure!(
@@ -403,9 +452,14 @@ pub fn do_register_operator<T: Config>(
             deposits_in_epoch: new_deposit.staking,
             withdrawals_in_epoch: Zero::zero(),
             total_storage_fee_deposit: new_deposit.storage_fee_deposit,
+            accepts_nominations: true,
+            tax_withdrawn_in_epoch: Zero::zero(),
         };
         Operators::<T>::insert(operator_id, operator);
         OperatorSigningKey::<T>::insert(signing_key, operator_id);
+        DomainEpochStakeDelta::<T>::mutate(domain_id, |delta| {
+            delta.deposits = delta.deposits.saturating_add(new_deposit.staking);
+        });
         // update stake summary to include new operator for next epoch
         domain_stake_summary.next_operators.insert(operator_id);
         // update pending transfers
@@ -579,6 +633,15 @@ pub(crate) fn do_nominate_operator<T: Config>(
             Error::OperatorNotRegistered
         );
 
+        // a deposit made while the operator is switching domains would be ambiguous about which
+        // domain stake summary it counts toward.
+        // NOTE: there is no extrinsic to switch domains at the moment, so `next_domain_id` always
+        // equals `current_domain_id` and the check is intentionally defensive.
+        ensure!(
+            operator.current_domain_id == operator.next_domain_id,
+            Error::PendingOperatorSwitch
+        );
+
         // If the this is the first staking request of this operator `note_pending_staking_operation` for it
         if operator.deposits_in_epoch.is_zero() && operator.withdrawals_in_epoch.is_zero() {
             note_pending_staking_operation::<T>(operator.current_domain_id)?;
@@ -605,6 +668,10 @@ pub(crate) fn do_nominate_operator<T: Config>(
             .checked_add(&new_deposit.storage_fee_deposit)
             .ok_or(Error::BalanceOverflow)?;
 
+        DomainEpochStakeDelta::<T>::mutate(operator.current_domain_id, |delta| {
+            delta.deposits = delta.deposits.saturating_add(new_deposit.staking);
+        });
+
         let current_domain_epoch = (
             operator.current_domain_id,
             domain_stake_summary.current_epoch_index,
@@ -626,7 +693,10 @@ pub(crate) fn do_nominate_operator<T: Config>(
         // - amount >= operator's minimum nominator stake amount.
         // - nominator count does not exceed max nominators.
         // - if first nomination, then increment the nominator count.
+        // - operator accepts new nominators.
         if !nominating {
+            ensure!(operator.accepts_nominations, Error::NominationsClosed);
+
             ensure!(
                 total_deposit >= operator.minimum_nominator_stake,
                 Error::MinimumNominatorStake
@@ -661,6 +731,30 @@ pub(crate) fn hold_deposit<T: Config>(
     Ok(())
 }
 
+/// Sets whether the operator accepts new nominators, existing nominators are not affected.
+pub(crate) fn do_set_accepts_nominations<T: Config>(
+    operator_owner: T::AccountId,
+    operator_id: OperatorId,
+    accepts_nominations: bool,
+) -> Result<(), Error> {
+    ensure!(
+        OperatorIdOwner::<T>::get(operator_id) == Some(operator_owner),
+        Error::NotOperatorOwner
+    );
+
+    Operators::<T>::try_mutate(operator_id, |maybe_operator| {
+        let operator = maybe_operator.as_mut().ok_or(Error::UnknownOperator)?;
+
+        ensure!(
+            *operator.status::<T>(operator_id) == OperatorStatus::Registered,
+            Error::OperatorNotRegistered
+        );
+
+        operator.accepts_nominations = accepts_nominations;
+        Ok(())
+    })
+}
+
 pub(crate) fn do_deregister_operator<T: Config>(
     operator_owner: T::AccountId,
     operator_id: OperatorId,
@@ -678,6 +772,14 @@ pub(crate) fn do_deregister_operator<T: Config>(
             Error::OperatorNotRegistered
         );
 
+        // the operator would be removed from the next operators of the domain it is leaving while
+        // still being expected to join the other one.
+        // NOTE: the check is intentionally defensive, same as in `do_nominate_operator`.
+        ensure!(
+            operator.current_domain_id == operator.next_domain_id,
+            Error::PendingOperatorSwitch
+        );
+
         DomainStakingSummary::<T>::try_mutate(
             operator.current_domain_id,
             |maybe_domain_stake_summary| {
@@ -706,10 +808,16 @@ pub(crate) fn do_deregister_operator<T: Config>(
     })
 }
 
+/// Withdraws the given shares of the nominator from the operator pool.
+///
+/// If the remaining stake of a nominator falls below the minimum nominator stake, all of the shares
+/// are withdrawn instead, unless `strict` is set, in which case the withdrawal is rejected so the
+/// nominator can choose to withdraw less or withdraw everything explicitly.
 pub(crate) fn do_withdraw_stake<T: Config>(
     operator_id: OperatorId,
     nominator_id: NominatorId<T>,
     shares_withdrew: T::Share,
+    strict: bool,
 ) -> Result<(), Error> {
     Operators::<T>::try_mutate(operator_id, |maybe_operator| {
         let operator = maybe_operator.as_mut().ok_or(Error::UnknownOperator)?;
@@ -770,23 +878,8 @@ pub(crate) fn do_withdraw_stake<T: Config>(
 
                     (remaining_shares, shares_withdrew)
                 } else {
-                    // total stake including any reward within this epoch.
-                    // used to calculate the share price at this instant.
-                    let total_stake = domain_stake_summary
-                        .current_epoch_rewards
-                        .get(&operator_id)
-                        .and_then(|rewards| {
-                            let operator_tax = operator.nomination_tax.mul_floor(*rewards);
-                            operator
-                                .current_total_stake
-                                .checked_add(rewards)?
-                                // deduct operator tax
-                                .checked_sub(&operator_tax)
-                        })
-                        .unwrap_or(operator.current_total_stake);
-
                     let share_price =
-                        SharePrice::new::<T>(operator.current_total_shares, total_stake);
+                        current_share_price::<T>(operator_id, operator, &domain_stake_summary);
 
                     let remaining_storage_fee =
                         Perbill::from_rational(remaining_shares, known_shares)
@@ -805,6 +898,7 @@ pub(crate) fn do_withdraw_stake<T: Config>(
 
                     // if not an owner, if remaining balance < MinNominatorStake, then withdraw all shares.
                     if !is_operator_owner && remaining_stake.lt(&operator.minimum_nominator_stake) {
+                        ensure!(!strict, Error::WouldFallBelowMinimum);
                         (T::Share::zero(), known_shares)
                     } else {
                         (remaining_shares, shares_withdrew)
@@ -847,6 +941,15 @@ pub(crate) fn do_withdraw_stake<T: Config>(
                 .checked_add(&shares_withdrew)
                 .ok_or(Error::ShareOverflow)?;
 
+            let stake_withdrew =
+                current_share_price::<T>(operator_id, operator, &domain_stake_summary)
+                    .shares_to_stake::<T>(shares_withdrew);
+            DomainEpochStakeDelta::<T>::mutate(operator.current_domain_id, |delta| {
+                delta.withdrawals = delta.withdrawals.saturating_add(stake_withdrew);
+            });
+
+            // withdrawn shares are deducted right away, so the minimum stake checks of any later
+            // withdrawal already account for the withdrawals pending in this epoch
             deposit.known.shares = remaining_shares;
             if remaining_shares.is_zero() {
                 if let Some(pending_deposit) = deposit.pending {
@@ -906,6 +1009,372 @@ pub(crate) fn do_withdraw_stake<T: Config>(
     })
 }
 
+/// Returns the share price of the operator at this instant, including any reward within the
+/// current epoch.
+fn current_share_price<T: Config>(
+    operator_id: OperatorId,
+    operator: &Operator<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+    domain_stake_summary: &StakingSummary<OperatorId, BalanceOf<T>>,
+) -> SharePrice {
+    let total_stake = current_pool_stake::<T>(operator_id, operator, domain_stake_summary);
+
+    SharePrice::new::<T>(operator.current_total_shares, total_stake)
+}
+
+/// Returns the stake of the operator pool at this instant, including any reward within the
+/// current epoch after the operator tax.
+fn current_pool_stake<T: Config>(
+    operator_id: OperatorId,
+    operator: &Operator<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+    domain_stake_summary: &StakingSummary<OperatorId, BalanceOf<T>>,
+) -> BalanceOf<T> {
+    domain_stake_summary
+        .current_epoch_rewards
+        .get(&operator_id)
+        .and_then(|rewards| {
+            let operator_tax = pending_operator_tax::<T>(operator, *rewards);
+            operator
+                .current_total_stake
+                .checked_add(rewards)?
+                // deduct operator tax
+                .checked_sub(&operator_tax)
+        })
+        .unwrap_or(operator.current_total_stake)
+}
+
+/// Transfers `shares` of the nominator `from` to the nominator `to` under the same operator,
+/// along with the proportional storage fee deposit and the staked fund backing the shares.
+pub(crate) fn do_transfer_nomination<T: Config>(
+    operator_id: OperatorId,
+    from: NominatorId<T>,
+    to: NominatorId<T>,
+    shares: T::Share,
+) -> Result<(), Error> {
+    ensure!(from != to, Error::TransferToSelf);
+    ensure!(!shares.is_zero(), Error::ZeroTransferShares);
+
+    let operator = Operators::<T>::get(operator_id).ok_or(Error::UnknownOperator)?;
+    ensure!(
+        *operator.status::<T>(operator_id) == OperatorStatus::Registered,
+        Error::OperatorNotRegistered
+    );
+
+    let domain_stake_summary = DomainStakingSummary::<T>::get(operator.current_domain_id)
+        .ok_or(Error::DomainNotInitialized)?;
+
+    // calculate shares and withdrawals for any previous epoch of both nominators
+    for nominator_id in [&from, &to] {
+        Deposits::<T>::try_mutate(operator_id, nominator_id, |maybe_deposit| {
+            if let Some(deposit) = maybe_deposit {
+                do_convert_previous_epoch_deposits::<T>(operator_id, deposit)?;
+            }
+            Ok(())
+        })?;
+        Withdrawals::<T>::try_mutate(operator_id, nominator_id, |maybe_withdrawal| {
+            if let Some(withdrawal) = maybe_withdrawal {
+                do_convert_previous_epoch_withdrawal::<T>(operator_id, withdrawal)?;
+            }
+            Ok(())
+        })?;
+    }
+
+    let mut from_deposit = Deposits::<T>::get(operator_id, &from).ok_or(Error::UnknownNominator)?;
+    let known_shares = from_deposit.known.shares;
+    let remaining_shares = known_shares
+        .checked_sub(&shares)
+        .ok_or(Error::InsufficientShares)?;
+
+    // The staked fund of a withdrawal in shares is still on hold and can't be told apart from
+    // the fund backing the known shares until the withdrawal is converted at epoch transition.
+    let maybe_from_withdrawal = Withdrawals::<T>::get(operator_id, &from);
+    ensure!(
+        maybe_from_withdrawal
+            .as_ref()
+            .map(|withdrawal| withdrawal.withdrawal_in_shares.is_none())
+            .unwrap_or(true),
+        Error::PendingWithdrawal
+    );
+
+    let share_price = current_share_price::<T>(operator_id, &operator, &domain_stake_summary);
+    let transfer_ratio = Perbill::from_rational(shares, known_shares);
+    let storage_fee_to_transfer = transfer_ratio.mul_floor(from_deposit.known.storage_fee_deposit);
+    let remaining_storage_fee = from_deposit
+        .known
+        .storage_fee_deposit
+        .checked_sub(&storage_fee_to_transfer)
+        .ok_or(Error::BalanceUnderflow)?;
+
+    let operator_owner = OperatorIdOwner::<T>::get(operator_id).ok_or(Error::UnknownOperator)?;
+    let remaining_stake = share_price
+        .shares_to_stake::<T>(remaining_shares)
+        .checked_add(&remaining_storage_fee)
+        .ok_or(Error::BalanceOverflow)?;
+    if from == operator_owner {
+        // the operator owner must keep the minimum operator stake bonded
+        ensure!(
+            remaining_stake >= T::MinOperatorStake::get(),
+            Error::MinimumOperatorStake
+        );
+    } else if remaining_shares.is_zero() {
+        // if there is a pending deposit, then ensure it is atleast minimum nominator stake
+        if let Some(pending_deposit) = from_deposit.pending {
+            ensure!(
+                pending_deposit.total()? >= operator.minimum_nominator_stake,
+                Error::MinimumNominatorStake
+            );
+        }
+    } else {
+        ensure!(
+            remaining_stake >= operator.minimum_nominator_stake,
+            Error::MinimumNominatorStake
+        );
+    }
+
+    let mut to_deposit = Deposits::<T>::get(operator_id, &to).unwrap_or_default();
+    let is_new_nominator = to_deposit.known.shares.is_zero() && to_deposit.pending.is_none();
+    // new nominators can't join through a transfer while the operator doesn't accept them
+    ensure!(
+        to == operator_owner || !is_new_nominator || operator.accepts_nominations,
+        Error::NominationsClosed
+    );
+    to_deposit.known.shares = to_deposit
+        .known
+        .shares
+        .checked_add(&shares)
+        .ok_or(Error::ShareOverflow)?;
+    to_deposit.known.storage_fee_deposit = to_deposit
+        .known
+        .storage_fee_deposit
+        .checked_add(&storage_fee_to_transfer)
+        .ok_or(Error::BalanceOverflow)?;
+    let to_stake = share_price
+        .shares_to_stake::<T>(to_deposit.known.shares)
+        .checked_add(&to_deposit.known.storage_fee_deposit)
+        .ok_or(Error::BalanceOverflow)?;
+    ensure!(
+        to_stake >= operator.minimum_nominator_stake,
+        Error::MinimumNominatorStake
+    );
+
+    // move the part of the staked hold that is backing the transferred shares, excluding the
+    // fund held for the pending deposit and the withdrawals
+    let staked_hold_id = T::HoldIdentifier::staking_staked(operator_id);
+    let known_staked_amount = T::Currency::balance_on_hold(&staked_hold_id, &from)
+        .saturating_sub(
+            from_deposit
+                .pending
+                .map(|pending_deposit| pending_deposit.amount)
+                .unwrap_or_default(),
+        )
+        .saturating_sub(
+            maybe_from_withdrawal
+                .map(|withdrawal| withdrawal.total_withdrawal_amount)
+                .unwrap_or_default(),
+        );
+    T::Currency::transfer_on_hold(
+        &staked_hold_id,
+        &from,
+        &to,
+        transfer_ratio.mul_floor(known_staked_amount),
+        Precision::Exact,
+        Restriction::OnHold,
+        Fortitude::Polite,
+    )
+    .map_err(|_| Error::BalanceFreeze)?;
+
+    from_deposit.known.shares = remaining_shares;
+    from_deposit.known.storage_fee_deposit = remaining_storage_fee;
+
+    if from != operator_owner && remaining_shares.is_zero() && from_deposit.pending.is_none() {
+        NominatorCount::<T>::mutate(operator_id, |count| {
+            *count -= 1;
+        });
+    }
+    if to != operator_owner && is_new_nominator {
+        NominatorCount::<T>::mutate(operator_id, |count| {
+            *count += 1;
+        });
+    }
+
+    Deposits::<T>::insert(operator_id, &from, from_deposit);
+    Deposits::<T>::insert(operator_id, &to, to_deposit);
+
+    Ok(())
+}
+
+/// Pays out the nominator's share of the operator's current epoch rewards accrued since their
+/// last claim into their spendable balance, instead of compounding it into the stake.
+///
+/// Shares worth the claimed amount at the current share price are burned, so the share price and
+/// thus the stake of other nominators is not affected. Returns the claimed amount.
+pub(crate) fn do_claim_rewards<T: Config>(
+    operator_id: OperatorId,
+    nominator_id: NominatorId<T>,
+) -> Result<BalanceOf<T>, Error> {
+    Operators::<T>::try_mutate(operator_id, |maybe_operator| {
+        let operator = maybe_operator.as_mut().ok_or(Error::UnknownOperator)?;
+        ensure!(
+            *operator.status::<T>(operator_id) == OperatorStatus::Registered,
+            Error::OperatorNotRegistered
+        );
+
+        let domain_stake_summary = DomainStakingSummary::<T>::get(operator.current_domain_id)
+            .ok_or(Error::DomainNotInitialized)?;
+        let domain_current_epoch = (
+            operator.current_domain_id,
+            domain_stake_summary.current_epoch_index,
+        )
+            .into();
+
+        Deposits::<T>::try_mutate(operator_id, nominator_id.clone(), |maybe_deposit| {
+            let deposit = maybe_deposit.as_mut().ok_or(Error::UnknownNominator)?;
+            do_convert_previous_epoch_deposits::<T>(operator_id, deposit)?;
+
+            let shares = deposit.known.shares;
+            if shares.is_zero() || operator.current_total_shares.is_zero() {
+                return Ok(Zero::zero());
+            }
+
+            // stake of the pool including the rewards of the current epoch after the operator tax
+            let total_stake = current_pool_stake::<T>(operator_id, operator, &domain_stake_summary);
+            let total_shares = operator.current_total_shares;
+
+            // The pool at the start of the epoch, recorded before the first claim of the epoch
+            // changes it. Claims burn shares at the price including the rewards, so the pool
+            // after a claim is not the baseline of the nominators who haven't claimed yet.
+            let epoch_baseline = match OperatorRewardBaselines::<T>::get(operator_id) {
+                Some(baseline) if baseline.domain_epoch == domain_current_epoch => baseline,
+                _ => {
+                    let baseline = RewardCheckpoint {
+                        domain_epoch: domain_current_epoch,
+                        total_stake: operator.current_total_stake,
+                        total_shares,
+                    };
+                    OperatorRewardBaselines::<T>::insert(operator_id, baseline);
+                    baseline
+                }
+            };
+
+            // rewards accrued before the last claim in this epoch were paid out already
+            let checkpoint =
+                match NominatorRewardCheckpoints::<T>::get(operator_id, nominator_id.clone()) {
+                    Some(checkpoint) if checkpoint.domain_epoch == domain_current_epoch => {
+                        checkpoint
+                    }
+                    _ => epoch_baseline,
+                };
+
+            let stake_of = |pool_stake: BalanceOf<T>, pool_shares: T::Share| {
+                Perquintill::from_rational(shares, pool_shares).mul_floor(pool_stake)
+            };
+            let rewards = stake_of(total_stake, total_shares)
+                .saturating_sub(stake_of(checkpoint.total_stake, checkpoint.total_shares));
+            if rewards.is_zero() {
+                return Ok(rewards);
+            }
+
+            // burn the shares worth the claimed rewards, rounding up in favor of other nominators
+            let burned_shares =
+                Perquintill::from_rational(rewards, total_stake).mul_ceil(total_shares);
+            deposit.known.shares = shares
+                .checked_sub(&burned_shares)
+                .ok_or(Error::ShareUnderflow)?;
+            operator.current_total_shares = total_shares
+                .checked_sub(&burned_shares)
+                .ok_or(Error::ShareUnderflow)?;
+            operator.current_total_stake = operator
+                .current_total_stake
+                .checked_sub(&rewards)
+                .ok_or(Error::BalanceUnderflow)?;
+
+            NominatorRewardCheckpoints::<T>::insert(
+                operator_id,
+                nominator_id.clone(),
+                RewardCheckpoint {
+                    domain_epoch: domain_current_epoch,
+                    total_stake: total_stake
+                        .checked_sub(&rewards)
+                        .ok_or(Error::BalanceUnderflow)?,
+                    total_shares: operator.current_total_shares,
+                },
+            );
+
+            mint_funds::<T>(&nominator_id, rewards)?;
+
+            Ok(rewards)
+        })
+    })
+}
+
+/// Returns the operator tax on the operator's current epoch `rewards` that is not withdrawn by the
+/// operator owner yet.
+///
+/// Withdrawn tax is already deducted from `rewards`, so the tax is calculated on the rewards before
+/// the withdrawals and the withdrawn tax is subtracted, such that the rewards are taxed only once.
+pub(crate) fn pending_operator_tax<T: Config>(
+    operator: &Operator<BalanceOf<T>, T::Share, DomainBlockNumberFor<T>>,
+    rewards: BalanceOf<T>,
+) -> BalanceOf<T> {
+    operator
+        .nomination_tax
+        .mul_floor(rewards.saturating_add(operator.tax_withdrawn_in_epoch))
+        .saturating_sub(operator.tax_withdrawn_in_epoch)
+}
+
+/// Pays out the operator tax on the operator's current epoch rewards accrued since the last
+/// withdrawal into the spendable balance of the operator owner, instead of staking it at the
+/// epoch transition.
+///
+/// Only the tax is deducted from the current epoch rewards, the stake and the shares of the
+/// operator pool are not affected. Returns the withdrawn amount.
+pub(crate) fn do_withdraw_operator_tax<T: Config>(
+    operator_owner: T::AccountId,
+    operator_id: OperatorId,
+) -> Result<BalanceOf<T>, Error> {
+    ensure!(
+        OperatorIdOwner::<T>::get(operator_id) == Some(operator_owner.clone()),
+        Error::NotOperatorOwner
+    );
+
+    Operators::<T>::try_mutate(operator_id, |maybe_operator| {
+        let operator = maybe_operator.as_mut().ok_or(Error::UnknownOperator)?;
+        ensure!(
+            !matches!(
+                operator.status::<T>(operator_id),
+                OperatorStatus::Slashed | OperatorStatus::PendingSlash
+            ),
+            Error::OperatorFrozen
+        );
+
+        DomainStakingSummary::<T>::try_mutate(operator.current_domain_id, |maybe_stake_summary| {
+            let stake_summary = maybe_stake_summary
+                .as_mut()
+                .ok_or(Error::DomainNotInitialized)?;
+
+            let Some(rewards) = stake_summary.current_epoch_rewards.get_mut(&operator_id) else {
+                return Ok(Zero::zero());
+            };
+
+            let operator_tax = pending_operator_tax::<T>(operator, *rewards);
+            if operator_tax.is_zero() {
+                return Ok(operator_tax);
+            }
+
+            *rewards = rewards
+                .checked_sub(&operator_tax)
+                .ok_or(Error::BalanceUnderflow)?;
+            operator.tax_withdrawn_in_epoch = operator
+                .tax_withdrawn_in_epoch
+                .checked_add(&operator_tax)
+                .ok_or(Error::BalanceOverflow)?;
+
+            mint_funds::<T>(&operator_owner, operator_tax)?;
+
+            Ok(operator_tax)
+        })
+    })
+}
+
 /// Unlocks any withdraws that are ready to be unlocked.
 pub(crate) fn do_unlock_funds<T: Config>(
     operator_id: OperatorId,
@@ -980,12 +1449,13 @@ pub(crate) fn do_unlock_funds<T: Config>(
         if withdrawal.withdrawals.is_empty() && withdrawal.withdrawal_in_shares.is_none() {
             *maybe_withdrawal = None;
             // if there is no deposit or pending deposits, then clean up the deposit state as well
-            Deposits::<T>::mutate_exists(operator_id, nominator_id, |maybe_deposit| {
+            Deposits::<T>::mutate_exists(operator_id, nominator_id.clone(), |maybe_deposit| {
                 if let Some(deposit) = maybe_deposit
                     && deposit.known.shares.is_zero()
                     && deposit.pending.is_none()
                 {
-                    *maybe_deposit = None
+                    *maybe_deposit = None;
+                    NominatorRewardCheckpoints::<T>::remove(operator_id, nominator_id);
                 }
             });
         }
@@ -1172,11 +1642,20 @@ pub(crate) fn do_cleanup_operator<T: Config>(
     bundle_storage_fund::transfer_all_to_treasury::<T>(operator_id)
         .map_err(Error::BundleStorageFund)?;
 
-    // transfer any remaining amount to treasury
+    // transfer any remaining amount, including the stake of the unowned share dust, to treasury
     mint_into_treasury::<T>(total_stake).ok_or(Error::MintBalance)?;
 
     // remove OperatorOwner Details
-    OperatorIdOwner::<T>::remove(operator_id);
+    if let Some(operator_owner) = OperatorIdOwner::<T>::take(operator_id) {
+        OperatorIdsByOwner::<T>::mutate_exists(operator_owner, |maybe_operator_ids| {
+            if let Some(operator_ids) = maybe_operator_ids {
+                operator_ids.remove(&operator_id);
+                if operator_ids.is_empty() {
+                    *maybe_operator_ids = None;
+                }
+            }
+        });
+    }
 
     // remove operator signing key
     OperatorSigningKey::<T>::remove(operator_signing_key);
@@ -1184,12 +1663,41 @@ pub(crate) fn do_cleanup_operator<T: Config>(
     // remove operator epoch share prices
     let _ = OperatorEpochSharePrice::<T>::clear_prefix(operator_id, u32::MAX, None);
 
+    // remove operator reward baseline
+    OperatorRewardBaselines::<T>::remove(operator_id);
+
+    // remove operator epoch stake history
+    let _ = OperatorEpochStake::<T>::clear_prefix(operator_id, u32::MAX, None);
+
     // remove nominator count for this operator.
     NominatorCount::<T>::remove(operator_id);
 
     Ok(())
 }
 
+/// Returns the share of the operator's stake in the current total stake of its domain, which is
+/// the expected fraction of bundle elections won by the operator in the current epoch.
+///
+/// This is only meant for modelling election frequency (e.g. dashboards and simulations) and has no
+/// effect on consensus. Operators that are not part of the current epoch get zero.
+pub fn expected_election_weight<T: Config>(operator_id: OperatorId) -> Perbill {
+    let Some(operator) = Operators::<T>::get(operator_id) else {
+        return Perbill::zero();
+    };
+    let Some(stake_summary) = DomainStakingSummary::<T>::get(operator.current_domain_id) else {
+        return Perbill::zero();
+    };
+    let Some(operator_stake) = stake_summary.current_operators.get(&operator_id) else {
+        return Perbill::zero();
+    };
+
+    if stake_summary.current_total_stake.is_zero() {
+        return Perbill::zero();
+    }
+
+    Perbill::from_rational(*operator_stake, stake_summary.current_total_stake)
+}
+
 /// Distribute the reward to the operators equally and drop any dust to treasury.
 pub(crate) fn do_reward_operators<T: Config>(
     domain_id: DomainId,
@@ -1249,6 +1757,10 @@ pub(crate) fn do_reward_operators<T: Config>(
                 .ok_or(Error::BalanceOverflow)?;
         }
 
+        DomainEpochStakeDelta::<T>::mutate(domain_id, |delta| {
+            delta.rewards = delta.rewards.saturating_add(allocated_rewards);
+        });
+
         // mint remaining funds to treasury
         mint_into_treasury::<T>(
             rewards
@@ -1312,22 +1824,26 @@ pub(crate) mod tests {
     use crate::domain_registry::{DomainConfig, DomainObject};
     use crate::pallet::{
         Config, Deposits, DomainRegistry, DomainStakingSummary,
-        LatestConfirmedDomainExecutionReceipt, NextOperatorId, NominatorCount, OperatorIdOwner,
-        Operators, PendingSlashes, Withdrawals,
+        LatestConfirmedDomainExecutionReceipt, NextOperatorId, NominatorCount,
+        NominatorRewardCheckpoints, OperatorEpochStake, OperatorIdOwner, Operators, PendingSlashes,
+        Withdrawals,
     };
     use crate::staking::{
-        do_convert_previous_epoch_withdrawal, do_mark_operators_as_slashed, do_nominate_operator,
-        do_reward_operators, do_unlock_funds, do_withdraw_stake, Error as StakingError, Operator,
-        OperatorConfig, OperatorSigningKeyProofOfOwnershipData, OperatorStatus, StakingSummary,
+        do_claim_rewards, do_convert_previous_epoch_deposits, do_convert_previous_epoch_withdrawal,
+        do_mark_operators_as_slashed, do_nominate_operator, do_reward_operators, do_unlock_funds,
+        do_withdraw_operator_tax, do_withdraw_stake, expected_election_weight, Deposit,
+        DomainEpoch, EpochStakeDelta, Error as StakingError, KnownDeposit, Operator,
+        OperatorConfig, OperatorSigningKeyProofOfOwnershipData, OperatorStatus, PendingDeposit,
+        StakingSummary,
     };
     use crate::staking_epoch::{do_finalize_domain_current_epoch, do_slash_operator};
     use crate::tests::{new_test_ext, ExistentialDeposit, RuntimeOrigin, Test};
     use crate::{
-        bundle_storage_fund, BalanceOf, Error, ExecutionReceiptOf, NominatorId, SlashedReason,
-        MAX_NOMINATORS_TO_SLASH,
+        bundle_storage_fund, BalanceOf, Error, ExecutionReceiptOf, HoldIdentifier, NominatorId,
+        SlashedReason, MAX_NOMINATORS_TO_SLASH, OPERATOR_STAKE_HISTORY_DEPTH,
     };
     use codec::Encode;
-    use frame_support::traits::fungible::Mutate;
+    use frame_support::traits::fungible::{InspectHold, Mutate};
     use frame_support::traits::Currency;
     use frame_support::weights::Weight;
     use frame_support::{assert_err, assert_ok};
@@ -1335,10 +1851,10 @@ pub(crate) mod tests {
     use sp_core::{sr25519, Pair, U256};
     use sp_domains::{
         BlockFees, DomainId, OperatorAllowList, OperatorId, OperatorPair, OperatorPublicKey,
-        OperatorSignature, Transfers,
+        OperatorSignature, OperatorStatusInfo, PendingWithdrawal, Transfers,
     };
     use sp_runtime::traits::Zero;
-    use sp_runtime::{PerThing, Perbill};
+    use sp_runtime::{PerThing, Perbill, Percent, Perquintill};
     use std::collections::{BTreeMap, BTreeSet};
     use std::vec;
     use subspace_runtime_primitives::SSC;
@@ -1547,6 +2063,8 @@ pub(crate) mod tests {
                     deposits_in_epoch: 0,
                     withdrawals_in_epoch: 0,
                     total_storage_fee_deposit: operator_storage_fee_deposit,
+                    accepts_nominations: true,
+                    tax_withdrawn_in_epoch: 0,
                 }
             );
 
@@ -1596,6 +2114,118 @@ pub(crate) mod tests {
         });
     }
 
+    #[test]
+    fn expected_election_weights() {
+        let domain_id = DomainId::new(0);
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let operator_ids = [1000 * SSC, 2000 * SSC, 3000 * SSC]
+                .into_iter()
+                .enumerate()
+                .map(|(index, operator_stake)| {
+                    let operator_account = index as u128 + 1;
+                    let pair = OperatorPair::from_seed(&U256::from(index as u32).into());
+                    let data = OperatorSigningKeyProofOfOwnershipData {
+                        operator_owner: operator_account,
+                    };
+                    let signature = pair.sign(&data.encode());
+                    let (operator_id, _) = register_operator(
+                        domain_id,
+                        operator_account,
+                        operator_stake + 1000 * SSC,
+                        operator_stake,
+                        SSC,
+                        pair.public(),
+                        signature,
+                        BTreeMap::new(),
+                    );
+                    operator_id
+                })
+                .collect::<Vec<_>>();
+
+            // operators are not elected until the next epoch
+            for operator_id in &operator_ids {
+                assert_eq!(
+                    expected_election_weight::<Test>(*operator_id),
+                    Perbill::zero()
+                );
+            }
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // weights are proportional to the stakes
+            let weights = operator_ids
+                .iter()
+                .map(|operator_id| expected_election_weight::<Test>(*operator_id))
+                .collect::<Vec<_>>();
+            assert!(weights[0] < weights[1] && weights[1] < weights[2]);
+            assert_eq!(weights[2], Perbill::from_percent(50));
+
+            // weights add up to 100% up to rounding
+            let total_parts = weights
+                .iter()
+                .map(|weight| u64::from(weight.deconstruct()))
+                .sum::<u64>();
+            let one_parts = u64::from(Perbill::one().deconstruct());
+            assert!(total_parts <= one_parts);
+            assert!(one_parts - total_parts < operator_ids.len() as u64);
+
+            // unknown operator has no weight
+            assert_eq!(
+                expected_election_weight::<Test>(operator_ids[2] + 1),
+                Perbill::zero()
+            );
+        });
+    }
+
+    #[test]
+    fn operators_of_owner() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let data = OperatorSigningKeyProofOfOwnershipData {
+                operator_owner: operator_account,
+            };
+            let signature = pair.sign(&data.encode());
+            let (operator_id, mut operator_config) = register_operator(
+                domain_id,
+                operator_account,
+                2500 * SSC,
+                1000 * SSC,
+                SSC,
+                pair.public(),
+                signature,
+                BTreeMap::new(),
+            );
+
+            let new_pair = OperatorPair::from_seed(&U256::from(1u32).into());
+            operator_config.signing_key = new_pair.public();
+            let signature = new_pair.sign(&data.encode());
+            assert_ok!(Domains::register_operator(
+                RuntimeOrigin::signed(operator_account),
+                domain_id,
+                1000 * SSC,
+                operator_config,
+                signature,
+            ));
+            let new_operator_id = operator_id + 1;
+            assert_eq!(
+                OperatorIdOwner::<Test>::get(new_operator_id),
+                Some(operator_account)
+            );
+
+            assert_eq!(
+                Domains::operators_of_owner(operator_account),
+                vec![operator_id, new_operator_id]
+            );
+            assert!(Domains::operators_of_owner(2).is_empty());
+        });
+    }
+
     #[test]
     fn nominate_operator() {
         let domain_id = DomainId::new(0);
@@ -1717,32 +2347,379 @@ pub(crate) mod tests {
     }
 
     #[test]
-    fn operator_deregistration() {
+    fn deregister_operator_switching_domain() {
         let domain_id = DomainId::new(0);
         let operator_account = 1;
-        let operator_stake = 200 * SSC;
-        let operator_free_balance = 250 * SSC;
         let pair = OperatorPair::from_seed(&U256::from(0u32).into());
         let data = OperatorSigningKeyProofOfOwnershipData {
             operator_owner: operator_account,
         };
         let signature = pair.sign(&data.encode());
+
         let mut ext = new_test_ext();
         ext.execute_with(|| {
             let (operator_id, _) = register_operator(
                 domain_id,
                 operator_account,
-                operator_free_balance,
-                operator_stake,
-                SSC,
+                1500 * SSC,
+                1000 * SSC,
+                10 * SSC,
                 pair.public(),
                 signature,
                 BTreeMap::new(),
             );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
 
-            let res =
-                Domains::deregister_operator(RuntimeOrigin::signed(operator_account), operator_id);
-            assert_ok!(res);
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().next_domain_id = DomainId::new(1);
+            });
+            assert_err!(
+                Domains::deregister_operator(RuntimeOrigin::signed(operator_account), operator_id),
+                Error::<Test>::Staking(StakingError::PendingOperatorSwitch)
+            );
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(
+                *operator.status::<Test>(operator_id),
+                OperatorStatus::Registered
+            );
+            assert!(DomainStakingSummary::<Test>::get(domain_id)
+                .unwrap()
+                .next_operators
+                .contains(&operator_id));
+
+            // deregistration is allowed again once the switch is complete
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().next_domain_id = domain_id;
+            });
+            assert_ok!(Domains::deregister_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+            ));
+            assert!(!DomainStakingSummary::<Test>::get(domain_id)
+                .unwrap()
+                .next_operators
+                .contains(&operator_id));
+        });
+    }
+
+    #[test]
+    fn staking_summary_operators_are_ordered_by_id() {
+        let domain_id = DomainId::new(0);
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            // register the operators out of numeric order of their ids
+            let mut operator_ids = vec![];
+            for (seed, next_operator_id) in [(0u32, 5), (1, 2), (2, 8), (3, 0)] {
+                let operator_account = u128::from(seed) + 1;
+                let pair = OperatorPair::from_seed(&U256::from(seed).into());
+                let data = OperatorSigningKeyProofOfOwnershipData {
+                    operator_owner: operator_account,
+                };
+                let signature = pair.sign(&data.encode());
+
+                NextOperatorId::<Test>::set(next_operator_id);
+                let (operator_id, _) = register_operator(
+                    domain_id,
+                    operator_account,
+                    1500 * SSC,
+                    1000 * SSC,
+                    10 * SSC,
+                    pair.public(),
+                    signature,
+                    BTreeMap::new(),
+                );
+                assert_eq!(operator_id, next_operator_id);
+                operator_ids.push(operator_id);
+            }
+
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert_eq!(
+                domain_stake_summary
+                    .next_operators
+                    .into_iter()
+                    .collect::<Vec<_>>(),
+                vec![0, 2, 5, 8]
+            );
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_ok!(Domains::deregister_operator(
+                RuntimeOrigin::signed(2),
+                operator_ids[1],
+            ));
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert_eq!(
+                domain_stake_summary
+                    .next_operators
+                    .into_iter()
+                    .collect::<Vec<_>>(),
+                vec![0, 5, 8]
+            );
+            assert_eq!(
+                domain_stake_summary
+                    .current_operators
+                    .into_keys()
+                    .collect::<Vec<_>>(),
+                vec![0, 5, 8]
+            );
+        });
+    }
+
+    #[test]
+    fn nominate_operator_switching_domain() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let nominator_account = 2;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                1500 * SSC,
+                1000 * SSC,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            Balances::set_balance(&nominator_account, 100 * SSC);
+
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().next_domain_id = DomainId::new(1);
+            });
+            assert_err!(
+                Domains::nominate_operator(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    50 * SSC,
+                ),
+                Error::<Test>::Staking(StakingError::PendingOperatorSwitch)
+            );
+
+            // nominations are accepted again once the switch is complete
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().next_domain_id = domain_id;
+            });
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                50 * SSC,
+            ));
+        });
+    }
+
+    #[test]
+    fn share_minting_never_exceeds_pool_shares() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+        let nominators = 2..10;
+
+        for seed in 1..=16u64 {
+            // xorshift, deterministic so that any failing sequence can be replayed
+            let mut state = seed;
+            let mut next_random = move |bound: u128| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u128 % bound
+            };
+
+            let mut ext = new_test_ext();
+            ext.execute_with(|| {
+                let (operator_id, _) = register_operator(
+                    domain_id,
+                    operator_account,
+                    1500 * SSC,
+                    1000 * SSC,
+                    SSC,
+                    pair.public(),
+                    signature.clone(),
+                    BTreeMap::new(),
+                );
+                do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+                let mut deposit_count = 1u128;
+                for nominator_id in nominators.clone() {
+                    Balances::set_balance(&nominator_id, 10_000 * SSC);
+                }
+                for _ in 0..5 {
+                    for nominator_id in nominators.clone() {
+                        if next_random(2) == 0 {
+                            continue;
+                        }
+                        let amount = 10 * SSC + next_random(100 * SSC);
+                        do_nominate_operator::<Test>(operator_id, nominator_id, amount).unwrap();
+                        deposit_count += 1;
+                    }
+                    let rewards = next_random(20 * SSC);
+                    do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), rewards)
+                        .unwrap();
+                    do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+                }
+
+                let shares: Vec<u128> = nominators
+                    .clone()
+                    .chain([operator_account])
+                    .filter_map(|nominator_id| Deposits::<Test>::get(operator_id, nominator_id))
+                    .map(|mut deposit| {
+                        do_convert_previous_epoch_deposits::<Test>(operator_id, &mut deposit)
+                            .unwrap();
+                        assert!(deposit.pending.is_none());
+                        deposit.known.shares
+                    })
+                    .collect();
+                let nominator_shares: u128 = shares.iter().sum();
+
+                let operator = Operators::<Test>::get(operator_id).unwrap();
+                let (total_stake, total_shares) =
+                    (operator.current_total_stake, operator.current_total_shares);
+                assert!(nominator_shares <= total_shares);
+                let dust_shares = total_shares - nominator_shares;
+                assert!(dust_shares <= deposit_count);
+
+                // the stake left for the treasury is the value of the dust plus the rounding of
+                // each nominator's value
+                let stake_of = |shares: u128| {
+                    Perquintill::from_rational(shares, total_shares).mul_floor(total_stake)
+                };
+                let nominator_stake: u128 = shares.iter().map(|shares| stake_of(*shares)).sum();
+                assert!(nominator_stake <= total_stake);
+                let max_dust_stake = Perquintill::from_rational(dust_shares, total_shares)
+                    .mul_ceil(total_stake)
+                    + (total_stake / 1_000_000_000_000_000_000 + 1) * (shares.len() as u128 + 1);
+                assert!(total_stake - nominator_stake <= max_dust_stake);
+            });
+        }
+    }
+
+    #[test]
+    fn nominations_closed() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let nominator_account = 2;
+        let new_nominator_account = 3;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                1500 * SSC,
+                1000 * SSC,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![(nominator_account, (150 * SSC, 100 * SSC))]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            Balances::set_balance(&new_nominator_account, 100 * SSC);
+
+            // only the operator owner can close nominations
+            assert_err!(
+                Domains::set_accepts_nominations(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    false
+                ),
+                Error::<Test>::Staking(StakingError::NotOperatorOwner)
+            );
+            assert_ok!(Domains::set_accepts_nominations(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                false
+            ));
+            assert!(
+                !Operators::<Test>::get(operator_id)
+                    .unwrap()
+                    .accepts_nominations
+            );
+
+            // new nominator is rejected
+            assert_err!(
+                Domains::nominate_operator(
+                    RuntimeOrigin::signed(new_nominator_account),
+                    operator_id,
+                    50 * SSC,
+                ),
+                Error::<Test>::Staking(StakingError::NominationsClosed)
+            );
+            assert!(Deposits::<Test>::get(operator_id, new_nominator_account).is_none());
+
+            // existing nominator and operator owner can still top up
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                40 * SSC,
+            ));
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                40 * SSC,
+            ));
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 1);
+
+            // reopened nominations accept new nominators again
+            assert_ok!(Domains::set_accepts_nominations(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                true
+            ));
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(new_nominator_account),
+                operator_id,
+                50 * SSC,
+            ));
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 2);
+        });
+    }
+
+    #[test]
+    fn operator_deregistration() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_stake = 200 * SSC;
+        let operator_free_balance = 250 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                SSC,
+                pair.public(),
+                signature,
+                BTreeMap::new(),
+            );
+
+            let res =
+                Domains::deregister_operator(RuntimeOrigin::signed(operator_account), operator_id);
+            assert_ok!(res);
 
             let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
             assert!(!domain_stake_summary.next_operators.contains(&operator_id));
@@ -1951,6 +2928,7 @@ pub(crate) mod tests {
                     RuntimeOrigin::signed(nominator_id),
                     operator_id,
                     withdraw_share_amount,
+                    false,
                 );
                 assert_eq!(
                     res,
@@ -2164,6 +3142,66 @@ pub(crate) mod tests {
         })
     }
 
+    #[test]
+    fn withdraw_stake_operator_multiple_withdraws_accounts_for_pending_withdrawal() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 0;
+        let operator_free_balance = 200 * SSC;
+        let operator_stake = 150 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_stake,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // Each withdrawal alone leaves the owner above `MinOperatorStake`, but together
+            // they would not, so the second one must be rejected.
+            let withdraw_share_amount = STORAGE_FEE_RESERVE.left_from_one().mul_ceil(30 * SSC);
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                withdraw_share_amount,
+                false,
+            ));
+            let deposit = Deposits::<Test>::get(operator_id, operator_account).unwrap();
+            let withdrawal = Withdrawals::<Test>::get(operator_id, operator_account).unwrap();
+
+            assert_err!(
+                Domains::withdraw_stake(
+                    RuntimeOrigin::signed(operator_account),
+                    operator_id,
+                    withdraw_share_amount,
+                    false,
+                ),
+                Error::<Test>::Staking(StakingError::MinimumOperatorStake)
+            );
+
+            // the rejected request must not change the pending withdrawal or the deposit
+            assert_eq!(
+                Deposits::<Test>::get(operator_id, operator_account).unwrap(),
+                deposit
+            );
+            assert_eq!(
+                Withdrawals::<Test>::get(operator_id, operator_account).unwrap(),
+                withdrawal
+            );
+        });
+    }
+
     #[test]
     fn withdraw_stake_nominator_below_minimum_with_rewards() {
         withdraw_stake(WithdrawParams {
@@ -2238,42 +3276,127 @@ pub(crate) mod tests {
     }
 
     #[test]
-    fn withdraw_stake_nominator_below_minimum_no_reward_multiple_rewards() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![(25 * SSC, Ok(())), (20 * SSC, Ok(()))],
-            maybe_deposit: None,
-            expected_withdraw: Some((50 * SSC, true)),
-            expected_nominator_count_reduced_by: 1,
-            storage_fund_change: (true, 0),
-        })
-    }
-
-    #[test]
-    fn withdraw_stake_nominator_below_minimum_no_reward_multiple_rewards_with_errors() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![
-                (25 * SSC, Ok(())),
-                (20 * SSC, Ok(())),
-                (20 * SSC, Err(StakingError::InsufficientShares)),
-            ],
-            maybe_deposit: None,
-            expected_withdraw: Some((50 * SSC, true)),
-            expected_nominator_count_reduced_by: 1,
-            storage_fund_change: (true, 0),
-        })
-    }
+    fn withdraw_stake_nominator_below_minimum_strict() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let nominator_account = 2;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
 
-    #[test]
-    fn withdraw_stake_nominator_above_minimum() {
-        withdraw_stake(WithdrawParams {
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                1500 * SSC,
+                1000 * SSC,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![(nominator_account, (100 * SSC, 50 * SSC))]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            let deposit = Deposits::<Test>::get(operator_id, nominator_account).unwrap();
+            let withdraw_share_amount = STORAGE_FEE_RESERVE.left_from_one().mul_ceil(45 * SSC);
+
+            // strict withdrawal is rejected instead of withdrawing all the shares
+            assert_err!(
+                Domains::withdraw_stake(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    withdraw_share_amount,
+                    true,
+                ),
+                Error::<Test>::Staking(StakingError::WouldFallBelowMinimum)
+            );
+            assert_eq!(
+                Deposits::<Test>::get(operator_id, nominator_account).unwrap(),
+                deposit
+            );
+            assert!(Withdrawals::<Test>::get(operator_id, nominator_account).is_none());
+
+            // strict withdrawal that keeps the minimum stake is allowed
+            let withdraw_share_amount_above_minimum =
+                STORAGE_FEE_RESERVE.left_from_one().mul_ceil(10 * SSC);
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                withdraw_share_amount_above_minimum,
+                true,
+            ));
+            let remaining_shares = deposit.known.shares - withdraw_share_amount_above_minimum;
+            assert_eq!(
+                Deposits::<Test>::get(operator_id, nominator_account)
+                    .unwrap()
+                    .known
+                    .shares,
+                remaining_shares
+            );
+
+            // lenient withdrawal of the same amount withdraws all the shares
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                withdraw_share_amount - withdraw_share_amount_above_minimum,
+                false,
+            ));
+            assert!(Deposits::<Test>::get(operator_id, nominator_account)
+                .unwrap()
+                .known
+                .shares
+                .is_zero());
+            assert_eq!(
+                Withdrawals::<Test>::get(operator_id, nominator_account)
+                    .unwrap()
+                    .withdrawal_in_shares
+                    .unwrap()
+                    .shares,
+                deposit.known.shares
+            );
+        });
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_below_minimum_no_reward_multiple_rewards() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![(25 * SSC, Ok(())), (20 * SSC, Ok(()))],
+            maybe_deposit: None,
+            expected_withdraw: Some((50 * SSC, true)),
+            expected_nominator_count_reduced_by: 1,
+            storage_fund_change: (true, 0),
+        })
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_below_minimum_no_reward_multiple_rewards_with_errors() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![
+                (25 * SSC, Ok(())),
+                (20 * SSC, Ok(())),
+                (20 * SSC, Err(StakingError::InsufficientShares)),
+            ],
+            maybe_deposit: None,
+            expected_withdraw: Some((50 * SSC, true)),
+            expected_nominator_count_reduced_by: 1,
+            storage_fund_change: (true, 0),
+        })
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_above_minimum() {
+        withdraw_stake(WithdrawParams {
             minimum_nominator_stake: 10 * SSC,
             nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
             operator_reward: 20 * SSC,
@@ -2411,105 +3534,857 @@ pub(crate) mod tests {
     }
 
     #[test]
-    fn withdraw_stake_nominator_zero_amount() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![(0, Err(StakingError::ZeroWithdrawShares))],
-            maybe_deposit: None,
-            expected_withdraw: None,
-            expected_nominator_count_reduced_by: 0,
-            storage_fund_change: (true, 0),
-        })
-    }
+    fn withdraw_stake_nominator_zero_amount() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![(0, Err(StakingError::ZeroWithdrawShares))],
+            maybe_deposit: None,
+            expected_withdraw: None,
+            expected_nominator_count_reduced_by: 0,
+            storage_fund_change: (true, 0),
+        })
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_all_with_storage_fee_profit() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![(50 * SSC, Ok(()))],
+            maybe_deposit: None,
+            // The storage fund increased 50% (i.e. 21 * SSC) thus the nominator make 50%
+            // storage fee profit i.e. 5 * SSC with rounding dust deducted
+            storage_fund_change: (true, 21),
+            expected_withdraw: Some((54999999994000000000, true)),
+            expected_nominator_count_reduced_by: 1,
+        })
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_all_with_storage_fee_loss() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![(50 * SSC, Ok(()))],
+            maybe_deposit: None,
+            // The storage fund decreased 50% (i.e. 21 * SSC) thus the nominator loss 50%
+            // storage fee deposit i.e. 5 * SSC with rounding dust deducted
+            storage_fund_change: (false, 21),
+            expected_withdraw: Some((44999999998000000000, true)),
+            expected_nominator_count_reduced_by: 1,
+        })
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_all_with_storage_fee_loss_all() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![(50 * SSC, Ok(()))],
+            maybe_deposit: None,
+            // The storage fund decreased 100% (i.e. 42 * SSC) thus the nominator loss 100%
+            // storage fee deposit i.e. 10 * SSC
+            storage_fund_change: (false, 42),
+            expected_withdraw: Some((40 * SSC, true)),
+            expected_nominator_count_reduced_by: 1,
+        })
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_multiple_withdraws_with_storage_fee_profit() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![(5 * SSC, Ok(())), (10 * SSC, Ok(())), (15 * SSC, Ok(()))],
+            maybe_deposit: None,
+            // The storage fund increased 50% (i.e. 21 * SSC) thus the nominator make 50%
+            // storage fee profit i.e. 5 * SSC with rounding dust deducted, withdraw 60% of
+            // the stake and the storage fee profit
+            storage_fund_change: (true, 21),
+            expected_withdraw: Some((30 * SSC + 2999999855527204374, false)),
+            expected_nominator_count_reduced_by: 0,
+        })
+    }
+
+    #[test]
+    fn withdraw_stake_nominator_multiple_withdraws_with_storage_fee_loss() {
+        withdraw_stake(WithdrawParams {
+            minimum_nominator_stake: 10 * SSC,
+            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
+            operator_reward: Zero::zero(),
+            nominator_id: 1,
+            withdraws: vec![(5 * SSC, Ok(())), (5 * SSC, Ok(())), (10 * SSC, Ok(()))],
+            maybe_deposit: None,
+            // The storage fund increased 50% (i.e. 21 * SSC) thus the nominator loss 50%
+            // storage fee i.e. 5 * SSC with rounding dust deducted, withdraw 40% of
+            // the stake and 40% of the storage fee loss are deducted
+            storage_fund_change: (false, 21),
+            expected_withdraw: Some((20 * SSC - 2 * SSC - 33331097576, false)),
+            expected_nominator_count_reduced_by: 0,
+        })
+    }
+
+    #[test]
+    fn transfer_nomination() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 1500 * SSC;
+        let operator_total_stake = 1000 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let nominator_account = 2;
+        let nominator_stake = 80 * SSC;
+        let nominator_storage_fee_deposit = 20 * SSC;
+        let new_nominator_account = 3;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_total_stake,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![
+                    (nominator_account, (150 * SSC, 100 * SSC)),
+                    (new_nominator_account, (50 * SSC, 0)),
+                ]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            let staked_hold_id = crate::tests::HoldIdentifier::staking_staked(operator_id);
+            assert_eq!(
+                Balances::balance_on_hold(&staked_hold_id, &nominator_account),
+                nominator_stake
+            );
+
+            // transfer a partial position to an account that is not nominating yet
+            let transferred_shares = 30 * SSC;
+            assert_ok!(Domains::transfer_nomination(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                new_nominator_account,
+                transferred_shares,
+            ));
+
+            let deposit = Deposits::<Test>::get(operator_id, nominator_account).unwrap();
+            assert_eq!(deposit.known.shares, nominator_stake - transferred_shares);
+            assert_eq!(
+                deposit.known.storage_fee_deposit,
+                nominator_storage_fee_deposit * 5 / 8
+            );
+            let deposit = Deposits::<Test>::get(operator_id, new_nominator_account).unwrap();
+            assert_eq!(deposit.known.shares, transferred_shares);
+            assert_eq!(
+                deposit.known.storage_fee_deposit,
+                nominator_storage_fee_deposit * 3 / 8
+            );
+
+            assert_eq!(
+                Balances::balance_on_hold(&staked_hold_id, &nominator_account),
+                nominator_stake - transferred_shares
+            );
+            assert_eq!(
+                Balances::balance_on_hold(&staked_hold_id, &new_nominator_account),
+                transferred_shares
+            );
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 2);
+
+            // the operator owner can't transfer the mandatory bond
+            assert_err!(
+                Domains::transfer_nomination(
+                    RuntimeOrigin::signed(operator_account),
+                    operator_id,
+                    new_nominator_account,
+                    800 * SSC,
+                ),
+                Error::<Test>::Staking(StakingError::MinimumOperatorStake)
+            );
+
+            // neither side can drop below the minimum nominator stake
+            assert_err!(
+                Domains::transfer_nomination(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    new_nominator_account,
+                    45 * SSC,
+                ),
+                Error::<Test>::Staking(StakingError::MinimumNominatorStake)
+            );
+            Balances::set_balance(&4, 50 * SSC);
+            assert_err!(
+                Domains::transfer_nomination(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    4,
+                    5 * SSC,
+                ),
+                Error::<Test>::Staking(StakingError::MinimumNominatorStake)
+            );
+
+            // the full position can be transferred
+            assert_ok!(Domains::transfer_nomination(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                new_nominator_account,
+                nominator_stake - transferred_shares,
+            ));
+            let deposit = Deposits::<Test>::get(operator_id, new_nominator_account).unwrap();
+            assert_eq!(deposit.known.shares, nominator_stake);
+            assert_eq!(
+                deposit.known.storage_fee_deposit,
+                nominator_storage_fee_deposit
+            );
+            assert!(Balances::balance_on_hold(&staked_hold_id, &nominator_account).is_zero());
+            assert_eq!(
+                Balances::balance_on_hold(&staked_hold_id, &new_nominator_account),
+                nominator_stake
+            );
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 1);
+        });
+    }
+
+    #[test]
+    fn transfer_nomination_with_nominations_closed() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let nominator_account = 2;
+        let other_nominator_account = 3;
+        let new_nominator_account = 4;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                1500 * SSC,
+                1000 * SSC,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![
+                    (nominator_account, (150 * SSC, 100 * SSC)),
+                    (other_nominator_account, (150 * SSC, 100 * SSC)),
+                ]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            Balances::set_balance(&new_nominator_account, 100 * SSC);
+            assert_ok!(Domains::set_accepts_nominations(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                false
+            ));
+
+            // new nominator can't join through a transfer
+            assert_err!(
+                Domains::transfer_nomination(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id,
+                    new_nominator_account,
+                    20 * SSC,
+                ),
+                Error::<Test>::Staking(StakingError::NominationsClosed)
+            );
+            assert!(Deposits::<Test>::get(operator_id, new_nominator_account).is_none());
+
+            // transfers to the existing nominators and the operator owner are still allowed
+            assert_ok!(Domains::transfer_nomination(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                other_nominator_account,
+                20 * SSC,
+            ));
+            assert_ok!(Domains::transfer_nomination(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                operator_account,
+                20 * SSC,
+            ));
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 2);
+
+            // reopened nominations accept new nominators through a transfer again
+            assert_ok!(Domains::set_accepts_nominations(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                true
+            ));
+            assert_ok!(Domains::transfer_nomination(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                new_nominator_account,
+                20 * SSC,
+            ));
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 3);
+        });
+    }
+
+    #[test]
+    fn claim_rewards() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 1500 * SSC;
+        let operator_total_stake = 1000 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let nominator_a = 2;
+        let nominator_b = 3;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_total_stake,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![
+                    (nominator_a, (400 * SSC, 300 * SSC)),
+                    (nominator_b, (150 * SSC, 100 * SSC)),
+                ]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // nothing to claim before any rewards
+            assert_eq!(
+                do_claim_rewards::<Test>(operator_id, nominator_a),
+                Ok(Zero::zero())
+            );
+
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), 20 * SSC)
+                .unwrap();
+
+            let free_balance_a = Balances::free_balance(nominator_a);
+            assert_ok!(Domains::claim_rewards(
+                RuntimeOrigin::signed(nominator_a),
+                operator_id,
+            ));
+            let rewards_a = Balances::free_balance(nominator_a) - free_balance_a;
+            assert!(!rewards_a.is_zero());
+
+            // claiming again right away yields nothing
+            assert_eq!(
+                do_claim_rewards::<Test>(operator_id, nominator_a),
+                Ok(Zero::zero())
+            );
+
+            let free_balance_b = Balances::free_balance(nominator_b);
+            let rewards_b = do_claim_rewards::<Test>(operator_id, nominator_b).unwrap();
+            assert_eq!(
+                Balances::free_balance(nominator_b) - free_balance_b,
+                rewards_b
+            );
+            assert_eq!(
+                do_claim_rewards::<Test>(operator_id, nominator_b),
+                Ok(Zero::zero())
+            );
+
+            // nominator `a` holds three times the shares of nominator `b`
+            assert!(rewards_a.abs_diff(rewards_b * 3) <= 10);
+            let expected_rewards_b = Perquintill::from_rational(80u128, 1120).mul_floor(20 * SSC);
+            assert!(rewards_b.abs_diff(expected_rewards_b) <= 10);
+
+            // claimed rewards are no longer part of the stake
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(
+                operator.current_total_stake,
+                1120 * SSC - rewards_a - rewards_b
+            );
+        });
+    }
+
+    #[test]
+    fn epoch_stake_delta() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let nominator_account = 2;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                1500 * SSC,
+                1000 * SSC,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![(nominator_account, (1000 * SSC, 100 * SSC))]),
+            );
+
+            // storage fee deposit is not counted as stake
+            assert_eq!(
+                Domains::epoch_stake_delta(domain_id),
+                EpochStakeDelta {
+                    deposits: 880 * SSC,
+                    withdrawals: 0,
+                    rewards: 0,
+                }
+            );
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(
+                Domains::epoch_stake_delta(domain_id),
+                EpochStakeDelta::default()
+            );
+
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                200 * SSC,
+            ));
+            // share price is one before any rewards
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                40 * SSC,
+                false,
+            ));
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                100 * SSC,
+                false,
+            ));
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), 20 * SSC)
+                .unwrap();
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                50 * SSC,
+            ));
+
+            assert_eq!(
+                Domains::epoch_stake_delta(domain_id),
+                EpochStakeDelta {
+                    deposits: 200 * SSC,
+                    withdrawals: 140 * SSC,
+                    rewards: 20 * SSC,
+                }
+            );
+
+            // other domains are not affected
+            assert_eq!(
+                Domains::epoch_stake_delta(DomainId::new(1)),
+                EpochStakeDelta::default()
+            );
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(
+                Domains::epoch_stake_delta(domain_id),
+                EpochStakeDelta::default()
+            );
+        });
+    }
+
+    #[test]
+    fn withdraw_operator_tax() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let nominator_account = 2;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                1500 * SSC,
+                1000 * SSC,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![(nominator_account, (1000 * SSC, 100 * SSC))]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                maybe_operator.as_mut().unwrap().nomination_tax = Percent::from_percent(10);
+            });
+
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            let (total_stake, total_shares) =
+                (operator.current_total_stake, operator.current_total_shares);
+            assert_eq!(total_stake, 880 * SSC);
+
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), 20 * SSC)
+                .unwrap();
+
+            let operator_balance = Balances::usable_balance(operator_account);
+            assert_ok!(Domains::withdraw_operator_tax(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+            ));
+            assert_eq!(
+                Balances::usable_balance(operator_account),
+                operator_balance + 2 * SSC
+            );
+
+            // only the tax is deducted from the rewards, principal and shares are untouched
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(operator.current_total_stake, total_stake);
+            assert_eq!(operator.current_total_shares, total_shares);
+            assert_eq!(operator.tax_withdrawn_in_epoch, 2 * SSC);
+            let stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert_eq!(
+                stake_summary.current_epoch_rewards.get(&operator_id),
+                Some(&(18 * SSC))
+            );
+
+            // tax is not paid out twice
+            assert_eq!(
+                do_withdraw_operator_tax::<Test>(operator_account, operator_id),
+                Ok(0)
+            );
+
+            assert_err!(
+                Domains::withdraw_operator_tax(
+                    RuntimeOrigin::signed(nominator_account),
+                    operator_id
+                ),
+                Error::<Test>::Staking(StakingError::NotOperatorOwner)
+            );
+
+            // only the rewards accrued since the last withdrawal are taxed
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), 10 * SSC)
+                .unwrap();
+            assert_eq!(
+                do_withdraw_operator_tax::<Test>(operator_account, operator_id),
+                Ok(SSC)
+            );
+
+            // withdrawn tax is not staked again at the epoch transition
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let operator = Operators::<Test>::get(operator_id).unwrap();
+            assert_eq!(operator.current_total_stake, total_stake + 27 * SSC);
+            assert_eq!(operator.current_total_shares, total_shares);
+            assert_eq!(operator.tax_withdrawn_in_epoch, 0);
+
+            do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), 10 * SSC)
+                .unwrap();
+            do_mark_operators_as_slashed::<Test>(
+                vec![operator_id],
+                SlashedReason::InvalidBundle(1),
+            )
+            .unwrap();
+            assert_err!(
+                Domains::withdraw_operator_tax(
+                    RuntimeOrigin::signed(operator_account),
+                    operator_id
+                ),
+                Error::<Test>::Staking(StakingError::OperatorFrozen)
+            );
+        });
+    }
+
+    #[test]
+    fn pending_deposit_and_withdrawal() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 1500 * SSC;
+        let operator_total_stake = 1000 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let nominator_account = 2;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_total_stake,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::new(),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(
+                Domains::pending_deposit(operator_id, nominator_account),
+                None
+            );
+
+            Balances::set_balance(&nominator_account, 200 * SSC);
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                100 * SSC,
+            ));
+            assert_eq!(
+                Domains::pending_deposit(operator_id, nominator_account),
+                Some(100 * SSC)
+            );
+
+            // the deposit is settled at the end of the epoch
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(
+                Domains::pending_deposit(operator_id, nominator_account),
+                None
+            );
+            assert_eq!(
+                Domains::pending_withdrawal(operator_id, nominator_account),
+                None
+            );
+
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                40 * SSC,
+                false,
+            ));
+            let current_epoch_index = DomainStakingSummary::<Test>::get(domain_id)
+                .unwrap()
+                .current_epoch_index;
+            assert_eq!(
+                Domains::pending_withdrawal(operator_id, nominator_account),
+                Some(PendingWithdrawal {
+                    epoch_index: current_epoch_index,
+                    shares: 40 * SSC,
+                    storage_fee_refund: 10 * SSC,
+                })
+            );
+
+            // the withdrawal is converted to balance at the end of the epoch
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(
+                Domains::pending_withdrawal(operator_id, nominator_account),
+                None
+            );
+        });
+    }
+
+    #[test]
+    fn operator_status_info() {
+        let domain_id = DomainId::new(0);
 
-    #[test]
-    fn withdraw_stake_nominator_all_with_storage_fee_profit() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![(50 * SSC, Ok(()))],
-            maybe_deposit: None,
-            // The storage fund increased 50% (i.e. 21 * SSC) thus the nominator make 50%
-            // storage fee profit i.e. 5 * SSC with rounding dust deducted
-            storage_fund_change: (true, 21),
-            expected_withdraw: Some((54999999994000000000, true)),
-            expected_nominator_count_reduced_by: 1,
-        })
-    }
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let operator_ids = (1..=2)
+                .map(|operator_account| {
+                    let pair = OperatorPair::from_seed(&U256::from(operator_account).into());
+                    let data = OperatorSigningKeyProofOfOwnershipData {
+                        operator_owner: operator_account,
+                    };
+                    let signature = pair.sign(&data.encode());
+                    let (operator_id, _) = register_operator(
+                        domain_id,
+                        operator_account,
+                        1500 * SSC,
+                        1000 * SSC,
+                        10 * SSC,
+                        pair.public(),
+                        signature,
+                        BTreeMap::new(),
+                    );
+                    operator_id
+                })
+                .collect::<Vec<_>>();
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
 
-    #[test]
-    fn withdraw_stake_nominator_all_with_storage_fee_loss() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![(50 * SSC, Ok(()))],
-            maybe_deposit: None,
-            // The storage fund decreased 50% (i.e. 21 * SSC) thus the nominator loss 50%
-            // storage fee deposit i.e. 5 * SSC with rounding dust deducted
-            storage_fund_change: (false, 21),
-            expected_withdraw: Some((44999999998000000000, true)),
-            expected_nominator_count_reduced_by: 1,
-        })
-    }
+            let active_status = OperatorStatusInfo {
+                is_frozen: false,
+                current_domain_id: domain_id,
+                next_domain_id: domain_id,
+                pending_deregistration: false,
+            };
+            for operator_id in &operator_ids {
+                assert_eq!(
+                    Domains::operator_status(*operator_id),
+                    Some(active_status.clone())
+                );
+            }
+            assert_eq!(Domains::operator_status(operator_ids[1] + 1), None);
 
-    #[test]
-    fn withdraw_stake_nominator_all_with_storage_fee_loss_all() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![(50 * SSC, Ok(()))],
-            maybe_deposit: None,
-            // The storage fund decreased 100% (i.e. 42 * SSC) thus the nominator loss 100%
-            // storage fee deposit i.e. 10 * SSC
-            storage_fund_change: (false, 42),
-            expected_withdraw: Some((40 * SSC, true)),
-            expected_nominator_count_reduced_by: 1,
-        })
-    }
+            // de-registered operator
+            assert_ok!(Domains::deregister_operator(
+                RuntimeOrigin::signed(1),
+                operator_ids[0],
+            ));
+            assert_eq!(
+                Domains::operator_status(operator_ids[0]),
+                Some(OperatorStatusInfo {
+                    pending_deregistration: true,
+                    ..active_status.clone()
+                })
+            );
 
-    #[test]
-    fn withdraw_stake_nominator_multiple_withdraws_with_storage_fee_profit() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![(5 * SSC, Ok(())), (10 * SSC, Ok(())), (15 * SSC, Ok(()))],
-            maybe_deposit: None,
-            // The storage fund increased 50% (i.e. 21 * SSC) thus the nominator make 50%
-            // storage fee profit i.e. 5 * SSC with rounding dust deducted, withdraw 60% of
-            // the stake and the storage fee profit
-            storage_fund_change: (true, 21),
-            expected_withdraw: Some((30 * SSC + 2999999855527204374, false)),
-            expected_nominator_count_reduced_by: 0,
-        })
+            // frozen operator
+            do_mark_operators_as_slashed::<Test>(
+                vec![operator_ids[1]],
+                SlashedReason::InvalidBundle(1),
+            )
+            .unwrap();
+            assert_eq!(
+                Domains::operator_status(operator_ids[1]),
+                Some(OperatorStatusInfo {
+                    is_frozen: true,
+                    ..active_status
+                })
+            );
+        });
     }
 
     #[test]
-    fn withdraw_stake_nominator_multiple_withdraws_with_storage_fee_loss() {
-        withdraw_stake(WithdrawParams {
-            minimum_nominator_stake: 10 * SSC,
-            nominators: vec![(0, 150 * SSC), (1, 50 * SSC), (2, 10 * SSC)],
-            operator_reward: Zero::zero(),
-            nominator_id: 1,
-            withdraws: vec![(5 * SSC, Ok(())), (5 * SSC, Ok(())), (10 * SSC, Ok(()))],
-            maybe_deposit: None,
-            // The storage fund increased 50% (i.e. 21 * SSC) thus the nominator loss 50%
-            // storage fee i.e. 5 * SSC with rounding dust deducted, withdraw 40% of
-            // the stake and 40% of the storage fee loss are deducted
-            storage_fund_change: (false, 21),
-            expected_withdraw: Some((20 * SSC - 2 * SSC - 33331097576, false)),
-            expected_nominator_count_reduced_by: 0,
-        })
+    fn renominate_after_full_withdrawal() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 1500 * SSC;
+        let operator_total_stake = 1000 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let nominator_account = 2;
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_total_stake,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::from_iter(vec![(nominator_account, (200 * SSC, 100 * SSC))]),
+            );
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 1);
+
+            // exit the pool fully
+            let shares = Deposits::<Test>::get(operator_id, nominator_account)
+                .unwrap()
+                .known
+                .shares;
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                shares,
+                false,
+            ));
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 0);
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            // staking withdrawal is 5 blocks, so confirmed block should be at least 105
+            LatestConfirmedDomainExecutionReceipt::<Test>::insert(
+                domain_id,
+                ExecutionReceiptOf::<Test> {
+                    domain_block_number: 105,
+                    domain_block_hash: Default::default(),
+                    domain_block_extrinsic_root: Default::default(),
+                    parent_domain_block_receipt_hash: Default::default(),
+                    consensus_block_number: Default::default(),
+                    consensus_block_hash: Default::default(),
+                    inboxed_bundles: vec![],
+                    final_state_root: Default::default(),
+                    execution_trace: vec![],
+                    execution_trace_root: Default::default(),
+                    block_fees: BlockFees::default(),
+                    transfers: Transfers::default(),
+                },
+            );
+            assert_ok!(do_unlock_funds::<Test>(operator_id, nominator_account));
+
+            // no residual state is left behind
+            assert!(Deposits::<Test>::get(operator_id, nominator_account).is_none());
+            assert!(Withdrawals::<Test>::get(operator_id, nominator_account).is_none());
+            assert!(
+                NominatorRewardCheckpoints::<Test>::get(operator_id, nominator_account).is_none()
+            );
+            let staked_hold_id = crate::tests::HoldIdentifier::staking_staked(operator_id);
+            assert!(Balances::balance_on_hold(&staked_hold_id, &nominator_account).is_zero());
+            let storage_fund_hold_id =
+                crate::tests::HoldIdentifier::storage_fund_withdrawal(operator_id);
+            assert!(Balances::balance_on_hold(&storage_fund_hold_id, &nominator_account).is_zero());
+
+            // enter the same pool again in a later epoch
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                50 * SSC,
+            ));
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 1);
+            let current_epoch_index = DomainStakingSummary::<Test>::get(domain_id)
+                .unwrap()
+                .current_epoch_index;
+            assert_eq!(
+                Deposits::<Test>::get(operator_id, nominator_account),
+                Some(Deposit {
+                    known: KnownDeposit {
+                        shares: 0,
+                        storage_fee_deposit: 0,
+                    },
+                    pending: Some(PendingDeposit {
+                        effective_domain_epoch: (domain_id, current_epoch_index).into(),
+                        amount: 40 * SSC,
+                        storage_fee_deposit: 10 * SSC,
+                    }),
+                })
+            );
+            assert!(Withdrawals::<Test>::get(operator_id, nominator_account).is_none());
+            assert_eq!(
+                Balances::balance_on_hold(&staked_hold_id, &nominator_account),
+                40 * SSC
+            );
+
+            // the new position is converted to shares and can be withdrawn again
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(
+                Domains::pending_deposit(operator_id, nominator_account),
+                None
+            );
+            assert_ok!(Domains::withdraw_stake(
+                RuntimeOrigin::signed(nominator_account),
+                operator_id,
+                40 * SSC,
+                false,
+            ));
+            assert_eq!(NominatorCount::<Test>::get(operator_id), 0);
+        });
     }
 
     #[test]
@@ -2570,7 +4445,7 @@ pub(crate) mod tests {
             );
 
             for unlock in &unlocking {
-                do_withdraw_stake::<Test>(operator_id, unlock.0, unlock.1).unwrap();
+                do_withdraw_stake::<Test>(operator_id, unlock.0, unlock.1, false).unwrap();
             }
 
             do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), 20 * SSC)
@@ -2728,7 +4603,7 @@ pub(crate) mod tests {
             );
 
             for unlock in &unlocking {
-                do_withdraw_stake::<Test>(operator_id, unlock.0, unlock.1).unwrap();
+                do_withdraw_stake::<Test>(operator_id, unlock.0, unlock.1, false).unwrap();
             }
 
             do_reward_operators::<Test>(domain_id, vec![operator_id].into_iter(), 20 * SSC)
@@ -3065,4 +4940,162 @@ pub(crate) mod tests {
             assert_eq!(bundle_storage_fund::total_balance::<Test>(operator_id), 0);
         });
     }
+
+    #[test]
+    fn operator_rankings() {
+        let domain_id = DomainId::new(0);
+        let operator_free_balance = 500 * SSC;
+        // (operator account, stake); 20% of the stake goes to the storage fund
+        let operators = [(1, 150 * SSC), (2, 250 * SSC), (3, 200 * SSC)];
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let mut operator_ids = vec![];
+            for (i, (operator_account, operator_stake)) in operators.into_iter().enumerate() {
+                let pair = OperatorPair::from_seed(&U256::from(i as u32).into());
+                let data = OperatorSigningKeyProofOfOwnershipData {
+                    operator_owner: operator_account,
+                };
+                let signature = pair.sign(&data.encode());
+                let (operator_id, _) = register_operator(
+                    domain_id,
+                    operator_account,
+                    operator_free_balance,
+                    operator_stake,
+                    10 * SSC,
+                    pair.public(),
+                    signature,
+                    Default::default(),
+                );
+                operator_ids.push(operator_id);
+            }
+
+            // operators only become part of the ranking once the epoch is finalized
+            assert!(Domains::operator_rankings(domain_id).is_empty());
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            let total_stake = 480 * SSC;
+            assert_eq!(
+                Domains::operator_rankings(domain_id),
+                vec![
+                    (
+                        operator_ids[1],
+                        200 * SSC,
+                        Perbill::from_rational(200 * SSC, total_stake)
+                    ),
+                    (
+                        operator_ids[2],
+                        160 * SSC,
+                        Perbill::from_rational(160 * SSC, total_stake)
+                    ),
+                    (
+                        operator_ids[0],
+                        120 * SSC,
+                        Perbill::from_rational(120 * SSC, total_stake)
+                    ),
+                ]
+            );
+            assert!(Domains::operator_rankings(DomainId::new(1)).is_empty());
+        });
+    }
+
+    #[test]
+    fn min_stake_to_enter() {
+        let domain_id = DomainId::new(0);
+        let operator_free_balance = 500 * SSC;
+        // (operator account, stake); 20% of the stake goes to the storage fund
+        let operators = [(1, 150 * SSC), (2, 250 * SSC), (3, 200 * SSC)];
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            for (i, (operator_account, operator_stake)) in operators.into_iter().enumerate() {
+                let pair = OperatorPair::from_seed(&U256::from(i as u32).into());
+                let data = OperatorSigningKeyProofOfOwnershipData {
+                    operator_owner: operator_account,
+                };
+                let signature = pair.sign(&data.encode());
+                register_operator(
+                    domain_id,
+                    operator_account,
+                    operator_free_balance,
+                    operator_stake,
+                    10 * SSC,
+                    pair.public(),
+                    signature,
+                    Default::default(),
+                );
+            }
+
+            // no current operators until the epoch is finalized
+            assert_eq!(Domains::min_stake_to_enter(domain_id), 0);
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+
+            assert_eq!(Domains::min_stake_to_enter(domain_id), 120 * SSC);
+            assert_eq!(Domains::min_stake_to_enter(DomainId::new(1)), 0);
+        });
+    }
+
+    #[test]
+    fn operator_stake_at() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let operator_free_balance = 500 * SSC;
+        let operator_total_stake = 150 * SSC;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                operator_free_balance,
+                operator_total_stake,
+                10 * SSC,
+                pair.public(),
+                signature,
+                Default::default(),
+            );
+
+            // operator is not part of the epoch 0
+            assert_eq!(Domains::operator_stake_at(operator_id, 0), None);
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Domains::operator_stake_at(operator_id, 0), None);
+            assert_eq!(Domains::operator_stake_at(operator_id, 1), Some(120 * SSC));
+
+            // 20% of the nomination goes to the storage fund
+            assert_ok!(Domains::nominate_operator(
+                RuntimeOrigin::signed(operator_account),
+                operator_id,
+                100 * SSC,
+            ));
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Domains::operator_stake_at(operator_id, 1), Some(120 * SSC));
+            assert_eq!(Domains::operator_stake_at(operator_id, 2), Some(200 * SSC));
+            assert_eq!(
+                OperatorEpochStake::<Test>::get(operator_id, DomainEpoch::from((domain_id, 1))),
+                Some(120 * SSC)
+            );
+
+            // future epoch is unknown
+            assert_eq!(Domains::operator_stake_at(operator_id, 3), None);
+
+            // epoch 1 is still retained until it falls out of the history window
+            for _ in 0..OPERATOR_STAKE_HISTORY_DEPTH - 1 {
+                do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            }
+            assert_eq!(Domains::operator_stake_at(operator_id, 1), Some(120 * SSC));
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            assert_eq!(Domains::operator_stake_at(operator_id, 1), None);
+            assert_eq!(
+                OperatorEpochStake::<Test>::get(operator_id, DomainEpoch::from((domain_id, 1))),
+                None
+            );
+            assert_eq!(Domains::operator_stake_at(operator_id, 2), Some(200 * SSC));
+        });
+    }
 }
@@ -1,17 +1,18 @@
 //! Staking epoch transition for domain
 use crate::bundle_storage_fund::deposit_reserve_for_storage_fund;
 use crate::pallet::{
-    AccumulatedTreasuryFunds, Deposits, DomainStakingSummary, LastEpochStakingDistribution,
-    NominatorCount, OperatorIdOwner, Operators, PendingSlashes, PendingStakingOperationCount,
-    Withdrawals,
+    AccumulatedTreasuryFunds, Deposits, DomainEpochStakeDelta, DomainStakingSummary,
+    LastEpochStakingDistribution, NominatorCount, OperatorEpochStake, OperatorIdOwner, Operators,
+    PendingSlashes, PendingStakingOperationCount, Withdrawals,
 };
 use crate::staking::{
     do_cleanup_operator, do_convert_previous_epoch_deposits, do_convert_previous_epoch_withdrawal,
-    DomainEpoch, Error as TransitionError, OperatorStatus, SharePrice, WithdrawalInShares,
+    pending_operator_tax, DomainEpoch, Error as TransitionError, OperatorStatus, SharePrice,
+    WithdrawalInShares,
 };
 use crate::{
     bundle_storage_fund, BalanceOf, Config, ElectionVerificationParams, Event, HoldIdentifier,
-    OperatorEpochSharePrice, Pallet,
+    OperatorEpochSharePrice, Pallet, OPERATOR_STAKE_HISTORY_DEPTH,
 };
 #[cfg(not(feature = "std"))]
 use alloc::vec;
@@ -49,6 +50,9 @@ pub(crate) fn do_finalize_domain_current_epoch<T: Config>(
     // Reset pending staking operation count to 0
     PendingStakingOperationCount::<T>::set(domain_id, 0);
 
+    // Stake changes are only tracked for the current epoch
+    DomainEpochStakeDelta::<T>::remove(domain_id);
+
     // re stake operator's tax from the rewards
     let rewarded_operator_count = operator_take_reward_tax_and_stake::<T>(domain_id)?;
 
@@ -82,8 +86,9 @@ pub(crate) fn operator_take_reward_tax_and_stake<T: Config>(
                     Some(operator) => operator,
                 };
 
-                // calculate operator tax, mint the balance, and stake them
-                let operator_tax_amount = operator.nomination_tax.mul_floor(reward);
+                // calculate operator tax that is not withdrawn yet, mint the balance, and stake them
+                let operator_tax_amount = pending_operator_tax::<T>(operator, reward);
+                operator.tax_withdrawn_in_epoch = Zero::zero();
                 if !operator_tax_amount.is_zero() {
                     let nominator_id = OperatorIdOwner::<T>::get(operator_id)
                         .ok_or(TransitionError::MissingOperatorOwner)?;
@@ -181,15 +186,30 @@ pub(crate) fn do_finalize_domain_epoch_staking<T: Config>(
                 previous_epoch,
             )?;
 
+            if stake_changed {
+                finalized_operator_count += 1;
+            }
+
+            // the operator is kept in the `next_operators` even if it is not elected below, so it
+            // can be elected again once the owner tops up its stake
+            next_operators.insert(*next_operator_id);
+
+            // the share price may drop after the owner's last deposit or withdrawal, so the owner
+            // stake is checked again and the operator is frozen out of the election if it fell
+            // below the minimum operator stake
+            let owner_stake = operator_owner_stake::<T>(*next_operator_id)?;
+            if owner_stake < T::MinOperatorStake::get() {
+                Pallet::<T>::deposit_event(Event::OperatorStakeBelowMinimum {
+                    operator_id: *next_operator_id,
+                    owner_stake,
+                });
+                continue;
+            }
+
             total_domain_stake = total_domain_stake
                 .checked_add(&operator_stake)
                 .ok_or(TransitionError::BalanceOverflow)?;
             current_operators.insert(*next_operator_id, operator_stake);
-            next_operators.insert(*next_operator_id);
-
-            if stake_changed {
-                finalized_operator_count += 1;
-            }
         }
 
         let election_verification_params = ElectionVerificationParams {
@@ -199,6 +219,23 @@ pub(crate) fn do_finalize_domain_epoch_staking<T: Config>(
 
         LastEpochStakingDistribution::<T>::insert(domain_id, election_verification_params);
 
+        // record the stake of the operators of the completed epoch and drop the records that
+        // fall out of the retained history
+        let maybe_expired_epoch = previous_epoch.checked_sub(OPERATOR_STAKE_HISTORY_DEPTH);
+        for (operator_id, operator_stake) in &stake_summary.current_operators {
+            OperatorEpochStake::<T>::insert(
+                operator_id,
+                DomainEpoch::from((domain_id, previous_epoch)),
+                operator_stake,
+            );
+            if let Some(expired_epoch) = maybe_expired_epoch {
+                OperatorEpochStake::<T>::remove(
+                    operator_id,
+                    DomainEpoch::from((domain_id, expired_epoch)),
+                );
+            }
+        }
+
         let previous_epoch = stake_summary.current_epoch_index;
         stake_summary.current_epoch_index = next_epoch;
         stake_summary.current_total_stake = total_domain_stake;
@@ -210,6 +247,25 @@ pub(crate) fn do_finalize_domain_epoch_staking<T: Config>(
     .map_err(Error::FinalizeDomainEpochStaking)
 }
 
+/// Returns the stake backing the operator owner's shares, including its storage fee deposit,
+/// using the latest share price of the operator.
+fn operator_owner_stake<T: Config>(
+    operator_id: OperatorId,
+) -> Result<BalanceOf<T>, TransitionError> {
+    let operator = Operators::<T>::get(operator_id).ok_or(TransitionError::UnknownOperator)?;
+    let operator_owner =
+        OperatorIdOwner::<T>::get(operator_id).ok_or(TransitionError::MissingOperatorOwner)?;
+    let mut deposit = Deposits::<T>::get(operator_id, operator_owner).unwrap_or_default();
+    do_convert_previous_epoch_deposits::<T>(operator_id, &mut deposit)?;
+
+    let share_price =
+        SharePrice::new::<T>(operator.current_total_shares, operator.current_total_stake);
+    share_price
+        .shares_to_stake::<T>(deposit.known.shares)
+        .checked_add(&deposit.known.storage_fee_deposit)
+        .ok_or(TransitionError::BalanceOverflow)
+}
+
 /// Finalize the epoch for the operator
 ///
 /// Return the new total stake of the operator and a bool indicate if its total stake
@@ -609,7 +665,7 @@ mod tests {
             }
 
             for (nominator_id, shares) in withdrawals {
-                do_withdraw_stake::<Test>(operator_id, nominator_id, shares).unwrap();
+                do_withdraw_stake::<Test>(operator_id, nominator_id, shares, false).unwrap();
             }
 
             if !rewards.is_zero() {
@@ -905,4 +961,62 @@ mod tests {
             assert!(domain_stake_summary.current_epoch_rewards.is_empty())
         });
     }
+
+    #[test]
+    fn operator_owner_stake_below_minimum_after_share_price_drop() {
+        let domain_id = DomainId::new(0);
+        let operator_account = 1;
+        let pair = OperatorPair::from_seed(&U256::from(0u32).into());
+        let data = OperatorSigningKeyProofOfOwnershipData {
+            operator_owner: operator_account,
+        };
+        let signature = pair.sign(&data.encode());
+
+        let mut ext = new_test_ext();
+        ext.execute_with(|| {
+            let (operator_id, _) = register_operator(
+                domain_id,
+                operator_account,
+                300 * SSC,
+                200 * SSC,
+                10 * SSC,
+                pair.public(),
+                signature,
+                BTreeMap::new(),
+            );
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert!(domain_stake_summary
+                .current_operators
+                .contains_key(&operator_id));
+
+            // simulate a drop of the share price, the owner stake is now 40 SSC staked plus
+            // 40 SSC storage fee deposit, which is below the minimum operator stake
+            Operators::<Test>::mutate(operator_id, |maybe_operator| {
+                let operator = maybe_operator.as_mut().unwrap();
+                operator.current_total_stake /= 4;
+            });
+
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert!(!domain_stake_summary
+                .current_operators
+                .contains_key(&operator_id));
+            assert!(domain_stake_summary.next_operators.contains(&operator_id));
+            assert_eq!(domain_stake_summary.current_total_stake, Zero::zero());
+
+            // topping up the owner stake makes the operator elected again
+            assert_ok!(do_nominate_operator::<Test>(
+                operator_id,
+                operator_account,
+                50 * SSC
+            ));
+            do_finalize_domain_current_epoch::<Test>(domain_id).unwrap();
+            let domain_stake_summary = DomainStakingSummary::<Test>::get(domain_id).unwrap();
+            assert!(domain_stake_summary
+                .current_operators
+                .contains_key(&operator_id));
+        });
+    }
 }
@@ -48,6 +48,10 @@ pub trait WeightInfo {
 	fn unlock_funds() -> Weight;
 	fn unlock_nominator() -> Weight;
 	fn update_domain_operator_allow_list() -> Weight;
+	fn transfer_nomination() -> Weight;
+	fn set_accepts_nominations() -> Weight;
+	fn claim_rewards() -> Weight;
+	fn withdraw_operator_tax() -> Weight;
 }
 
 /// Weights for pallet_domains using the Substrate node and recommended hardware.
@@ -478,6 +482,94 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
+	/// Storage: `Domains::Operators` (r:1 w:0)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::DomainStakingSummary` (r:1 w:0)
+	/// Proof: `Domains::DomainStakingSummary` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Deposits` (r:2 w:2)
+	/// Proof: `Domains::Deposits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorEpochSharePrice` (r:1 w:0)
+	/// Proof: `Domains::OperatorEpochSharePrice` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Withdrawals` (r:2 w:0)
+	/// Proof: `Domains::Withdrawals` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+	/// Proof: `Domains::OperatorIdOwner` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Balances::Holds` (r:2 w:2)
+	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(5550), added: 8025, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:2 w:2)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Domains::NominatorCount` (r:1 w:1)
+	/// Proof: `Domains::NominatorCount` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn transfer_nomination() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1936`
+		//  Estimated: `9015`
+		// Minimum execution time: 104_000_000 picoseconds.
+		Weight::from_parts(109_000_000, 9015)
+			.saturating_add(T::DbWeight::get().reads(14_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+	}
+	/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+	/// Proof: `Domains::OperatorIdOwner` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Operators` (r:1 w:1)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn set_accepts_nominations() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `900`
+		//  Estimated: `4365`
+		// Minimum execution time: 21_000_000 picoseconds.
+		Weight::from_parts(22_000_000, 4365)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Domains::Operators` (r:1 w:1)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::DomainStakingSummary` (r:1 w:0)
+	/// Proof: `Domains::DomainStakingSummary` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Deposits` (r:1 w:1)
+	/// Proof: `Domains::Deposits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorEpochSharePrice` (r:1 w:0)
+	/// Proof: `Domains::OperatorEpochSharePrice` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorRewardBaselines` (r:1 w:1)
+	/// Proof: `Domains::OperatorRewardBaselines` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::NominatorRewardCheckpoints` (r:1 w:1)
+	/// Proof: `Domains::NominatorRewardCheckpoints` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn claim_rewards() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1512`
+		//  Estimated: `4977`
+		// Minimum execution time: 58_000_000 picoseconds.
+		Weight::from_parts(61_000_000, 4977)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+	/// Proof: `Domains::OperatorIdOwner` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Operators` (r:1 w:1)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::DomainStakingSummary` (r:1 w:1)
+	/// Proof: `Domains::DomainStakingSummary` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn withdraw_operator_tax() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1247`
+		//  Estimated: `4712`
+		// Minimum execution time: 47_000_000 picoseconds.
+		Weight::from_parts(49_000_000, 4712)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
 }
 
 // For backwards compatibility and tests
@@ -907,4 +999,92 @@ impl WeightInfo for () {
 			.saturating_add(ParityDbWeight::get().reads(1_u64))
 			.saturating_add(ParityDbWeight::get().writes(1_u64))
 	}
+	/// Storage: `Domains::Operators` (r:1 w:0)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::DomainStakingSummary` (r:1 w:0)
+	/// Proof: `Domains::DomainStakingSummary` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Deposits` (r:2 w:2)
+	/// Proof: `Domains::Deposits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorEpochSharePrice` (r:1 w:0)
+	/// Proof: `Domains::OperatorEpochSharePrice` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Withdrawals` (r:2 w:0)
+	/// Proof: `Domains::Withdrawals` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+	/// Proof: `Domains::OperatorIdOwner` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Balances::Holds` (r:2 w:2)
+	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(5550), added: 8025, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:2 w:2)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Domains::NominatorCount` (r:1 w:1)
+	/// Proof: `Domains::NominatorCount` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn transfer_nomination() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1936`
+		//  Estimated: `9015`
+		// Minimum execution time: 104_000_000 picoseconds.
+		Weight::from_parts(109_000_000, 9015)
+			.saturating_add(ParityDbWeight::get().reads(14_u64))
+			.saturating_add(ParityDbWeight::get().writes(7_u64))
+	}
+	/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+	/// Proof: `Domains::OperatorIdOwner` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Operators` (r:1 w:1)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn set_accepts_nominations() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `900`
+		//  Estimated: `4365`
+		// Minimum execution time: 21_000_000 picoseconds.
+		Weight::from_parts(22_000_000, 4365)
+			.saturating_add(ParityDbWeight::get().reads(3_u64))
+			.saturating_add(ParityDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Domains::Operators` (r:1 w:1)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::DomainStakingSummary` (r:1 w:0)
+	/// Proof: `Domains::DomainStakingSummary` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Deposits` (r:1 w:1)
+	/// Proof: `Domains::Deposits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorEpochSharePrice` (r:1 w:0)
+	/// Proof: `Domains::OperatorEpochSharePrice` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::OperatorRewardBaselines` (r:1 w:1)
+	/// Proof: `Domains::OperatorRewardBaselines` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::NominatorRewardCheckpoints` (r:1 w:1)
+	/// Proof: `Domains::NominatorRewardCheckpoints` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn claim_rewards() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1512`
+		//  Estimated: `4977`
+		// Minimum execution time: 58_000_000 picoseconds.
+		Weight::from_parts(61_000_000, 4977)
+			.saturating_add(ParityDbWeight::get().reads(8_u64))
+			.saturating_add(ParityDbWeight::get().writes(5_u64))
+	}
+	/// Storage: `Domains::OperatorIdOwner` (r:1 w:0)
+	/// Proof: `Domains::OperatorIdOwner` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::Operators` (r:1 w:1)
+	/// Proof: `Domains::Operators` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::LatestSubmittedER` (r:1 w:0)
+	/// Proof: `Domains::LatestSubmittedER` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Domains::DomainStakingSummary` (r:1 w:1)
+	/// Proof: `Domains::DomainStakingSummary` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn withdraw_operator_tax() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1247`
+		//  Estimated: `4712`
+		// Minimum execution time: 47_000_000 picoseconds.
+		Weight::from_parts(49_000_000, 4712)
+			.saturating_add(ParityDbWeight::get().reads(5_u64))
+			.saturating_add(ParityDbWeight::get().writes(3_u64))
+	}
 }
@@ -57,7 +57,7 @@ use sp_runtime::generic::OpaqueDigestItemId;
 use sp_runtime::traits::{
     BlakeTwo256, Block as BlockT, CheckedAdd, Hash as HashT, Header as HeaderT, NumberFor, Zero,
 };
-use sp_runtime::{Digest, DigestItem, OpaqueExtrinsic, Percent};
+use sp_runtime::{Digest, DigestItem, OpaqueExtrinsic, Perbill, Percent};
 use sp_runtime_interface::pass_by;
 use sp_runtime_interface::pass_by::PassBy;
 use sp_std::collections::btree_map::BTreeMap;
@@ -69,7 +69,7 @@ use sp_weights::Weight;
 use std::collections::BTreeSet;
 use subspace_core_primitives::crypto::blake3_hash;
 use subspace_core_primitives::{bidirectional_distance, Blake3Hash, PotOutput, Randomness, U256};
-use subspace_runtime_primitives::{Balance, Moment};
+use subspace_runtime_primitives::{AccountId, Balance, Moment};
 
 /// Key type for Operator.
 pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"oper");
@@ -515,6 +515,63 @@ impl<Extrinsic: Encode, Number, Hash, DomainHeader: HeaderT, Balance>
     }
 }
 
+impl<Extrinsic: Encode, Number: Clone, Hash: Clone, DomainHeader: HeaderT, Balance: Clone>
+    Bundle<Extrinsic, Number, Hash, DomainHeader, Balance>
+{
+    /// Returns the compact form of this bundle, each extrinsic is replaced by its hash computed
+    /// with `Hashing`.
+    pub fn to_compact_bundle<Hashing: HashT>(
+        &self,
+    ) -> CompactBundle<Hashing::Output, Number, Hash, DomainHeader, Balance> {
+        CompactBundle {
+            sealed_header: self.sealed_header.clone(),
+            extrinsics_hashes: self.extrinsics.iter().map(Hashing::hash_of).collect(),
+        }
+    }
+}
+
+/// Compact form of a domain bundle, which carries the extrinsic hashes instead of the full
+/// extrinsics.
+///
+/// The receiver is expected to already know the extrinsics (e.g. from its transaction pool) and
+/// reconstruct the full bundle with [`CompactBundle::into_bundle`].
+#[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
+pub struct CompactBundle<ExtrinsicHash, Number, Hash, DomainHeader: HeaderT, Balance> {
+    /// Sealed bundle header.
+    pub sealed_header: SealedBundleHeader<Number, Hash, DomainHeader, Balance>,
+    /// The hashes of the accompanying extrinsics.
+    pub extrinsics_hashes: Vec<ExtrinsicHash>,
+}
+
+impl<ExtrinsicHash, Number, Hash, DomainHeader: HeaderT, Balance>
+    CompactBundle<ExtrinsicHash, Number, Hash, DomainHeader, Balance>
+{
+    /// Reconstructs the full bundle, `get_extrinsic` is used to look up the extrinsic of each
+    /// hash.
+    ///
+    /// Returns the hash of the first extrinsic that can not be found as error.
+    pub fn into_bundle<Extrinsic, F>(
+        self,
+        mut get_extrinsic: F,
+    ) -> Result<Bundle<Extrinsic, Number, Hash, DomainHeader, Balance>, ExtrinsicHash>
+    where
+        F: FnMut(&ExtrinsicHash) -> Option<Extrinsic>,
+    {
+        let CompactBundle {
+            sealed_header,
+            extrinsics_hashes,
+        } = self;
+        let extrinsics = extrinsics_hashes
+            .into_iter()
+            .map(|xt_hash| get_extrinsic(&xt_hash).ok_or(xt_hash))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Bundle {
+            sealed_header,
+            extrinsics,
+        })
+    }
+}
+
 #[cfg(any(feature = "std", feature = "runtime-benchmarks"))]
 pub fn dummy_opaque_bundle<
     Number: Encode,
@@ -1404,6 +1461,33 @@ pub struct OperatorSigningKeyProofOfOwnershipData<AccountId> {
     pub operator_owner: AccountId,
 }
 
+/// A nominator's withdrawal that is not yet converted to balance since the domain epoch in which
+/// it was requested has not completed.
+#[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
+pub struct PendingWithdrawal<Balance, Share> {
+    /// Epoch of the operator's domain in which the withdrawal was requested.
+    pub epoch_index: EpochIndex,
+    /// Shares of the operator pool to withdraw, converted to balance using the share price of the
+    /// epoch once it completes.
+    pub shares: Share,
+    /// Storage fee deposit refunded along with the withdrawal.
+    pub storage_fee_refund: Balance,
+}
+
+/// Status of an operator, e.g. for nominators to check whether the operator accepts staking
+/// operations before submitting them.
+#[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
+pub struct OperatorStatusInfo {
+    /// Operator is slashed or pending to be slashed, and no longer accepts staking operations.
+    pub is_frozen: bool,
+    /// Domain the operator is currently operating on.
+    pub current_domain_id: DomainId,
+    /// Domain the operator will be operating on from the next epoch.
+    pub next_domain_id: DomainId,
+    /// Operator is de-registered and its stake is waiting to be unlocked.
+    pub pending_deregistration: bool,
+}
+
 /// Hook to handle chain rewards.
 pub trait OnChainRewards<Balance> {
     fn on_chain_rewards(chain_id: ChainId, reward: Balance);
@@ -1415,7 +1499,7 @@ impl<Balance> OnChainRewards<Balance> for () {
 
 sp_api::decl_runtime_apis! {
     /// API necessary for domains pallet.
-    #[api_version(5)]
+    #[api_version(6)]
     pub trait DomainsApi<DomainHeader: HeaderT> {
         /// Submits the transaction bundle via an unsigned extrinsic.
         fn submit_bundle_unsigned(opaque_bundle: OpaqueBundle<NumberFor<Block>, Block::Hash, DomainHeader, Balance>);
@@ -1506,6 +1590,34 @@ sp_api::decl_runtime_apis! {
 
         /// Return domain sudo call.
         fn domain_sudo_call(domain_id: DomainId) -> Option<Vec<u8>>;
+
+        /// Returns the current epoch operators of the given domain sorted by stake in descending
+        /// order, along with each operator's share of the domain total stake.
+        #[api_version(6)]
+        fn operator_rankings(domain_id: DomainId) -> Vec<(OperatorId, Balance, Perbill)>;
+
+        /// Returns the stake of the operator used for the bundle election at the given epoch,
+        /// `None` if the epoch is beyond the retained history.
+        #[api_version(6)]
+        fn operator_stake_at(operator_id: OperatorId, epoch_index: EpochIndex) -> Option<Balance>;
+
+        /// Returns the lowest stake among the current epoch operators of the given domain, zero if
+        /// the domain has no operators.
+        #[api_version(6)]
+        fn min_stake_to_enter(domain_id: DomainId) -> Balance;
+
+        /// Returns the nominator's deposit to the operator that is not yet converted to shares.
+        #[api_version(6)]
+        fn pending_deposit(operator_id: OperatorId, nominator_id: AccountId) -> Option<Balance>;
+
+        /// Returns the nominator's withdrawal from the operator that is not yet converted to
+        /// balance, the shares are of the runtime's share type which is the same as `Balance`.
+        #[api_version(6)]
+        fn pending_withdrawal(operator_id: OperatorId, nominator_id: AccountId) -> Option<PendingWithdrawal<Balance, Balance>>;
+
+        /// Returns the status of the operator, `None` if the operator doesn't exist.
+        #[api_version(6)]
+        fn operator_status(operator_id: OperatorId) -> Option<OperatorStatusInfo>;
     }
 
     pub trait BundleProducerElectionApi<Balance: Encode + Decode> {
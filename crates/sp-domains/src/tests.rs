@@ -1,8 +1,14 @@
-use crate::{signer_in_tx_range, InvalidBundleType, EMPTY_EXTRINSIC_ROOT};
+use crate::{
+    dummy_opaque_bundle, signer_in_tx_range, DomainId, ExecutionReceipt, InvalidBundleType,
+    EMPTY_EXTRINSIC_ROOT,
+};
 use num_traits::ops::wrapping::{WrappingAdd, WrappingSub};
 use parity_scale_codec::Encode;
+use sp_core::H256;
+use sp_runtime::generic::Header;
 use sp_runtime::traits::{BlakeTwo256, Hash};
 use sp_runtime::OpaqueExtrinsic;
+use std::collections::BTreeMap;
 use subspace_core_primitives::U256;
 
 #[test]
@@ -278,3 +284,56 @@ fn test_invalid_bundle_type_checking_order() {
         invalid_type(9876, 5).checking_order()
     );
 }
+
+#[test]
+fn test_compact_bundle_round_trip() {
+    let receipt = ExecutionReceipt::dummy::<BlakeTwo256>(
+        1u32,
+        H256::repeat_byte(1),
+        1u32,
+        H256::repeat_byte(2),
+    );
+    let mut bundle = dummy_opaque_bundle::<u32, H256, Header<u32, BlakeTwo256>, u128>(
+        DomainId::new(0),
+        0,
+        receipt,
+    );
+    bundle.extrinsics = (0..3u8)
+        .map(|i| OpaqueExtrinsic::from_bytes(&vec![i; 4].encode()).unwrap())
+        .collect();
+
+    let compact_bundle = bundle.to_compact_bundle::<BlakeTwo256>();
+    assert_eq!(compact_bundle.sealed_header, bundle.sealed_header);
+    assert_eq!(
+        compact_bundle.extrinsics_hashes,
+        bundle
+            .extrinsics
+            .iter()
+            .map(BlakeTwo256::hash_of)
+            .collect::<Vec<_>>()
+    );
+    assert!(compact_bundle.encoded_size() < bundle.encoded_size());
+
+    // Reconstruct the full bundle with all the extrinsics known
+    let known_extrinsics: BTreeMap<H256, OpaqueExtrinsic> = bundle
+        .extrinsics
+        .iter()
+        .map(|xt| (BlakeTwo256::hash_of(xt), xt.clone()))
+        .collect();
+    let reconstructed_bundle = compact_bundle
+        .clone()
+        .into_bundle(|xt_hash| known_extrinsics.get(xt_hash).cloned())
+        .unwrap();
+    assert_eq!(reconstructed_bundle, bundle);
+
+    // Reconstruction fails if any of the extrinsics is unknown
+    let missing_xt_hash = compact_bundle.extrinsics_hashes[1];
+    let res = compact_bundle.into_bundle(|xt_hash| {
+        if *xt_hash == missing_xt_hash {
+            None
+        } else {
+            known_extrinsics.get(xt_hash).cloned()
+        }
+    });
+    assert_eq!(res, Err(missing_xt_hash));
+}
@@ -41,20 +41,22 @@ use sp_runtime::traits::Header as HeaderT;
 use sp_runtime::ArithmeticError;
 use sp_std::cmp::Ordering;
 use sp_std::collections::btree_map::BTreeMap;
+use sp_std::collections::btree_set::BTreeSet;
 use sp_std::marker::PhantomData;
 use sp_std::num::NonZeroU64;
+use sp_std::{iter, mem};
 #[cfg(not(feature = "pot"))]
 use subspace_core_primitives::Randomness;
 use subspace_core_primitives::{
     ArchivedHistorySegment, BlockWeight, HistorySize, PublicKey, RewardSignature, SectorId,
-    SegmentCommitment, SegmentIndex, SolutionRange, REWARD_SIGNING_CONTEXT,
+    SegmentCommitment, SegmentIndex, SlotNumber, Solution, SolutionRange, REWARD_SIGNING_CONTEXT,
 };
 use subspace_verification::{
     calculate_block_weight, check_reward_signature, PieceCheckParams, VerifySolutionParams,
 };
 
 /// Chain constants.
-#[derive(Debug, Encode, Decode, Clone, TypeInfo)]
+#[derive(Debug, Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
 pub struct ChainConstants<Header: HeaderT> {
     /// K Depth at which we finalize the heads.
     pub k_depth: NumberOf<Header>,
@@ -81,10 +83,16 @@ pub struct ChainConstants<Header: HeaderT> {
     pub recent_history_fraction: (HistorySize, HistorySize),
     /// Minimum lifetime of a plotted sector, measured in archived segment.
     pub min_sector_lifetime: HistorySize,
+    /// Reject the headers with default (zeroed) extrinsics root, as a defensive check against
+    /// malformed headers.
+    pub require_extrinsics_root: bool,
+    /// Maximum number of slots a header may be behind the best chain header at the same number.
+    /// Headers of forks whose slots fall further behind are rejected, `None` disables the check.
+    pub max_sibling_slot_skew: Option<SlotNumber>,
 }
 
 /// Defines the storage bound for the light client store.
-#[derive(Default, Debug, Encode, Decode, TypeInfo, Clone)]
+#[derive(Default, Debug, Encode, Decode, TypeInfo, Clone, Eq, PartialEq)]
 pub enum StorageBound<Number> {
     /// Keeps all the headers in the storage.
     #[default]
@@ -111,14 +119,64 @@ pub struct HeaderExt<Header> {
     pub maybe_next_solution_range_override: Option<SolutionRange>,
     /// Restrict block authoring to this public key.
     pub maybe_root_plot_public_key: Option<FarmerPublicKey>,
+    /// Header is verified, but doesn't participate in the fork choice until it is confirmed.
+    pub is_speculative: bool,
+    /// Slot of the header, cached on import. `None` if the header was not imported by the
+    /// importer, e.g. the genesis or checkpoint header.
+    pub maybe_slot: Option<Slot>,
+    /// Public key of the farmer that authored the header, cached on import. `None` if the header
+    /// was not imported by the importer, e.g. the genesis or checkpoint header.
+    pub maybe_author: Option<FarmerPublicKey>,
 
     #[cfg(all(test, not(feature = "pot")))]
     test_overrides: mock::TestOverrides,
 }
 
+impl<Header: HeaderT> HeaderExt<Header> {
+    /// Returns the slot of the header, extracting it from the pre-digest only if it is not cached.
+    pub fn slot(&self) -> Result<Slot, ImportError<Header>> {
+        match self.maybe_slot {
+            Some(slot) => Ok(slot),
+            None => Ok(extract_pre_digest(&self.header)
+                .map_err(ImportError::digest_error(&self.header))?
+                .slot()),
+        }
+    }
+
+    /// Returns the author of the header, extracting it from the pre-digest only if it is not
+    /// cached.
+    pub fn author(&self) -> Result<FarmerPublicKey, ImportError<Header>> {
+        match &self.maybe_author {
+            Some(author) => Ok(author.clone()),
+            None => Ok(extract_pre_digest(&self.header)
+                .map_err(ImportError::digest_error(&self.header))?
+                .solution()
+                .public_key
+                .clone()),
+        }
+    }
+}
+
+/// Summary of the chain state known to the light client, e.g. for reporting sync progress.
+#[derive(Debug, Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+pub struct ChainInfo<Header: HeaderT> {
+    /// Number of the best header.
+    pub best_number: NumberOf<Header>,
+    /// Hash of the best header.
+    pub best_hash: HashOf<Header>,
+    /// Cumulative weight of the chain until the best header.
+    pub best_total_weight: BlockWeight,
+    /// Number of the finalized header.
+    pub finalized_number: NumberOf<Header>,
+    /// Hash of the finalized header.
+    pub finalized_hash: HashOf<Header>,
+    /// Number of headers in the store.
+    pub header_count: u64,
+}
+
 /// Type to hold next digest items present in parent header that are used to verify the immediate
 /// descendant.
-#[derive(Default, Debug, Encode, Decode, Clone, TypeInfo)]
+#[derive(Default, Debug, Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
 pub struct NextDigestItems {
     #[cfg(not(feature = "pot"))]
     next_global_randomness: Randomness,
@@ -153,7 +211,8 @@ impl<Header: HeaderT> HeaderExt<Header> {
             ..
         } = extract_subspace_digest_items::<_, FarmerPublicKey, FarmerPublicKey, FarmerSignature>(
             &self.header,
-        )?;
+        )
+        .map_err(ImportError::digest_error(&self.header))?;
 
         // if there is override for solution range for current era, override it
         let solution_range = self
@@ -189,9 +248,31 @@ impl<Header: HeaderT> HeaderExt<Header> {
 type HashOf<T> = <T as HeaderT>::Hash;
 type NumberOf<T> = <T as HeaderT>::Number;
 
+/// Storage mutation staged by the importer, see [`Storage::commit_batch`].
+#[derive(Debug, Clone)]
+pub enum StorageOp<Header: HeaderT> {
+    /// Store the extended header, see [`Storage::store_header`].
+    StoreHeader {
+        /// Extended header to store.
+        header_ext: HeaderExt<Header>,
+        /// Whether the header is considered best.
+        as_best_header: bool,
+    },
+    /// Mark header with hash as best, see [`Storage::set_best_header`].
+    SetBestHeader(HashOf<Header>),
+    /// Prune header with hash, see [`Storage::prune_header`].
+    PruneHeader(HashOf<Header>),
+    /// Mark header with hash as finalized, see [`Storage::finalize_header`].
+    FinalizeHeader(HashOf<Header>),
+    /// Store segment commitments, see [`Storage::store_segment_commitments`].
+    StoreSegmentCommitments(BTreeMap<SegmentIndex, SegmentCommitment>),
+}
+
 /// Storage responsible for storing headers.
 pub trait Storage<Header: HeaderT> {
     /// Returns the chain constants.
+    /// Note: constants must be persisted when the store is initialized and returned unchanged once
+    /// the store is reopened, see [`HeaderImporter::verify_constants`].
     fn chain_constants(&self) -> ChainConstants<Header>;
 
     /// Queries a header at a specific block number or block hash.
@@ -202,8 +283,14 @@ pub trait Storage<Header: HeaderT> {
     fn store_header(&mut self, header_ext: HeaderExt<Header>, as_best_header: bool);
 
     /// Returns the best known tip of the chain.
+    /// Note: the returned header may have been pruned since it was stored as best.
     fn best_header(&self) -> HeaderExt<Header>;
 
+    /// Marks an already stored header with hash as best.
+    /// Note: the header may be the finalized header that was already pruned, it must not be stored
+    /// again in that case.
+    fn set_best_header(&mut self, hash: HashOf<Header>);
+
     /// Returns headers at a given number.
     fn headers_at_number(&self, number: NumberOf<Header>) -> Vec<HeaderExt<Header>>;
 
@@ -214,6 +301,8 @@ pub trait Storage<Header: HeaderT> {
     fn finalize_header(&mut self, hash: HashOf<Header>);
 
     /// Returns the latest finalized header.
+    /// Note: the finalized header must be returned even if it was already pruned, so that its
+    /// children can still be imported.
     fn finalized_header(&self) -> HeaderExt<Header>;
 
     /// Stores segment commitments for fast retrieval by segment index at or below finalized header.
@@ -231,6 +320,46 @@ pub trait Storage<Header: HeaderT> {
 
     /// How many pieces one sector is supposed to contain (max)
     fn max_pieces_in_sector(&self) -> u16;
+
+    /// Returns the number of headers in the store.
+    fn header_count(&self) -> u64;
+
+    /// Called every time the finalized head advances during the header import, with the newly
+    /// finalized header.
+    ///
+    /// When a single import finalizes several headers at once (e.g. after a re-org to a longer
+    /// chain), each of them is delivered separately in ascending order of numbers. Headers are
+    /// delivered after the import was committed to the store.
+    ///
+    /// Can be used to prune any state derived from the headers and to notify subscribers, does
+    /// nothing by default.
+    fn on_finalize(&mut self, _header_ext: &HeaderExt<Header>) {}
+
+    /// Applies the storage mutations staged by the importer as a single unit.
+    ///
+    /// The importer commits all the mutations of a header import with a single call, such that a
+    /// failed import leaves the store as it was before the import.
+    ///
+    /// Stores that can fail mid-way should override this to apply either all of the operations or
+    /// none of them. By default operations are applied sequentially and never fail.
+    fn commit_batch(&mut self, ops: Vec<StorageOp<Header>>) -> Result<(), String> {
+        for op in ops {
+            match op {
+                StorageOp::StoreHeader {
+                    header_ext,
+                    as_best_header,
+                } => self.store_header(header_ext, as_best_header),
+                StorageOp::SetBestHeader(hash) => self.set_best_header(hash),
+                StorageOp::PruneHeader(hash) => self.prune_header(hash),
+                StorageOp::FinalizeHeader(hash) => self.finalize_header(hash),
+                StorageOp::StoreSegmentCommitments(segment_commitments) => {
+                    self.store_segment_commitments(segment_commitments)
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Error type that holds the current finalized number and the header number we are trying to import.
@@ -240,55 +369,261 @@ pub struct HeaderBelowArchivingDepthError<Header: HeaderT> {
     header_number: NumberOf<Header>,
 }
 
+/// Arithmetic error during the header import.
+///
+/// Wraps [`ArithmeticError`] such that it can be exposed as the source of [`ImportError`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
+#[cfg_attr(feature = "thiserror", error("{0:?}"))]
+pub struct ImportArithmeticError(pub ArithmeticError);
+
+impl From<ArithmeticError> for ImportArithmeticError {
+    fn from(error: ArithmeticError) -> Self {
+        Self(error)
+    }
+}
+
 /// Error during the header import.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
 pub enum ImportError<Header: HeaderT> {
     /// Header already imported.
+    #[cfg_attr(feature = "thiserror", error("Header already imported"))]
     HeaderAlreadyImported,
     /// Missing parent header.
+    #[cfg_attr(feature = "thiserror", error("Missing parent of header {0:?}"))]
     MissingParent(HashOf<Header>),
     /// Missing header associated with hash.
+    #[cfg_attr(feature = "thiserror", error("Missing header {0:?}"))]
     MissingHeader(HashOf<Header>),
     /// Missing ancestor header at the number.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Missing ancestor of header {0:?} at number {1:?}")
+    )]
     MissingAncestorHeader(HashOf<Header>, NumberOf<Header>),
     /// Error while extracting digests from header.
-    DigestError(DigestError),
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Digest error in header {hash:?}: {source}")
+    )]
+    DigestError {
+        /// Hash of the header digests were extracted from.
+        hash: HashOf<Header>,
+        /// Underlying digest error.
+        #[cfg_attr(feature = "thiserror", source)]
+        source: DigestError,
+    },
     /// Invalid digest in the header.
+    #[cfg_attr(feature = "thiserror", error("Invalid {0} digest"))]
     InvalidDigest(ErrorDigestType),
     /// Invalid slot when compared with parent header.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Slot is not greater than the slot of the parent header")
+    )]
     InvalidSlot,
     /// Block signature is invalid.
+    #[cfg_attr(feature = "thiserror", error("Invalid block signature"))]
     InvalidBlockSignature,
     /// Solution present in the header is invalid.
+    #[cfg_attr(feature = "thiserror", error("Invalid solution: {0}"))]
     InvalidSolution(String),
     /// Arithmetic error.
-    ArithmeticError(ArithmeticError),
+    #[cfg_attr(feature = "thiserror", error("Arithmetic error: {0}"))]
+    ArithmeticError(#[cfg_attr(feature = "thiserror", source)] ImportArithmeticError),
     /// Switched to different fork beyond archiving depth.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Switched to a fork below the archiving depth")
+    )]
     SwitchedToForkBelowArchivingDepth,
     /// Header being imported is below the archiving depth.
+    #[cfg_attr(
+        feature = "thiserror",
+        error(
+            "Header #{:?} is not above the finalized header #{:?}",
+            .0.header_number,
+            .0.current_finalized_number
+        )
+    )]
     HeaderIsBelowArchivingDepth(HeaderBelowArchivingDepthError<Header>),
     /// Missing segment commitment for a given segment index.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Missing segment commitment for segment index {0}")
+    )]
     MissingSegmentCommitment(SegmentIndex),
     /// Incorrect block author.
+    #[cfg_attr(feature = "thiserror", error("Incorrect block author {0:?}"))]
     IncorrectBlockAuthor(FarmerPublicKey),
     /// Segment commitment history is empty
+    #[cfg_attr(feature = "thiserror", error("Segment commitment history is empty"))]
     EmptySegmentCommitmentHistory,
     /// Invalid history size
+    #[cfg_attr(feature = "thiserror", error("Invalid history size"))]
     InvalidHistorySize,
+    /// Header is not speculative.
+    #[cfg_attr(feature = "thiserror", error("Header {0:?} is not speculative"))]
+    HeaderIsNotSpeculative(HashOf<Header>),
+    /// Parent of the speculative header is not confirmed yet.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Parent of header {0:?} is not confirmed yet")
+    )]
+    SpeculativeParent(HashOf<Header>),
+    /// Header at the given number is not present in the canonical chain.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Missing canonical header at number {0:?}")
+    )]
+    MissingCanonicalHeader(NumberOf<Header>),
+    /// Consensus values stored for the header don't match the values derived from its parent.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Stored consensus values of header {0:?} don't match the derived ones")
+    )]
+    InvalidDerivedValues(HashOf<Header>),
+    /// Header extrinsics root is missing.
+    #[cfg_attr(feature = "thiserror", error("Missing extrinsics root"))]
+    MissingExtrinsicsRoot,
+    /// Slot is too far behind the slot of the best chain header at the same number.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Slot is too far behind the slot of the best chain header at the same number")
+    )]
+    SlotInconsistentWithSiblings,
+    /// Storage failed to commit the batch of mutations.
+    #[cfg_attr(feature = "thiserror", error("Failed to commit storage batch: {0}"))]
+    FailedToCommitStorageBatch(String),
+    /// Chain constants persisted in the store don't match the expected ones.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Chain constants persisted in the store don't match the expected ones")
+    )]
+    ConstantsMismatch,
+    /// Header number is not the number of the parent header plus one.
+    #[cfg_attr(
+        feature = "thiserror",
+        error("Invalid block number: expected {expected:?}, got {got:?}")
+    )]
+    InvalidBlockNumber {
+        /// Number of the parent header plus one.
+        expected: NumberOf<Header>,
+        /// Number of the header.
+        got: NumberOf<Header>,
+    },
 }
 
-impl<Header: HeaderT> From<DigestError> for ImportError<Header> {
+impl<Header: HeaderT> ImportError<Header> {
+    /// Returns conversion of digest error of the header into import error.
     #[inline]
-    fn from(error: DigestError) -> Self {
-        ImportError::DigestError(error)
+    fn digest_error(header: &Header) -> impl FnOnce(DigestError) -> Self + '_ {
+        move |source| ImportError::DigestError {
+            hash: header.hash(),
+            source,
+        }
+    }
+}
+
+impl<Header: HeaderT> From<ArithmeticError> for ImportError<Header> {
+    fn from(error: ArithmeticError) -> Self {
+        ImportError::ArithmeticError(error.into())
+    }
+}
+
+/// Consensus values of the header derived from its parent header.
+#[derive(Debug, PartialEq)]
+struct DerivedValues {
+    era_start_slot: Slot,
+    should_adjust_solution_range: bool,
+    maybe_current_solution_range_override: Option<SolutionRange>,
+    maybe_next_solution_range_override: Option<SolutionRange>,
+    maybe_root_plot_public_key: Option<FarmerPublicKey>,
+}
+
+impl<Header> From<&HeaderExt<Header>> for DerivedValues {
+    fn from(header_ext: &HeaderExt<Header>) -> Self {
+        DerivedValues {
+            era_start_slot: header_ext.era_start_slot,
+            should_adjust_solution_range: header_ext.should_adjust_solution_range,
+            maybe_current_solution_range_override: header_ext.maybe_current_solution_range_override,
+            maybe_next_solution_range_override: header_ext.maybe_next_solution_range_override,
+            maybe_root_plot_public_key: header_ext.maybe_root_plot_public_key.clone(),
+        }
+    }
+}
+
+/// Challenge derivation used by [`HeaderImporter`] to verify solutions and weigh blocks.
+///
+/// Test chains may provide simplified deterministic implementation, production chains should use
+/// [`SubspaceChallengeDerivation`].
+pub trait ChallengeDerivation {
+    /// Verifies solution against derived global challenge, returns solution distance on success.
+    fn verify_solution(
+        solution: &Solution<FarmerPublicKey, FarmerPublicKey>,
+        slot: SlotNumber,
+        params: &VerifySolutionParams,
+    ) -> Result<SolutionRange, String>;
+
+    /// Calculates weight of the block from its solution range.
+    fn block_weight(solution_range: SolutionRange) -> BlockWeight;
+}
+
+/// Challenge derivation as done by Subspace consensus.
+#[derive(Debug)]
+pub struct SubspaceChallengeDerivation;
+
+impl ChallengeDerivation for SubspaceChallengeDerivation {
+    fn verify_solution(
+        solution: &Solution<FarmerPublicKey, FarmerPublicKey>,
+        slot: SlotNumber,
+        params: &VerifySolutionParams,
+    ) -> Result<SolutionRange, String> {
+        verify_solution(solution.into(), slot, params.into())
+    }
+
+    fn block_weight(solution_range: SolutionRange) -> BlockWeight {
+        calculate_block_weight(solution_range)
+    }
+}
+
+/// Storage mutations staged by the importer that are not committed to the store yet.
+///
+/// Reads done by the importer while staging see the staged mutations, such that all mutations of a
+/// single import can be committed to the store as one batch.
+#[derive(Debug)]
+struct StagedChanges<Header: HeaderT> {
+    ops: Vec<StorageOp<Header>>,
+    headers: BTreeMap<HashOf<Header>, HeaderExt<Header>>,
+    pruned_headers: BTreeSet<HashOf<Header>>,
+    best_header: Option<HeaderExt<Header>>,
+    /// Finalized headers in ascending order of numbers
+    finalized_headers: Vec<HeaderExt<Header>>,
+}
+
+impl<Header: HeaderT> Default for StagedChanges<Header> {
+    fn default() -> Self {
+        Self {
+            ops: Vec::new(),
+            headers: BTreeMap::new(),
+            pruned_headers: BTreeSet::new(),
+            best_header: None,
+            finalized_headers: Vec::new(),
+        }
     }
 }
 
 /// Verifies and import headers.
 #[derive(Debug)]
-pub struct HeaderImporter<Header: HeaderT, Store: Storage<Header>> {
+pub struct HeaderImporter<
+    Header: HeaderT,
+    Store: Storage<Header>,
+    Derivation: ChallengeDerivation = SubspaceChallengeDerivation,
+> {
     store: Store,
-    _phantom: PhantomData<Header>,
+    staged: StagedChanges<Header>,
+    _phantom: PhantomData<(Header, Derivation)>,
 }
 
 impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
@@ -296,12 +631,105 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
     pub fn new(store: Store) -> Self {
         HeaderImporter {
             store,
+            staged: Default::default(),
+            _phantom: Default::default(),
+        }
+    }
+}
+
+impl<Header: HeaderT, Store: Storage<Header>, Derivation: ChallengeDerivation>
+    HeaderImporter<Header, Store, Derivation>
+{
+    /// Returns a new instance of HeaderImporter with provided Storage impls and custom challenge
+    /// derivation.
+    pub fn with_challenge_derivation(store: Store) -> Self {
+        HeaderImporter {
+            store,
+            staged: Default::default(),
             _phantom: Default::default(),
         }
     }
 
     /// Verifies header, computes consensus values for block progress and stores the HeaderExt.
-    pub fn import_header(&mut self, mut header: Header) -> Result<(), ImportError<Header>> {
+    pub fn import_header(&mut self, header: Header) -> Result<(), ImportError<Header>> {
+        self.import_header_internal(header, false)
+    }
+
+    /// Verifies header the same way as [`HeaderImporter::import_header`] and stores it as
+    /// speculative.
+    ///
+    /// Speculative header (and any of its descendants) don't participate in the fork choice until
+    /// confirmed with [`HeaderImporter::confirm_speculative`], e.g. once the same header was
+    /// received from another peer.
+    pub fn import_speculative(&mut self, header: Header) -> Result<(), ImportError<Header>> {
+        self.import_header_internal(header, true)
+    }
+
+    /// Confirms previously imported speculative header together with all of its descendants, which
+    /// makes them participate in the fork choice.
+    pub fn confirm_speculative(&mut self, hash: HashOf<Header>) -> Result<(), ImportError<Header>> {
+        let header_ext = self
+            .store
+            .header(hash)
+            .ok_or(ImportError::MissingHeader(hash))?;
+        if !header_ext.is_speculative {
+            return Err(ImportError::HeaderIsNotSpeculative(hash));
+        }
+
+        let parent_hash = *header_ext.header.parent_hash();
+        let parent_header = self
+            .header_or_finalized(parent_hash)
+            .ok_or(ImportError::MissingParent(hash))?;
+        if parent_header.is_speculative {
+            return Err(ImportError::SpeculativeParent(parent_hash));
+        }
+
+        // finalized header might have moved past the speculative header since it was imported
+        let current_finalized_number = *self.store.finalized_header().header.number();
+        if *header_ext.header.number() <= current_finalized_number {
+            return Err(ImportError::HeaderIsBelowArchivingDepth(
+                HeaderBelowArchivingDepthError {
+                    current_finalized_number,
+                    header_number: *header_ext.header.number(),
+                },
+            ));
+        }
+
+        self.transaction(|importer| {
+            // descendants were speculative only because of this header, the heaviest of the
+            // confirmed headers becomes best if it is heavier than the current best header
+            let mut best_header = importer.best_header()?;
+            let mut is_best_header_changed = false;
+            let descendants = importer.descendants_of_header(&header_ext)?;
+            for mut header_ext in iter::once(header_ext).chain(descendants) {
+                header_ext.is_speculative = false;
+                let is_best_header = header_ext.total_weight > best_header.total_weight;
+                if is_best_header {
+                    best_header = header_ext.clone();
+                    is_best_header_changed = true;
+                }
+
+                importer.stage(StorageOp::StoreHeader {
+                    header_ext,
+                    as_best_header: is_best_header,
+                });
+            }
+
+            // finalize, prune forks, and ensure storage is bounded if the chain has progressed
+            if is_best_header_changed {
+                importer.finalize_header_at_k_depth()?;
+                importer.ensure_storage_bound()?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn import_header_internal(
+        &mut self,
+        mut header: Header,
+        is_speculative: bool,
+    ) -> Result<(), ImportError<Header>> {
         // check if the header is already imported
         match self.store.header(header.hash()) {
             Some(_) => Err(ImportError::HeaderAlreadyImported),
@@ -319,50 +747,341 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
             ));
         }
 
+        // reject header with zeroed extrinsics root if required
+        if self.store.chain_constants().require_extrinsics_root
+            && *header.extrinsics_root() == Default::default()
+        {
+            return Err(ImportError::MissingExtrinsicsRoot);
+        }
+
         // fetch parent header
         let parent_header = self
-            .store
-            .header(*header.parent_hash())
+            .header_or_finalized(*header.parent_hash())
             .ok_or_else(|| ImportError::MissingParent(header.hash()))?;
 
-        // verify global randomness and solution range from the parent header
-        let header_digests = self.verify_header_digest_with_parent(&parent_header, &header)?;
+        // header must extend the parent, otherwise indexing by number gets corrupted
+        let expected_number = parent_header
+            .header
+            .number()
+            .checked_add(&One::one())
+            .ok_or(ArithmeticError::Overflow)?;
+        if *header.number() != expected_number {
+            return Err(ImportError::InvalidBlockNumber {
+                expected: expected_number,
+                got: *header.number(),
+            });
+        }
 
-        // verify next digest items
+        // verify digests and derive consensus values from the parent header
+        let (header_digests, derived_values) =
+            self.derive_consensus_values(&parent_header, &header)?;
         let constants = self.store.chain_constants();
-        let mut maybe_root_plot_public_key = parent_header.maybe_root_plot_public_key;
-        if let Some(root_plot_public_key) = &maybe_root_plot_public_key {
-            if root_plot_public_key != &header_digests.pre_digest.solution().public_key {
-                return Err(ImportError::IncorrectBlockAuthor(
-                    header_digests.pre_digest.solution().public_key.clone(),
-                ));
+
+        // slot must be strictly increasing from the parent header
+        Self::verify_slot(&parent_header, &header_digests.pre_digest)?;
+
+        // slot must not fall too far behind the best chain at the same number
+        if let Some(max_sibling_slot_skew) = constants.max_sibling_slot_skew {
+            self.verify_slot_against_best_chain(
+                &header,
+                &header_digests.pre_digest,
+                max_sibling_slot_skew,
+            )?;
+        }
+
+        // verify block signature and solution
+        self.verify_signature_and_solution(
+            &mut header,
+            &parent_header,
+            &header_digests,
+            &constants,
+        )?;
+
+        let added_weight = Derivation::block_weight(header_digests.solution_range);
+        let total_weight = parent_header.total_weight + added_weight;
+
+        // descendants of the speculative header are speculative as well
+        let is_speculative = is_speculative || parent_header.is_speculative;
+
+        let DerivedValues {
+            era_start_slot,
+            should_adjust_solution_range,
+            maybe_current_solution_range_override,
+            maybe_next_solution_range_override,
+            maybe_root_plot_public_key,
+        } = derived_values;
+        let maybe_slot = Some(header_digests.pre_digest.slot());
+        let maybe_author = Some(header_digests.pre_digest.solution().public_key.clone());
+
+        // store header
+        let header_ext = HeaderExt {
+            header,
+            total_weight,
+            era_start_slot,
+            should_adjust_solution_range,
+            maybe_current_solution_range_override,
+            maybe_next_solution_range_override,
+            maybe_root_plot_public_key,
+            is_speculative,
+            maybe_slot,
+            maybe_author,
+
+            #[cfg(all(test, not(feature = "pot")))]
+            test_overrides: Default::default(),
+        };
+
+        // header and all the mutations caused by it are committed to the store at once
+        self.transaction(|importer| {
+            // last best header should ideally be parent header. if not check for forks and pick the
+            // best chain
+            let last_best_header = importer.best_header()?;
+            let last_best_weight = last_best_header.total_weight;
+            let is_best_header = !is_speculative && total_weight > last_best_weight;
+
+            importer.stage(StorageOp::StoreHeader {
+                header_ext,
+                as_best_header: is_best_header,
+            });
+
+            // finalize, prune forks, and ensure storage is bounded if the chain has progressed
+            if is_best_header {
+                importer.finalize_header_at_k_depth()?;
+                importer.ensure_storage_bound()?;
             }
+
+            Ok(())
+        })
+    }
+
+    /// Walks the canonical chain from `from` to `to` (inclusive), re-derives the consensus values
+    /// of each header from its parent and compares them with the stored ones.
+    ///
+    /// Returns the number of the lowest header that failed the audit along with the error.
+    pub fn audit_chain(
+        &self,
+        from: NumberOf<Header>,
+        to: NumberOf<Header>,
+    ) -> Result<(), (NumberOf<Header>, ImportError<Header>)> {
+        // find the canonical header at `to`
+        let mut header_ext = self.store.best_header();
+        if *header_ext.header.number() < to {
+            return Err((to, ImportError::MissingCanonicalHeader(to)));
+        }
+        while *header_ext.header.number() > to {
+            let parent_hash = *header_ext.header.parent_hash();
+            header_ext = self.header_or_finalized(parent_hash).ok_or((
+                *header_ext.header.number() - One::one(),
+                ImportError::MissingHeader(parent_hash),
+            ))?;
         }
 
-        let mut should_adjust_solution_range = parent_header.should_adjust_solution_range;
-        let mut maybe_next_solution_range_override =
-            parent_header.maybe_next_solution_range_override;
-        verify_next_digests::<Header>(NextDigestsVerificationParams {
-            number: *header.number(),
-            header_digests: &header_digests,
-            #[cfg(not(feature = "pot"))]
-            global_randomness_interval: constants.global_randomness_interval,
-            era_duration: constants.era_duration,
-            slot_probability: constants.slot_probability,
-            era_start_slot: parent_header.era_start_slot,
-            should_adjust_solution_range: &mut should_adjust_solution_range,
-            maybe_next_solution_range_override: &mut maybe_next_solution_range_override,
-            maybe_root_plot_public_key: &mut maybe_root_plot_public_key,
-        })?;
+        // genesis header doesn't have a parent to derive the values from
+        let from = from.max(One::one());
+        let mut maybe_mismatch = None;
+        while *header_ext.header.number() >= from {
+            let number = *header_ext.header.number();
+            let parent_header = self
+                .header_or_finalized(*header_ext.header.parent_hash())
+                .ok_or((number, ImportError::MissingParent(header_ext.header.hash())))?;
+
+            if let Err(error) = self.audit_header(&parent_header, &header_ext) {
+                maybe_mismatch = Some((number, error));
+            }
 
-        // slot must be strictly increasing from the parent header
-        Self::verify_slot(&parent_header.header, &header_digests.pre_digest)?;
+            header_ext = parent_header;
+        }
+
+        match maybe_mismatch {
+            Some(mismatch) => Err(mismatch),
+            None => Ok(()),
+        }
+    }
+
+    /// Re-verifies the block signature and the solution of the canonical headers from `from` to
+    /// `to` (inclusive) using the current chain constants, without modifying the storage.
+    ///
+    /// Returns the hash and the verification result of each header in ascending order of numbers.
+    /// The walk stops at the first header whose parent is missing, which is reported as an error.
+    pub fn reverify_range(
+        &self,
+        from: NumberOf<Header>,
+        to: NumberOf<Header>,
+    ) -> Vec<(HashOf<Header>, Result<(), ImportError<Header>>)> {
+        // genesis header doesn't have a parent to verify against
+        let from = from.max(One::one());
+        let mut results = Vec::new();
+        let mut maybe_header_ext = Some(self.store.best_header());
+        while let Some(header_ext) = maybe_header_ext.take() {
+            if *header_ext.header.number() < from {
+                break;
+            }
+
+            let maybe_parent_header = self.header_or_finalized(*header_ext.header.parent_hash());
+            if *header_ext.header.number() <= to {
+                let result = match &maybe_parent_header {
+                    Some(parent_header) => self.reverify_header(parent_header, &header_ext.header),
+                    None => Err(ImportError::MissingParent(header_ext.header.hash())),
+                };
+                results.push((header_ext.header.hash(), result));
+            }
+
+            maybe_header_ext = maybe_parent_header;
+        }
+
+        results.reverse();
+        results
+    }
+
+    /// Verifies that the chain constants persisted in the store match the `constants` the client
+    /// is configured with, e.g. after the store is reopened.
+    ///
+    /// Importing headers with different constants would silently change the consensus rules, so
+    /// the client should call this before importing any headers into a reopened store.
+    pub fn verify_constants(
+        &self,
+        constants: &ChainConstants<Header>,
+    ) -> Result<(), ImportError<Header>> {
+        if self.store.chain_constants() != *constants {
+            return Err(ImportError::ConstantsMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the best and finalized heads along with the number of stored headers.
+    pub fn chain_info(&self) -> ChainInfo<Header> {
+        let best_header = self.store.best_header();
+        let finalized_header = self.store.finalized_header();
+        ChainInfo {
+            best_number: *best_header.header.number(),
+            best_hash: best_header.header.hash(),
+            best_total_weight: best_header.total_weight,
+            finalized_number: *finalized_header.header.number(),
+            finalized_hash: finalized_header.header.hash(),
+            header_count: self.store.header_count(),
+        }
+    }
 
+    /// Returns the tips of all the forks above the finalized header, i.e. the headers that are not
+    /// the parent of any other stored header, sorted by total weight in descending order.
+    ///
+    /// The finalized header is the only tip if there are no headers above it.
+    pub fn tips(&self) -> Vec<HeaderExt<Header>> {
+        let mut tips = Vec::new();
+        let finalized_header = self.store.finalized_header();
+        let mut number = *finalized_header.header.number();
+        let mut headers = vec![finalized_header];
+        while !headers.is_empty() {
+            number = match number.checked_add(&One::one()) {
+                Some(number) => number,
+                None => {
+                    tips.extend(headers);
+                    break;
+                }
+            };
+            let children = self.store.headers_at_number(number);
+            let parents = children
+                .iter()
+                .map(|child| *child.header.parent_hash())
+                .collect::<BTreeSet<_>>();
+            tips.extend(
+                headers
+                    .into_iter()
+                    .filter(|header| !parents.contains(&header.header.hash())),
+            );
+            headers = children;
+        }
+
+        tips.sort_by(|a, b| b.total_weight.cmp(&a.total_weight));
+        tips
+    }
+
+    /// Returns the lowest common ancestor of the headers `a` and `b`, which is the header itself
+    /// if one header is the ancestor of the other.
+    ///
+    /// The search doesn't go beyond the finalized header since there are no forks below it, the
+    /// finalized header is found even if the store already pruned it.
+    /// Returns `None` if either header or any of their ancestors up to the common one are missing.
+    pub fn common_ancestor(
+        &self,
+        a: HashOf<Header>,
+        b: HashOf<Header>,
+    ) -> Option<HeaderExt<Header>> {
+        let finalized_number = *self.store.finalized_header().header.number();
+        let mut a = self.header_or_finalized(a)?;
+        let mut b = self.header_or_finalized(b)?;
+
+        // walk the higher header down to the number of the lower one
+        while *a.header.number() > *b.header.number() {
+            a = self.header_or_finalized(*a.header.parent_hash())?;
+        }
+        while *b.header.number() > *a.header.number() {
+            b = self.header_or_finalized(*b.header.parent_hash())?;
+        }
+
+        // walk both chains in lockstep until they meet
+        loop {
+            if a.header.hash() == b.header.hash() {
+                return Some(a);
+            }
+
+            if *a.header.number() <= finalized_number {
+                return None;
+            }
+
+            a = self.header_or_finalized(*a.header.parent_hash())?;
+            b = self.header_or_finalized(*b.header.parent_hash())?;
+        }
+    }
+
+    /// Returns the header at `number` on the chain of the current best header.
+    ///
+    /// Returns `None` if `number` is above the best header or the header is no longer available.
+    pub fn best_chain_header_at(&self, number: NumberOf<Header>) -> Option<HeaderExt<Header>> {
+        let best_hash = self.store.best_header().header.hash();
+        self.find_ancestor_of_header_at_number(best_hash, number)
+    }
+
+    fn audit_header(
+        &self,
+        parent_header: &HeaderExt<Header>,
+        header_ext: &HeaderExt<Header>,
+    ) -> Result<(), ImportError<Header>> {
+        let (_header_digests, derived_values) =
+            self.derive_consensus_values(parent_header, &header_ext.header)?;
+        if derived_values != DerivedValues::from(header_ext) {
+            return Err(ImportError::InvalidDerivedValues(header_ext.header.hash()));
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the header digests, the block signature and the solution of an already imported
+    /// header against its parent header.
+    fn reverify_header(
+        &self,
+        parent_header: &HeaderExt<Header>,
+        header: &Header,
+    ) -> Result<(), ImportError<Header>> {
+        let header_digests = self.verify_header_digest_with_parent(parent_header, header)?;
+        self.verify_signature_and_solution(
+            &mut header.clone(),
+            parent_header,
+            &header_digests,
+            &self.store.chain_constants(),
+        )
+    }
+
+    /// Verifies the block signature and the solution of the header against its parent header.
+    fn verify_signature_and_solution(
+        &self,
+        header: &mut Header,
+        parent_header: &HeaderExt<Header>,
+        header_digests: &SubspaceDigestItems<FarmerPublicKey, FarmerPublicKey, FarmerSignature>,
+        constants: &ChainConstants<Header>,
+    ) -> Result<(), ImportError<Header>> {
         // verify block signature
-        Self::verify_block_signature(
-            &mut header,
-            &header_digests.pre_digest.solution().public_key,
-        )?;
+        Self::verify_block_signature(header, &header_digests.pre_digest.solution().public_key)?;
 
         // verify solution
         let sector_id = SectorId::new(
@@ -401,10 +1120,10 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
                 parent_header.header.hash(),
             )?;
 
-        verify_solution(
-            header_digests.pre_digest.solution().into(),
+        Derivation::verify_solution(
+            header_digests.pre_digest.solution(),
             header_digests.pre_digest.slot().into(),
-            (&VerifySolutionParams {
+            &VerifySolutionParams {
                 #[cfg(not(feature = "pot"))]
                 global_randomness: header_digests.global_randomness,
                 #[cfg(feature = "pot")]
@@ -419,21 +1138,59 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
                     current_history_size,
                     sector_expiration_check_segment_commitment,
                 }),
-            })
-                .into(),
+            },
         )
         .map_err(ImportError::InvalidSolution)?;
 
-        let added_weight = calculate_block_weight(header_digests.solution_range);
-        let total_weight = parent_header.total_weight + added_weight;
+        Ok(())
+    }
 
-        // last best header should ideally be parent header. if not check for forks and pick the best chain
-        let last_best_header = self.store.best_header();
-        let last_best_weight = last_best_header.total_weight;
-        let is_best_header = total_weight > last_best_weight;
+    /// Verifies the header digests against the parent header and derives the consensus values of
+    /// the header.
+    fn derive_consensus_values(
+        &self,
+        parent_header: &HeaderExt<Header>,
+        header: &Header,
+    ) -> Result<
+        (
+            SubspaceDigestItems<FarmerPublicKey, FarmerPublicKey, FarmerSignature>,
+            DerivedValues,
+        ),
+        ImportError<Header>,
+    > {
+        // verify global randomness and solution range from the parent header
+        let header_digests = self.verify_header_digest_with_parent(parent_header, header)?;
+
+        // verify next digest items
+        let constants = self.store.chain_constants();
+        let mut maybe_root_plot_public_key = parent_header.maybe_root_plot_public_key.clone();
+        if let Some(root_plot_public_key) = &maybe_root_plot_public_key {
+            if root_plot_public_key != &header_digests.pre_digest.solution().public_key {
+                return Err(ImportError::IncorrectBlockAuthor(
+                    header_digests.pre_digest.solution().public_key.clone(),
+                ));
+            }
+        }
+
+        let mut should_adjust_solution_range = parent_header.should_adjust_solution_range;
+        let mut maybe_next_solution_range_override =
+            parent_header.maybe_next_solution_range_override;
+        verify_next_digests::<Header>(NextDigestsVerificationParams {
+            number: *header.number(),
+            header_digests: &header_digests,
+            #[cfg(not(feature = "pot"))]
+            global_randomness_interval: constants.global_randomness_interval,
+            era_duration: constants.era_duration,
+            slot_probability: constants.slot_probability,
+            era_start_slot: parent_header.era_start_slot,
+            should_adjust_solution_range: &mut should_adjust_solution_range,
+            maybe_next_solution_range_override: &mut maybe_next_solution_range_override,
+            maybe_root_plot_public_key: &mut maybe_root_plot_public_key,
+        })
+        .map_err(ImportError::digest_error(header))?;
 
         // check if era has changed
-        let era_start_slot = if Self::has_era_changed(&header, constants.era_duration) {
+        let era_start_slot = if Self::has_era_changed(header, constants.era_duration) {
             header_digests.pre_digest.slot()
         } else {
             parent_header.era_start_slot
@@ -452,34 +1209,21 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
 
         // check if the era has changed and there is a current solution range override, reset it
         if maybe_current_solution_range_override.is_some()
-            && Self::has_era_changed(&header, constants.era_duration)
+            && Self::has_era_changed(header, constants.era_duration)
         {
             maybe_current_solution_range_override = None
         }
 
-        // store header
-        let header_ext = HeaderExt {
-            header,
-            total_weight,
-            era_start_slot,
-            should_adjust_solution_range,
-            maybe_current_solution_range_override,
-            maybe_next_solution_range_override,
-            maybe_root_plot_public_key,
-
-            #[cfg(all(test, not(feature = "pot")))]
-            test_overrides: Default::default(),
-        };
-
-        self.store.store_header(header_ext, is_best_header);
-
-        // finalize, prune forks, and ensure storage is bounded if the chain has progressed
-        if is_best_header {
-            self.finalize_header_at_k_depth()?;
-            self.ensure_storage_bound();
-        }
-
-        Ok(())
+        Ok((
+            header_digests,
+            DerivedValues {
+                era_start_slot,
+                should_adjust_solution_range,
+                maybe_current_solution_range_override,
+                maybe_next_solution_range_override,
+                maybe_root_plot_public_key,
+            },
+        ))
     }
 
     fn has_era_changed(header: &Header, era_duration: NumberOf<Header>) -> bool {
@@ -499,7 +1243,8 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
         ImportError<Header>,
     > {
         // extract digest items from the header
-        let pre_digest_items = extract_subspace_digest_items(header)?;
+        let pre_digest_items =
+            extract_subspace_digest_items(header).map_err(ImportError::digest_error(header))?;
         // extract next digest items from the parent header
         let next_digest_items = {
             // if the header we are verifying is #1, then parent header, genesis, wont have the next digests
@@ -528,30 +1273,51 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
 
     /// Verifies that slot present in the header is strictly increasing from the slot in the parent.
     fn verify_slot(
-        parent_header: &Header,
+        parent_header: &HeaderExt<Header>,
         pre_digest: &PreDigest<FarmerPublicKey, FarmerPublicKey>,
     ) -> Result<(), ImportError<Header>> {
-        let parent_pre_digest = extract_pre_digest(parent_header)?;
-
-        if pre_digest.slot() <= parent_pre_digest.slot() {
+        if pre_digest.slot() <= parent_header.slot()? {
             return Err(ImportError::InvalidSlot);
         }
 
         Ok(())
     }
 
+    /// Verifies that the slot of the header is at most `max_sibling_slot_skew` slots behind the
+    /// slot of the best chain header at the same number, if there is one.
+    fn verify_slot_against_best_chain(
+        &self,
+        header: &Header,
+        pre_digest: &PreDigest<FarmerPublicKey, FarmerPublicKey>,
+        max_sibling_slot_skew: SlotNumber,
+    ) -> Result<(), ImportError<Header>> {
+        let best_hash = self.store.best_header().header.hash();
+        let Some(best_chain_header) =
+            self.find_ancestor_of_header_at_number(best_hash, *header.number())
+        else {
+            return Ok(());
+        };
+
+        let best_chain_slot = u64::from(best_chain_header.slot()?);
+        if u64::from(pre_digest.slot()).saturating_add(max_sibling_slot_skew) < best_chain_slot {
+            return Err(ImportError::SlotInconsistentWithSiblings);
+        }
+
+        Ok(())
+    }
+
     /// Verifies the block signature present in the last digest log.
     fn verify_block_signature(
         header: &mut Header,
         public_key: &FarmerPublicKey,
     ) -> Result<(), ImportError<Header>> {
-        let seal =
-            header
-                .digest_mut()
-                .pop()
-                .ok_or(ImportError::DigestError(DigestError::Missing(
-                    ErrorDigestType::Seal,
-                )))?;
+        let seal = header
+            .digest_mut()
+            .pop()
+            .ok_or_else(|| ImportError::DigestError {
+                hash: header.hash(),
+                source: DigestError::Missing(ErrorDigestType::Seal),
+            })?;
 
         let signature = seal
             .as_subspace_seal()
@@ -580,14 +1346,14 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
         hash: HashOf<Header>,
         ancestor_number: NumberOf<Header>,
     ) -> Option<HeaderExt<Header>> {
-        let header = self.store.header(hash)?;
+        let header = self.header(hash)?;
 
         // header number must be greater than the ancestor number
         if *header.header.number() < ancestor_number {
             return None;
         }
 
-        let headers_at_ancestor_number = self.store.headers_at_number(ancestor_number);
+        let headers_at_ancestor_number = self.headers_at_number(ancestor_number);
 
         // short circuit if there are no fork headers at the ancestor number
         if headers_at_ancestor_number.len() == 1 {
@@ -597,7 +1363,7 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
         // start tree route till the ancestor
         let mut header = header;
         while *header.header.number() > ancestor_number {
-            header = self.store.header(*header.header.parent_hash())?;
+            header = self.header(*header.header.parent_hash())?;
         }
 
         Some(header)
@@ -608,43 +1374,56 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
         &mut self,
         header: HeaderExt<Header>,
     ) -> Result<(), ImportError<Header>> {
+        let descendants = self.descendants_of_header(&header)?;
+
         // prune the header
-        self.store.prune_header(header.header.hash());
+        self.stage(StorageOp::PruneHeader(header.header.hash()));
+
+        // prune the descendant headers
+        for descendant in descendants {
+            self.stage(StorageOp::PruneHeader(descendant.header.hash()));
+        }
 
-        // start pruning all the descendant headers from the current header
+        Ok(())
+    }
+
+    /// Returns all the descendants of the header in ascending order of numbers.
+    fn descendants_of_header(
+        &self,
+        header: &HeaderExt<Header>,
+    ) -> Result<Vec<HeaderExt<Header>>, ImportError<Header>> {
+        // collect all the descendant headers from the current header
         //        header(at number n)
         //        /         \
         //  descendant-1   descendant-2
         //     /
         //  descendant-3
-        let mut pruned_parent_hashes = vec![header.header.hash()];
+        let mut descendants = vec![];
+        let mut parent_hashes = vec![header.header.hash()];
         let mut current_number = *header.header.number();
 
-        while !pruned_parent_hashes.is_empty() {
+        while !parent_hashes.is_empty() {
             current_number = current_number
                 .checked_add(&One::one())
-                .ok_or(ImportError::ArithmeticError(ArithmeticError::Overflow))?;
+                .ok_or(ArithmeticError::Overflow)?;
 
-            // get headers at the current number and filter the headers descended from the pruned parents
-            let descendant_header_hashes = self
-                .store
+            // get headers at the current number and filter the headers descended from the parents
+            let descendant_headers = self
                 .headers_at_number(current_number)
                 .into_iter()
                 .filter(|descendant_header| {
-                    pruned_parent_hashes.contains(descendant_header.header.parent_hash())
+                    parent_hashes.contains(descendant_header.header.parent_hash())
                 })
-                .map(|header| header.header.hash())
-                .collect::<Vec<HashOf<Header>>>();
+                .collect::<Vec<HeaderExt<Header>>>();
 
-            // prune the descendant headers
-            descendant_header_hashes
+            parent_hashes = descendant_headers
                 .iter()
-                .for_each(|hash| self.store.prune_header(*hash));
-
-            pruned_parent_hashes = descendant_header_hashes;
+                .map(|header| header.header.hash())
+                .collect();
+            descendants.extend(descendant_headers);
         }
 
-        Ok(())
+        Ok(descendants)
     }
 
     /// Returns the total pieces on chain where chain_tip is the hash of the tip of the chain.
@@ -671,8 +1450,7 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
 
         // calculate segment count present in each header from header till finalized header
         let mut header = self
-            .store
-            .header(chain_tip)
+            .header_or_finalized(chain_tip)
             .ok_or(ImportError::MissingHeader(chain_tip))?;
 
         while header.header.hash() != finalized_header.header.hash() {
@@ -681,12 +1459,12 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
                 FarmerPublicKey,
                 FarmerPublicKey,
                 FarmerSignature,
-            >(&header.header)?;
+            >(&header.header)
+            .map_err(ImportError::digest_error(&header.header))?;
             segment_commitments_count += digest_items.segment_commitments.len() as u64;
 
             header = self
-                .store
-                .header(*header.header.parent_hash())
+                .header_or_finalized(*header.header.parent_hash())
                 .ok_or_else(|| ImportError::MissingParent(header.header.hash()))?;
         }
 
@@ -722,8 +1500,7 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
         // find the segment commitment from the headers which are not finalized yet.
         let finalized_header = self.store.finalized_header();
         let mut header = self
-            .store
-            .header(chain_tip)
+            .header_or_finalized(chain_tip)
             .ok_or(ImportError::MissingHeader(chain_tip))?;
 
         while header.header.hash() != finalized_header.header.hash() {
@@ -732,37 +1509,41 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
                 FarmerPublicKey,
                 FarmerPublicKey,
                 FarmerSignature,
-            >(&header.header)?;
+            >(&header.header)
+            .map_err(ImportError::digest_error(&header.header))?;
 
             if let Some(segment_commitment) = digest_items.segment_commitments.get(&segment_index) {
                 return Ok(Some(*segment_commitment));
             }
 
             header = self
-                .store
-                .header(*header.header.parent_hash())
+                .header_or_finalized(*header.header.parent_hash())
                 .ok_or_else(|| ImportError::MissingParent(header.header.hash()))?;
         }
 
         Ok(None)
     }
 
-    /// Stores finalized header and segment commitments present in the header.
+    /// Stages finalized header and segment commitments present in the header, finality is notified
+    /// once staged mutations are committed.
     fn store_finalized_header_and_segment_commitments(
         &mut self,
-        header: &Header,
+        header_ext: &HeaderExt<Header>,
     ) -> Result<(), ImportError<Header>> {
+        let header = &header_ext.header;
         let digests_items =
             extract_subspace_digest_items::<_, FarmerPublicKey, FarmerPublicKey, FarmerSignature>(
                 header,
-            )?;
+            )
+            .map_err(ImportError::digest_error(header))?;
 
-        // mark header as finalized
-        self.store.finalize_header(header.hash());
+        // mark header as finalized and store the segment commitments present in the header digests
+        self.stage(StorageOp::FinalizeHeader(header.hash()));
+        self.stage(StorageOp::StoreSegmentCommitments(
+            digests_items.segment_commitments,
+        ));
+        self.staged.finalized_headers.push(header_ext.clone());
 
-        // store the segment commitments present in the header digests
-        self.store
-            .store_segment_commitments(digests_items.segment_commitments);
         Ok(())
     }
 
@@ -775,12 +1556,11 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
     ///    fail and let user know.
     fn finalize_header_at_k_depth(&mut self) -> Result<(), ImportError<Header>> {
         let k_depth = self.store.chain_constants().k_depth;
-        let current_finalized_header = self.store.finalized_header();
+        let current_finalized_header = self.finalized_header();
 
         // ensure we have imported at least K-depth number of headers
         let number_to_finalize = match self
-            .store
-            .best_header()
+            .staged_best_header()
             .header
             .number()
             .checked_sub(&k_depth)
@@ -810,25 +1590,23 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
                 while current_finalized_number < number_to_finalize {
                     current_finalized_number = current_finalized_number
                         .checked_add(&One::one())
-                        .ok_or(ImportError::ArithmeticError(ArithmeticError::Overflow))?;
+                        .ok_or(ArithmeticError::Overflow)?;
 
                     // find the headers at the number to be finalized
                     let headers_at_number_to_be_finalized =
-                        self.store.headers_at_number(current_finalized_number);
+                        self.headers_at_number(current_finalized_number);
                     // if there is just one header at that number, we mark that header as finalized and move one
                     if headers_at_number_to_be_finalized.len() == 1 {
                         let header_to_finalize = headers_at_number_to_be_finalized
                             .first()
                             .expect("First item must exist as the len is 1.");
 
-                        self.store_finalized_header_and_segment_commitments(
-                            &header_to_finalize.header,
-                        )?
+                        self.store_finalized_header_and_segment_commitments(header_to_finalize)?
                     } else {
                         // there are multiple headers at the number to be finalized.
                         // find the correct ancestor header of the current best header.
                         // finalize it and prune all the remaining fork headers.
-                        let current_best_header = self.store.best_header();
+                        let current_best_header = self.staged_best_header();
                         let (current_best_hash, current_best_number) = (
                             current_best_header.header.hash(),
                             *current_best_header.header.number(),
@@ -857,9 +1635,7 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
                         }
 
                         // mark the header as finalized
-                        self.store_finalized_header_and_segment_commitments(
-                            &header_to_finalize.header,
-                        )?
+                        self.store_finalized_header_and_segment_commitments(&header_to_finalize)?
                     }
                 }
 
@@ -874,39 +1650,202 @@ impl<Header: HeaderT, Store: Storage<Header>> HeaderImporter<Header, Store> {
     /// beyond K depth as per bounded value.
     /// If finalized head is at x and storage is bounded to keep y headers beyond, then
     /// prune all headers at and below (x - y - 1)
-    fn ensure_storage_bound(&mut self) {
+    fn ensure_storage_bound(&mut self) -> Result<(), ImportError<Header>> {
         let storage_bound = self.store.chain_constants().storage_bound;
         let number_of_headers_to_keep_beyond_k_depth = match storage_bound {
             // unbounded storage, so return
-            StorageBound::Unbounded => return,
+            StorageBound::Unbounded => return Ok(()),
             // bounded storage, keep only # number of headers beyond K depth
             StorageBound::NumberOfHeaderToKeepBeyondKDepth(number_of_headers_to_keep) => {
                 number_of_headers_to_keep
             }
         };
 
-        let finalized_head_number = *self.store.finalized_header().header.number();
+        let finalized_head_number = *self.finalized_header().header.number();
         // (finalized_number - bound_value - 1)
         let mut maybe_prune_headers_from_number = finalized_head_number
             .checked_sub(&number_of_headers_to_keep_beyond_k_depth)
             .and_then(|number| number.checked_sub(&One::one()));
 
         let mut headers_to_prune = maybe_prune_headers_from_number
-            .map(|number| self.store.headers_at_number(number))
+            .map(|number| self.headers_at_number(number))
             .unwrap_or_default();
 
         while !headers_to_prune.is_empty() {
             // loop and prune even though there should be only 1 head beyond finalized head
             for header in headers_to_prune {
-                self.store.prune_header(header.header.hash())
+                self.stage(StorageOp::PruneHeader(header.header.hash()));
             }
 
             maybe_prune_headers_from_number =
                 maybe_prune_headers_from_number.and_then(|number| number.checked_sub(&One::one()));
 
             headers_to_prune = maybe_prune_headers_from_number
-                .map(|number| self.store.headers_at_number(number))
+                .map(|number| self.headers_at_number(number))
                 .unwrap_or_default();
         }
+
+        Ok(())
+    }
+
+    /// Returns the header with the given hash.
+    /// The finalized header is always known to the store, so it is returned even if the store
+    /// already pruned it from the headers.
+    fn header_or_finalized(&self, hash: HashOf<Header>) -> Option<HeaderExt<Header>> {
+        self.header(hash).or_else(|| {
+            let finalized_header = self.finalized_header();
+            (finalized_header.header.hash() == hash).then_some(finalized_header)
+        })
+    }
+
+    /// Returns the best header of the store.
+    /// If the best header was pruned, the heaviest non-speculative header from the finalized head
+    /// upwards is marked as the new best header instead, so that fork choice never compares against
+    /// a dangling header.
+    fn best_header(&mut self) -> Result<HeaderExt<Header>, ImportError<Header>> {
+        let best_header = self.staged_best_header();
+        if self.header(best_header.header.hash()).is_some() {
+            return Ok(best_header);
+        }
+
+        let mut best_header = self.finalized_header();
+        let mut number = *best_header.header.number();
+        loop {
+            number = number
+                .checked_add(&One::one())
+                .ok_or(ArithmeticError::Overflow)?;
+
+            let headers_at_number = self.headers_at_number(number);
+            if headers_at_number.is_empty() {
+                break;
+            }
+
+            for header in headers_at_number {
+                if !header.is_speculative && header.total_weight > best_header.total_weight {
+                    best_header = header;
+                }
+            }
+        }
+
+        // only the best pointer is updated, the finalized header may have been pruned already
+        self.stage(StorageOp::SetBestHeader(best_header.header.hash()));
+
+        Ok(best_header)
+    }
+
+    /// Runs `f` that stages storage mutations, commits all of them to the store as a single batch
+    /// if `f` succeeds and drops them otherwise.
+    ///
+    /// Finality is notified after the batch is committed.
+    fn transaction<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<R, ImportError<Header>>,
+    ) -> Result<R, ImportError<Header>> {
+        let result = f(self);
+        let staged = mem::take(&mut self.staged);
+        let value = result?;
+
+        if !staged.ops.is_empty() {
+            self.store
+                .commit_batch(staged.ops)
+                .map_err(ImportError::FailedToCommitStorageBatch)?;
+        }
+
+        for header_ext in &staged.finalized_headers {
+            self.store.on_finalize(header_ext);
+        }
+
+        Ok(value)
+    }
+
+    /// Stages storage mutation to be committed at the end of the transaction.
+    fn stage(&mut self, op: StorageOp<Header>) {
+        match &op {
+            StorageOp::StoreHeader {
+                header_ext,
+                as_best_header,
+            } => {
+                let hash = header_ext.header.hash();
+                self.staged.pruned_headers.remove(&hash);
+                self.staged.headers.insert(hash, header_ext.clone());
+                if *as_best_header {
+                    self.staged.best_header = Some(header_ext.clone());
+                }
+            }
+            StorageOp::SetBestHeader(hash) => {
+                let best_header = self.header_or_finalized(*hash);
+                self.staged.best_header = best_header;
+            }
+            StorageOp::PruneHeader(hash) => {
+                // header is pruned already as a descendant of another pruned header
+                if !self.staged.pruned_headers.insert(*hash) {
+                    return;
+                }
+                self.staged.headers.remove(hash);
+            }
+            StorageOp::FinalizeHeader(_) | StorageOp::StoreSegmentCommitments(_) => {}
+        }
+
+        self.staged.ops.push(op);
+    }
+
+    /// Returns the header with the given hash, taking staged mutations into account.
+    fn header(&self, hash: HashOf<Header>) -> Option<HeaderExt<Header>> {
+        if self.staged.pruned_headers.contains(&hash) {
+            return None;
+        }
+
+        self.staged
+            .headers
+            .get(&hash)
+            .cloned()
+            .or_else(|| self.store.header(hash))
+    }
+
+    /// Returns headers at a given number, taking staged mutations into account.
+    fn headers_at_number(&self, number: NumberOf<Header>) -> Vec<HeaderExt<Header>> {
+        let mut headers = self
+            .store
+            .headers_at_number(number)
+            .into_iter()
+            .filter(|header| !self.staged.pruned_headers.contains(&header.header.hash()))
+            .map(|header| {
+                self.staged
+                    .headers
+                    .get(&header.header.hash())
+                    .cloned()
+                    .unwrap_or(header)
+            })
+            .collect::<Vec<_>>();
+
+        for staged_header in self.staged.headers.values() {
+            if *staged_header.header.number() == number
+                && !headers
+                    .iter()
+                    .any(|header| header.header.hash() == staged_header.header.hash())
+            {
+                headers.push(staged_header.clone());
+            }
+        }
+
+        headers
+    }
+
+    /// Returns the best header marked in the store, taking staged mutations into account.
+    /// Note: the returned header may have been pruned, see [`Self::best_header`].
+    fn staged_best_header(&self) -> HeaderExt<Header> {
+        self.staged
+            .best_header
+            .clone()
+            .unwrap_or_else(|| self.store.best_header())
+    }
+
+    /// Returns the latest finalized header, taking staged mutations into account.
+    fn finalized_header(&self) -> HeaderExt<Header> {
+        self.staged
+            .finalized_headers
+            .last()
+            .cloned()
+            .unwrap_or_else(|| self.store.finalized_header())
     }
 }
@@ -1,4 +1,4 @@
-use crate::{ChainConstants, HashOf, HeaderExt, NumberOf, Storage};
+use crate::{ChainConstants, HashOf, HeaderExt, NumberOf, Storage, StorageOp};
 use codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use sp_arithmetic::traits::Zero;
@@ -26,14 +26,20 @@ pub(crate) fn kzg_instance() -> &'static Kzg {
     KZG.get_or_init(|| Kzg::new(embedded_kzg_settings()))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct StorageData {
     constants: ChainConstants<Header>,
     headers: HashMap<HashOf<Header>, HeaderExt<Header>>,
     number_to_hashes: HashMap<NumberOf<Header>, Vec<HashOf<Header>>>,
     best_header: (NumberOf<Header>, HashOf<Header>),
+    // last known state of the best header if it was pruned
+    pruned_best_header: Option<HeaderExt<Header>>,
+    // finalized header metadata kept after the finalized header was pruned
+    pruned_finalized_header: Option<HeaderExt<Header>>,
     finalized_head: Option<(NumberOf<Header>, HashOf<Header>)>,
     segment_commitments: BTreeMap<SegmentIndex, SegmentCommitment>,
+    finalized_head_advances: Vec<(NumberOf<Header>, HashOf<Header>)>,
+    fail_commit_batch_at_op: Option<usize>,
 }
 
 #[derive(Default, Debug, Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
@@ -67,13 +73,36 @@ impl Storage<Header> for MockStorage {
             self.0.number_to_hashes.insert(number, set);
         }
         if as_best_header {
-            self.0.best_header = (number, hash)
+            self.0.best_header = (number, hash);
+            self.0.pruned_best_header = None;
         }
     }
 
     fn best_header(&self) -> HeaderExt<Header> {
         let (_, hash) = self.0.best_header;
-        self.0.headers.get(&hash).cloned().unwrap()
+        self.0
+            .headers
+            .get(&hash)
+            .or(self.0.pruned_best_header.as_ref())
+            .cloned()
+            .unwrap()
+    }
+
+    fn set_best_header(&mut self, hash: HashOf<Header>) {
+        let number = match self.0.headers.get(&hash) {
+            Some(header_ext) => {
+                self.0.pruned_best_header = None;
+                *header_ext.header.number()
+            }
+            None => {
+                let finalized_header = self.finalized_header();
+                assert_eq!(finalized_header.header.hash(), hash);
+                let number = *finalized_header.header.number();
+                self.0.pruned_best_header = Some(finalized_header);
+                number
+            }
+        };
+        self.0.best_header = (number, hash);
     }
 
     fn headers_at_number(&self, number: NumberOf<Header>) -> Vec<HeaderExt<Header>> {
@@ -88,6 +117,13 @@ impl Storage<Header> for MockStorage {
 
     fn prune_header(&mut self, pruned_hash: HashOf<Header>) {
         if let Some(pruned_header) = self.0.headers.remove(&pruned_hash) {
+            if self.0.best_header.1 == pruned_hash {
+                self.0.pruned_best_header = Some(pruned_header.clone());
+            }
+            if matches!(self.0.finalized_head, Some((_, hash)) if hash == pruned_hash) {
+                self.0.pruned_finalized_header = Some(pruned_header.clone());
+            }
+
             let number_to_hashes = self
                 .0
                 .number_to_hashes
@@ -105,13 +141,20 @@ impl Storage<Header> for MockStorage {
 
     fn finalize_header(&mut self, hash: HashOf<Header>) {
         let header = self.0.headers.get(&hash).unwrap();
-        self.0.finalized_head = Some((*header.header.number(), header.header.hash()))
+        self.0.finalized_head = Some((*header.header.number(), header.header.hash()));
+        self.0.pruned_finalized_header = None;
     }
 
     fn finalized_header(&self) -> HeaderExt<Header> {
         self.0
             .finalized_head
-            .and_then(|(_, hash)| self.0.headers.get(&hash).cloned())
+            .and_then(|(_, hash)| {
+                self.0
+                    .headers
+                    .get(&hash)
+                    .or(self.0.pruned_finalized_header.as_ref())
+                    .cloned()
+            })
             .unwrap_or_else(|| {
                 self.0
                     .headers
@@ -147,6 +190,43 @@ impl Storage<Header> for MockStorage {
     fn max_pieces_in_sector(&self) -> u16 {
         MAX_PIECES_IN_SECTOR
     }
+
+    fn header_count(&self) -> u64 {
+        self.0.headers.len() as u64
+    }
+
+    fn on_finalize(&mut self, header_ext: &HeaderExt<Header>) {
+        self.0
+            .finalized_head_advances
+            .push((*header_ext.header.number(), header_ext.header.hash()))
+    }
+
+    fn commit_batch(&mut self, ops: Vec<StorageOp<Header>>) -> Result<(), String> {
+        // operations are applied one by one like in a database transaction, which is rolled back if
+        // any of them fails
+        let data_before_batch = self.0.clone();
+        for (op_index, op) in ops.into_iter().enumerate() {
+            if self.0.fail_commit_batch_at_op == Some(op_index) {
+                self.0 = data_before_batch;
+                return Err(format!("Failed to apply operation {op_index}"));
+            }
+
+            match op {
+                StorageOp::StoreHeader {
+                    header_ext,
+                    as_best_header,
+                } => self.store_header(header_ext, as_best_header),
+                StorageOp::SetBestHeader(hash) => self.set_best_header(hash),
+                StorageOp::PruneHeader(hash) => self.prune_header(hash),
+                StorageOp::FinalizeHeader(hash) => self.finalize_header(hash),
+                StorageOp::StoreSegmentCommitments(segment_commitments) => {
+                    self.store_segment_commitments(segment_commitments)
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl MockStorage {
@@ -156,20 +236,45 @@ impl MockStorage {
             headers: Default::default(),
             number_to_hashes: Default::default(),
             best_header: (Default::default(), Default::default()),
+            pruned_best_header: None,
+            pruned_finalized_header: None,
             finalized_head: None,
             segment_commitments: Default::default(),
+            finalized_head_advances: Default::default(),
+            fail_commit_batch_at_op: None,
         })
     }
 
+    // finalized heads reported through `Storage::on_finalize`
+    pub(crate) fn finalized_head_advances(&self) -> &[(NumberOf<Header>, HashOf<Header>)] {
+        &self.0.finalized_head_advances
+    }
+
+    // makes batches with more than `op_index` operations fail at the operation with `op_index`
+    pub(crate) fn fail_commit_batch_at_op(&mut self, maybe_op_index: Option<usize>) {
+        self.0.fail_commit_batch_at_op = maybe_op_index;
+    }
+
+    // stored header or the pruned finalized header with the given hash
+    fn header_mut(&mut self, hash: HashOf<Header>) -> &mut HeaderExt<Header> {
+        if self.0.headers.contains_key(&hash) {
+            return self.0.headers.get_mut(&hash).unwrap();
+        }
+
+        self.0
+            .pruned_finalized_header
+            .as_mut()
+            .filter(|header| header.header.hash() == hash)
+            .unwrap()
+    }
+
     // hack to adjust the solution range
     pub(crate) fn override_solution_range(
         &mut self,
         hash: HashOf<Header>,
         solution_range: SolutionRange,
     ) {
-        let mut header = self.0.headers.remove(&hash).unwrap();
-        header.test_overrides.solution_range = Some(solution_range);
-        self.0.headers.insert(hash, header);
+        self.header_mut(hash).test_overrides.solution_range = Some(solution_range);
     }
 
     // hack to adjust the next solution range
@@ -178,9 +283,7 @@ impl MockStorage {
         hash: HashOf<Header>,
         next_solution_range: SolutionRange,
     ) {
-        let mut header = self.0.headers.remove(&hash).unwrap();
-        header.test_overrides.next_solution_range = Some(next_solution_range);
-        self.0.headers.insert(hash, header);
+        self.header_mut(hash).test_overrides.next_solution_range = Some(next_solution_range);
     }
 
     // hack to adjust constants when importing Block #1
@@ -188,11 +291,19 @@ impl MockStorage {
         self.0.constants = constants;
     }
 
+    // hack to replace the stored header while keeping the hash it is stored under
+    pub(crate) fn override_header(&mut self, hash: HashOf<Header>, header: Header) {
+        self.header_mut(hash).header = header;
+    }
+
     // hack to adjust the cumulative weight
     pub(crate) fn override_cumulative_weight(&mut self, hash: HashOf<Header>, weight: BlockWeight) {
-        let mut header = self.0.headers.remove(&hash).unwrap();
-        header.total_weight = weight;
-        self.0.headers.insert(hash, header);
+        self.header_mut(hash).total_weight = weight;
+    }
+
+    // hack to mark the stored header as speculative
+    pub(crate) fn override_speculative(&mut self, hash: HashOf<Header>) {
+        self.header_mut(hash).is_speculative = true;
     }
 
     // hack to store segment commitments
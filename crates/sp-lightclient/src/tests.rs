@@ -1,8 +1,10 @@
 use crate::mock::{kzg_instance, new_test_ext, Header, MockStorage, PosTable};
 use crate::{
-    ChainConstants, DigestError, HashOf, HeaderExt, HeaderImporter, ImportError, NextDigestItems,
+    ChainConstants, ChainInfo, ChallengeDerivation, DigestError, HashOf,
+    HeaderBelowArchivingDepthError, HeaderExt, HeaderImporter, ImportError, NextDigestItems,
     NumberOf, Storage, StorageBound,
 };
+use codec::{Decode, Encode};
 use frame_support::{assert_err, assert_ok};
 use futures::executor::block_on;
 use rand::rngs::StdRng;
@@ -19,7 +21,8 @@ use sp_consensus_subspace::{FarmerPublicKey, FarmerSignature};
 use sp_runtime::app_crypto::UncheckedFrom;
 use sp_runtime::testing::H256;
 use sp_runtime::traits::Header as HeaderT;
-use sp_runtime::{Digest, DigestItem};
+use sp_runtime::{ArithmeticError, Digest, DigestItem};
+use std::error::Error;
 use std::iter;
 use std::num::{NonZeroU64, NonZeroUsize};
 use std::sync::OnceLock;
@@ -79,6 +82,8 @@ fn default_test_constants() -> ChainConstants<Header> {
             HistorySize::from(NonZeroU64::new(10).unwrap()),
         ),
         min_sector_lifetime: HistorySize::from(NonZeroU64::new(4).unwrap()),
+        require_extrinsics_root: false,
+        max_sibling_slot_skew: None,
     }
 }
 
@@ -331,6 +336,9 @@ fn initialize_store(
         maybe_current_solution_range_override: None,
         maybe_next_solution_range_override: None,
         maybe_root_plot_public_key,
+        is_speculative: false,
+        maybe_slot: None,
+        maybe_author: None,
         test_overrides: Default::default(),
     };
 
@@ -380,8 +388,8 @@ struct ForkAt {
     is_best: Option<bool>,
 }
 
-fn add_headers_to_chain(
-    importer: &mut HeaderImporter<Header, MockStorage>,
+fn add_headers_to_chain<Derivation: ChallengeDerivation>(
+    importer: &mut HeaderImporter<Header, MockStorage, Derivation>,
     keypair: &Keypair,
     headers_to_add: NumberOf<Header>,
     maybe_fork_chain: Option<ForkAt>,
@@ -390,7 +398,7 @@ fn add_headers_to_chain(
     let best_header_ext = importer.store.best_header();
     let constants = importer.store.chain_constants();
     let (parent_hash, number, slot) = if let Some(ForkAt { parent_hash, .. }) = maybe_fork_chain {
-        let header = importer.store.header(parent_hash).unwrap();
+        let header = importer.header_or_finalized(parent_hash).unwrap();
         let digests = extract_pre_digest(&header.header).unwrap();
 
         (parent_hash, *header.header.number(), digests.slot())
@@ -413,7 +421,7 @@ fn add_headers_to_chain(
             let randomness = default_randomness();
             (randomness, false)
         } else {
-            let header = importer.store.header(parent_hash).unwrap();
+            let header = importer.header_or_finalized(parent_hash).unwrap();
             let digests = extract_subspace_digest_items::<
                 _,
                 FarmerPublicKey,
@@ -428,7 +436,7 @@ fn add_headers_to_chain(
             (randomness, digests.next_global_randomness.is_some())
         };
 
-        let (mut header, solution_range, block_weight, segment_index, segment_commitment) =
+        let (mut header, solution_range, _block_weight, segment_index, segment_commitment) =
             valid_header(ValidHeaderParams {
                 parent_hash,
                 number,
@@ -437,6 +445,7 @@ fn add_headers_to_chain(
                 global_randomness,
                 farmer_parameters,
             });
+        let block_weight = Derivation::block_weight(solution_range);
         importer.store.override_cumulative_weight(parent_hash, 0);
         if number == 1 {
             // adjust Chain constants for Block #1
@@ -455,10 +464,12 @@ fn add_headers_to_chain(
         importer
             .store
             .store_segment_commitment(segment_index, segment_commitment);
-        if let Some(ForkAt {
+        // the best header may have been pruned, in which case its weight can't be overridden
+        let best_header_exists = importer.store.header(best_header_hash).is_some();
+        if let Some(&ForkAt {
             is_best: maybe_best,
             ..
-        }) = maybe_fork_chain
+        }) = maybe_fork_chain.as_ref().filter(|_| best_header_exists)
         {
             if let Some(is_best) = maybe_best {
                 if is_best {
@@ -866,9 +877,12 @@ fn test_next_global_randomness_digest() {
         let res = importer.import_header(header.clone());
         assert_err!(
             res,
-            ImportError::DigestError(DigestError::NextDigestVerificationError(
-                ErrorDigestType::NextGlobalRandomness
-            ))
+            ImportError::DigestError {
+                hash: header.hash(),
+                source: DigestError::NextDigestVerificationError(
+                    ErrorDigestType::NextGlobalRandomness
+                ),
+            }
         );
         assert_eq!(importer.store.best_header().header.hash(), hash_of_4);
 
@@ -934,9 +948,12 @@ fn test_next_solution_range_digest_with_adjustment_enabled() {
         let res = importer.import_header(header.clone());
         assert_err!(
             res,
-            ImportError::DigestError(DigestError::NextDigestVerificationError(
-                ErrorDigestType::NextSolutionRange
-            ))
+            ImportError::DigestError {
+                hash: header.hash(),
+                source: DigestError::NextDigestVerificationError(
+                    ErrorDigestType::NextSolutionRange
+                ),
+            }
         );
         assert_eq!(importer.store.best_header().header.hash(), hash_of_4);
 
@@ -1269,9 +1286,12 @@ fn test_disallow_enable_solution_range_digest_when_solution_range_adjustment_is_
         let res = importer.import_header(header.clone());
         assert_err!(
             res,
-            ImportError::DigestError(DigestError::NextDigestVerificationError(
-                ErrorDigestType::EnableSolutionRangeAdjustmentAndOverride
-            ))
+            ImportError::DigestError {
+                hash: header.hash(),
+                source: DigestError::NextDigestVerificationError(
+                    ErrorDigestType::EnableSolutionRangeAdjustmentAndOverride
+                ),
+            }
         );
     });
 }
@@ -1480,14 +1500,1179 @@ fn test_disallow_root_plot_public_key_override() {
             .store
             .store_segment_commitment(segment_index, segment_commitment);
         importer.store.override_cumulative_weight(genesis_hash, 0);
+        let hash = header.hash();
         let res = importer.import_header(header);
         assert_err!(
             res,
-            ImportError::DigestError(DigestError::NextDigestVerificationError(
-                ErrorDigestType::RootPlotPublicKeyUpdate
-            ))
+            ImportError::DigestError {
+                hash,
+                source: DigestError::NextDigestVerificationError(
+                    ErrorDigestType::RootPlotPublicKeyUpdate
+                ),
+            }
         );
     });
 }
 
 // TODO: Test for expired sector
+
+#[test]
+fn test_speculative_header_import() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer_parameters = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (mut store, genesis_hash) = initialize_store(constants, true, None);
+        let (mut header, solution_range, _block_weight, segment_index, segment_commitment) =
+            valid_header(ValidHeaderParams {
+                parent_hash: genesis_hash,
+                number: 1,
+                slot: 1,
+                keypair: &keypair,
+                global_randomness: default_randomness(),
+                farmer_parameters: &farmer_parameters,
+            });
+        // adjust Chain constants for Block #1
+        let mut constants = store.chain_constants();
+        constants.genesis_digest_items.next_solution_range = solution_range;
+        store.override_constants(constants);
+        store.store_segment_commitment(segment_index, segment_commitment);
+        add_next_digests(&store, 1, &mut header);
+        seal_header(&keypair, &mut header);
+        let hash_of_1 = header.hash();
+
+        let mut importer = HeaderImporter::new(store);
+        assert_err!(
+            importer.confirm_speculative(hash_of_1),
+            ImportError::MissingHeader(hash_of_1)
+        );
+
+        // speculative header is verified and stored, but is not part of the fork choice
+        assert_ok!(importer.import_speculative(header.clone()));
+        assert!(importer.store.header(hash_of_1).unwrap().is_speculative);
+        assert_eq!(importer.store.best_header().header.hash(), genesis_hash);
+        assert_err!(
+            importer.import_speculative(header.clone()),
+            ImportError::HeaderAlreadyImported
+        );
+        assert_err!(
+            importer.import_header(header),
+            ImportError::HeaderAlreadyImported
+        );
+
+        // once confirmed it participates in the fork choice
+        assert_ok!(importer.confirm_speculative(hash_of_1));
+        assert!(!importer.store.header(hash_of_1).unwrap().is_speculative);
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_1);
+        assert_err!(
+            importer.confirm_speculative(hash_of_1),
+            ImportError::HeaderIsNotSpeculative(hash_of_1)
+        );
+
+        // chain continues to grow on top of the confirmed header
+        let hash_of_3 = add_headers_to_chain(&mut importer, &keypair, 2, None, &farmer_parameters);
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_3);
+    });
+}
+
+#[test]
+fn test_confirm_speculative_with_descendants() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let hash_of_1 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+        let hash_of_2 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+        let fork_hash_of_3 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            2,
+            Some(ForkAt {
+                parent_hash: hash_of_1,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        let fork_hash_of_2 = *importer
+            .store
+            .header(fork_hash_of_3)
+            .unwrap()
+            .header
+            .parent_hash();
+
+        // speculative fork is heavier than the best chain
+        importer.store.override_speculative(fork_hash_of_2);
+        importer.store.override_speculative(fork_hash_of_3);
+        let best_weight = importer.store.best_header().total_weight;
+        importer
+            .store
+            .override_cumulative_weight(fork_hash_of_3, best_weight + 1);
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_2);
+
+        // confirming the fork confirms its descendants as well and the heaviest of them becomes best
+        assert_ok!(importer.confirm_speculative(fork_hash_of_2));
+        assert!(
+            !importer
+                .store
+                .header(fork_hash_of_2)
+                .unwrap()
+                .is_speculative
+        );
+        assert!(
+            !importer
+                .store
+                .header(fork_hash_of_3)
+                .unwrap()
+                .is_speculative
+        );
+        assert_eq!(importer.store.best_header().header.hash(), fork_hash_of_3);
+    });
+}
+
+#[test]
+fn test_confirm_speculative_child_of_pruned_finalized_header() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.k_depth = 4;
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let hash_of_6 = add_headers_to_chain(&mut importer, &keypair, 6, None, &farmer);
+        let finalized_hash = importer.store.finalized_header().header.hash();
+        let fork_hash_of_3 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: finalized_hash,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        importer.store.override_speculative(fork_hash_of_3);
+
+        // the store prunes the finalized header but keeps its metadata
+        importer.store.prune_header(finalized_hash);
+        assert!(importer.store.header(finalized_hash).is_none());
+
+        assert_ok!(importer.confirm_speculative(fork_hash_of_3));
+        assert!(
+            !importer
+                .store
+                .header(fork_hash_of_3)
+                .unwrap()
+                .is_speculative
+        );
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_6);
+    });
+}
+
+#[test]
+fn test_header_import_missing_extrinsics_root() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer_parameters = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.require_extrinsics_root = true;
+        let (mut store, genesis_hash) = initialize_store(constants, true, None);
+        let (mut header, solution_range, _block_weight, segment_index, segment_commitment) =
+            valid_header(ValidHeaderParams {
+                parent_hash: genesis_hash,
+                number: 1,
+                slot: 1,
+                keypair: &keypair,
+                global_randomness: default_randomness(),
+                farmer_parameters: &farmer_parameters,
+            });
+        // adjust Chain constants for Block #1
+        let mut constants = store.chain_constants();
+        constants.genesis_digest_items.next_solution_range = solution_range;
+        store.override_constants(constants);
+        store.store_segment_commitment(segment_index, segment_commitment);
+        add_next_digests(&store, 1, &mut header);
+
+        // header with zeroed extrinsics root is rejected
+        let mut zeroed_header = header.clone();
+        seal_header(&keypair, &mut zeroed_header);
+        let mut importer = HeaderImporter::new(store);
+        assert_err!(
+            importer.import_header(zeroed_header.clone()),
+            ImportError::MissingExtrinsicsRoot
+        );
+        assert!(importer.store.header(zeroed_header.hash()).is_none());
+
+        // header with extrinsics root is imported
+        header.extrinsics_root = H256::repeat_byte(1);
+        seal_header(&keypair, &mut header);
+        assert_ok!(importer.import_header(header.clone()));
+        assert_eq!(importer.store.best_header().header.hash(), header.hash());
+    });
+}
+
+#[test]
+fn test_header_import_invalid_block_number() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer_parameters = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (mut store, genesis_hash) = initialize_store(constants, true, None);
+        let (mut header, solution_range, _block_weight, segment_index, segment_commitment) =
+            valid_header(ValidHeaderParams {
+                parent_hash: genesis_hash,
+                number: 1,
+                slot: 1,
+                keypair: &keypair,
+                global_randomness: default_randomness(),
+                farmer_parameters: &farmer_parameters,
+            });
+        let mut constants = store.chain_constants();
+        constants.genesis_digest_items.next_solution_range = solution_range;
+        store.override_constants(constants);
+        store.store_segment_commitment(segment_index, segment_commitment);
+        add_next_digests(&store, 1, &mut header);
+
+        // header with a tampered number on top of a valid parent is rejected
+        let mut tampered_header = header.clone();
+        tampered_header.number = 2;
+        seal_header(&keypair, &mut tampered_header);
+        let mut importer = HeaderImporter::new(store);
+        assert_err!(
+            importer.import_header(tampered_header.clone()),
+            ImportError::InvalidBlockNumber {
+                expected: 1,
+                got: 2,
+            }
+        );
+        assert!(importer.store.header(tampered_header.hash()).is_none());
+        assert!(importer.store.headers_at_number(2).is_empty());
+
+        seal_header(&keypair, &mut header);
+        assert_ok!(importer.import_header(header.clone()));
+        assert_eq!(importer.store.best_header().header.hash(), header.hash());
+    });
+}
+
+fn block_one_at_slot(
+    importer: &mut HeaderImporter<Header, MockStorage>,
+    genesis_hash: HashOf<Header>,
+    slot: u64,
+    keypair: &Keypair,
+    farmer_parameters: &FarmerParameters,
+) -> Header {
+    let (mut header, solution_range, _block_weight, segment_index, segment_commitment) =
+        valid_header(ValidHeaderParams {
+            parent_hash: genesis_hash,
+            number: 1,
+            slot,
+            keypair,
+            global_randomness: default_randomness(),
+            farmer_parameters,
+        });
+    let mut constants = importer.store.chain_constants();
+    constants.genesis_digest_items.next_solution_range = solution_range;
+    importer.store.override_constants(constants);
+    importer
+        .store
+        .store_segment_commitment(segment_index, segment_commitment);
+    add_next_digests(&importer.store, 1, &mut header);
+    seal_header(keypair, &mut header);
+    header
+}
+
+#[test]
+fn test_header_import_slot_inconsistent_with_siblings() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer_parameters = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.max_sibling_slot_skew = Some(20);
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+
+        let best_header = block_one_at_slot(
+            &mut importer,
+            genesis_hash,
+            100,
+            &keypair,
+            &farmer_parameters,
+        );
+        assert_ok!(importer.import_header(best_header.clone()));
+        assert_eq!(
+            importer.store.best_header().header.hash(),
+            best_header.hash()
+        );
+
+        // sibling within the allowed skew is imported as a fork
+        let consistent_sibling = block_one_at_slot(
+            &mut importer,
+            genesis_hash,
+            85,
+            &keypair,
+            &farmer_parameters,
+        );
+        assert_ok!(importer.import_header(consistent_sibling.clone()));
+        assert!(importer.store.header(consistent_sibling.hash()).is_some());
+
+        // sibling too far behind the best chain is rejected
+        let inconsistent_sibling = block_one_at_slot(
+            &mut importer,
+            genesis_hash,
+            50,
+            &keypair,
+            &farmer_parameters,
+        );
+        assert_err!(
+            importer.import_header(inconsistent_sibling.clone()),
+            ImportError::SlotInconsistentWithSiblings
+        );
+        assert!(importer.store.header(inconsistent_sibling.hash()).is_none());
+
+        // same sibling is accepted once the check is disabled
+        let mut constants = importer.store.chain_constants();
+        constants.max_sibling_slot_skew = None;
+        importer.store.override_constants(constants);
+        assert_ok!(importer.import_header(inconsistent_sibling.clone()));
+        assert!(importer.store.header(inconsistent_sibling.hash()).is_some());
+    });
+}
+
+#[test]
+fn test_audit_chain() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        add_headers_to_chain(&mut importer, &keypair, 25, None, &farmer);
+
+        // clean chain passes the audit
+        assert_ok!(importer.audit_chain(0, 25));
+        assert_ok!(importer.audit_chain(10, 20));
+        assert_eq!(
+            importer.audit_chain(0, 26),
+            Err((26, ImportError::MissingCanonicalHeader(26)))
+        );
+
+        // tamper with the derived value of header #12
+        let mut header_ext = importer.store.headers_at_number(12).pop().unwrap();
+        header_ext.era_start_slot = Slot::from(u64::from(header_ext.era_start_slot) + 1);
+        let tampered_hash = header_ext.header.hash();
+        importer.store.store_header(header_ext, false);
+
+        // headers before the tampered one still pass the audit
+        assert_ok!(importer.audit_chain(1, 11));
+        // the first mismatch is reported
+        assert_eq!(
+            importer.audit_chain(1, 25),
+            Err((12, ImportError::InvalidDerivedValues(tampered_hash)))
+        );
+    });
+}
+
+#[test]
+fn test_audit_chain_through_pruned_finalized_header() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        add_headers_to_chain(&mut importer, &keypair, 25, None, &farmer);
+
+        // the store prunes the finalized header but keeps its metadata
+        let finalized_header = importer.store.finalized_header();
+        assert_eq!(*finalized_header.header.number(), 18);
+        importer.store.prune_header(finalized_header.header.hash());
+
+        assert_ok!(importer.audit_chain(19, 25));
+        assert_ok!(importer.audit_chain(10, 18));
+        assert_ok!(importer.audit_chain(0, 25));
+    });
+}
+
+#[test]
+fn test_reverify_range() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        add_headers_to_chain(&mut importer, &keypair, 25, None, &farmer);
+
+        // valid headers pass the re-verification in ascending order
+        let results = importer.reverify_range(0, 25);
+        let expected_hashes = (1..=25)
+            .map(|number| {
+                let header = importer.store.headers_at_number(number).pop().unwrap();
+                header.header.hash()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results
+                .iter()
+                .map(|(hash, _result)| *hash)
+                .collect::<Vec<_>>(),
+            expected_hashes
+        );
+        assert!(results.iter().all(|(_hash, result)| result.is_ok()));
+        assert_eq!(importer.reverify_range(10, 20).len(), 11);
+        assert!(importer.reverify_range(26, 30).is_empty());
+
+        // tamper with the contents of header #12 without signing it again
+        let stored_header = importer.store.headers_at_number(12).pop().unwrap().header;
+        let mut tampered_header = stored_header.clone();
+        tampered_header.extrinsics_root = H256::repeat_byte(1);
+        let tampered_hash = tampered_header.hash();
+        importer
+            .store
+            .override_header(stored_header.hash(), tampered_header);
+        let chain_info = importer.chain_info();
+
+        // only the tampered header fails the re-verification
+        let results = importer.reverify_range(1, 25);
+        assert_eq!(results.len(), 25);
+        assert_eq!(
+            results
+                .into_iter()
+                .filter(|(_hash, result)| result.is_err())
+                .collect::<Vec<_>>(),
+            vec![(tampered_hash, Err(ImportError::InvalidBlockSignature))]
+        );
+        assert!(importer
+            .reverify_range(13, 25)
+            .iter()
+            .all(|(_hash, result)| result.is_ok()));
+
+        // storage is left untouched
+        assert_eq!(importer.chain_info(), chain_info);
+    });
+}
+
+#[test]
+fn test_on_finalize_hook() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let k_depth = constants.k_depth;
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+
+        // nothing is finalized until the chain reaches k depth
+        add_headers_to_chain(&mut importer, &keypair, k_depth, None, &farmer);
+        assert!(importer.store.finalized_head_advances().is_empty());
+
+        add_headers_to_chain(&mut importer, &keypair, 3, None, &farmer);
+        let expected_advances = (1..=3)
+            .map(|number| {
+                let header = importer.store.headers_at_number(number).pop().unwrap();
+                (number, header.header.hash())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(importer.store.finalized_head_advances(), expected_advances);
+        assert_eq!(
+            importer.store.finalized_head_advances().last().unwrap().1,
+            importer.store.finalized_header().header.hash()
+        );
+    });
+}
+
+#[test]
+fn test_on_finalize_hook_catch_up() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.k_depth = 4;
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+
+        // best chain is not long enough to finalize anything
+        add_headers_to_chain(&mut importer, &keypair, 4, None, &farmer);
+
+        // longer fork that is lighter until its last header
+        let fork_hash_of_7 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            7,
+            Some(ForkAt {
+                parent_hash: genesis_hash,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        assert!(importer.store.finalized_head_advances().is_empty());
+
+        // a single import re-orgs to the fork and finalizes four of its headers at once
+        let fork_hash_of_8 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: fork_hash_of_7,
+                is_best: Some(true),
+            }),
+            &farmer,
+        );
+        assert_eq!(importer.store.best_header().header.hash(), fork_hash_of_8);
+        let expected_advances = (1..=4)
+            .map(|number| {
+                let header = importer
+                    .find_ancestor_of_header_at_number(fork_hash_of_8, number)
+                    .unwrap();
+                (number, header.header.hash())
+            })
+            .collect::<Vec<_>>();
+
+        // each finalized header is delivered once, in ascending order
+        assert_eq!(importer.store.finalized_head_advances(), expected_advances);
+        assert_eq!(
+            importer.store.finalized_header().header.hash(),
+            expected_advances.last().unwrap().1
+        );
+    });
+}
+
+/// Deterministic derivation that accepts any solution and gives every block the same weight.
+struct StubChallengeDerivation;
+
+impl ChallengeDerivation for StubChallengeDerivation {
+    fn verify_solution(
+        _solution: &Solution<FarmerPublicKey, FarmerPublicKey>,
+        _slot: SlotNumber,
+        _params: &VerifySolutionParams,
+    ) -> Result<SolutionRange, String> {
+        Ok(0)
+    }
+
+    fn block_weight(_solution_range: SolutionRange) -> BlockWeight {
+        1
+    }
+}
+
+#[test]
+fn test_header_import_with_stub_challenge_derivation() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer =
+            HeaderImporter::<_, _, StubChallengeDerivation>::with_challenge_derivation(store);
+        let hash_of_2 = add_headers_to_chain(&mut importer, &keypair, 2, None, &farmer);
+        let hash_of_3 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+        let best_header = importer.store.best_header();
+        assert_eq!(best_header.header.hash(), hash_of_3);
+        // parent weight is reset to zero before each import, so only the stub weight is added
+        assert_eq!(best_header.total_weight, 1);
+
+        // lighter fork doesn't become the best
+        add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: hash_of_2,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_3);
+        assert_eq!(importer.store.headers_at_number(3).len(), 2);
+
+        // heavier fork becomes the best
+        let fork_hash_of_4 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            2,
+            Some(ForkAt {
+                parent_hash: hash_of_2,
+                is_best: Some(true),
+            }),
+            &farmer,
+        );
+        assert_eq!(importer.store.best_header().header.hash(), fork_hash_of_4);
+    });
+}
+
+#[test]
+fn test_common_ancestor() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+
+        let hash_of_2 = add_headers_to_chain(&mut importer, &keypair, 2, None, &farmer);
+        let hash_of_4 = add_headers_to_chain(&mut importer, &keypair, 2, None, &farmer);
+
+        // sibling of #3 forked from #2
+        let fork_hash_of_3 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: hash_of_2,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        // deeper fork from genesis
+        let fork_hash_of_3_from_genesis = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            3,
+            Some(ForkAt {
+                parent_hash: genesis_hash,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+
+        let common_ancestor_hash = |a, b| {
+            importer
+                .common_ancestor(a, b)
+                .map(|header_ext| header_ext.header.hash())
+        };
+
+        // tips at different depths
+        assert_eq!(
+            common_ancestor_hash(hash_of_4, fork_hash_of_3),
+            Some(hash_of_2)
+        );
+        assert_eq!(
+            common_ancestor_hash(fork_hash_of_3, hash_of_4),
+            Some(hash_of_2)
+        );
+        assert_eq!(
+            common_ancestor_hash(hash_of_4, fork_hash_of_3_from_genesis),
+            Some(genesis_hash)
+        );
+        assert_eq!(
+            common_ancestor_hash(fork_hash_of_3, fork_hash_of_3_from_genesis),
+            Some(genesis_hash)
+        );
+
+        // header is the common ancestor of itself and its descendants
+        assert_eq!(common_ancestor_hash(hash_of_4, hash_of_4), Some(hash_of_4));
+        assert_eq!(common_ancestor_hash(hash_of_4, hash_of_2), Some(hash_of_2));
+
+        // unknown header
+        assert_eq!(common_ancestor_hash(hash_of_4, Default::default()), None);
+    });
+}
+
+#[test]
+fn test_best_chain_header_at() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+
+        let hash_of_1 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+        let hash_of_2 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+        let hash_of_3 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+        let hash_of_4 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+
+        // siblings of the canonical headers that are not part of the best chain
+        add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: hash_of_2,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            3,
+            Some(ForkAt {
+                parent_hash: genesis_hash,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_4);
+
+        let best_chain_hash_at = |number| {
+            importer
+                .best_chain_header_at(number)
+                .map(|header_ext| header_ext.header.hash())
+        };
+
+        assert_eq!(best_chain_hash_at(0), Some(genesis_hash));
+        assert_eq!(best_chain_hash_at(1), Some(hash_of_1));
+        assert_eq!(best_chain_hash_at(2), Some(hash_of_2));
+        assert_eq!(best_chain_hash_at(3), Some(hash_of_3));
+        assert_eq!(best_chain_hash_at(4), Some(hash_of_4));
+
+        // nothing above the best header
+        assert_eq!(best_chain_hash_at(5), None);
+    });
+}
+
+#[test]
+fn test_storage_bound_keeps_recent_headers() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.k_depth = 7;
+        constants.storage_bound = StorageBound::NumberOfHeaderToKeepBeyondKDepth(3);
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let hash_of_100 = add_headers_to_chain(&mut importer, &keypair, 100, None, &farmer);
+
+        // best and finalized headers are intact
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_100);
+        let finalized_header = importer.store.finalized_header();
+        assert_eq!(finalized_header.header.number, 93);
+        assert_eq!(
+            importer.store.header(finalized_header.header.hash()),
+            Some(finalized_header)
+        );
+
+        // old headers are gone
+        for number in 0..90 {
+            assert!(importer.store.headers_at_number(number).is_empty());
+        }
+
+        // the window below finalized header and everything above it remain queryable
+        let mut header = importer.store.best_header();
+        while header.header.number > 90 {
+            header = importer.store.header(header.header.parent_hash).unwrap();
+        }
+        assert_eq!(header.header.number, 90);
+        for number in 90..=100 {
+            assert_eq!(importer.store.headers_at_number(number).len(), 1);
+        }
+    });
+}
+
+#[test]
+fn test_failed_storage_batch_leaves_no_partial_state() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer_parameters = FarmerParameters::new();
+
+        // header is finalized right away, such that a single import both stores and finalizes it
+        let mut constants = default_test_constants();
+        constants.k_depth = 0;
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let header =
+            block_one_at_slot(&mut importer, genesis_hash, 1, &keypair, &farmer_parameters);
+        let number_of_segments = importer.store.number_of_segments();
+
+        // fail on the second operation, after the header was stored
+        importer.store.fail_commit_batch_at_op(Some(1));
+        assert!(matches!(
+            importer.import_header(header.clone()),
+            Err(ImportError::FailedToCommitStorageBatch(_))
+        ));
+
+        // none of the mutations of the import are observable
+        assert!(importer.store.header(header.hash()).is_none());
+        assert!(importer.store.headers_at_number(1).is_empty());
+        assert_eq!(importer.store.header_count(), 1);
+        assert_eq!(importer.store.best_header().header.hash(), genesis_hash);
+        assert_eq!(
+            importer.store.finalized_header().header.hash(),
+            genesis_hash
+        );
+        assert_eq!(importer.store.number_of_segments(), number_of_segments);
+        assert!(importer.store.finalized_head_advances().is_empty());
+
+        // once the store recovers, the same header is stored and finalized by a single import
+        importer.store.fail_commit_batch_at_op(None);
+        assert_ok!(importer.import_header(header.clone()));
+        assert_eq!(importer.store.best_header().header.hash(), header.hash());
+        assert_eq!(
+            importer.store.finalized_header().header.hash(),
+            header.hash()
+        );
+        assert_eq!(
+            importer.store.finalized_head_advances(),
+            &[(1, header.hash())]
+        );
+    });
+}
+
+#[test]
+fn test_recover_pruned_best_header() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let hash_of_2 = add_headers_to_chain(&mut importer, &keypair, 2, None, &farmer);
+        let hash_of_3 = add_headers_to_chain(&mut importer, &keypair, 1, None, &farmer);
+        let fork_hash_of_3 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: hash_of_2,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_3);
+
+        // prune the branch of the best header, leaving it dangling
+        importer.store.prune_header(hash_of_3);
+        assert!(importer.store.header(hash_of_3).is_none());
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_3);
+
+        // the dangling best header is heavier than the fork, the next import must not compare
+        // against it
+        let hash_of_4 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: fork_hash_of_3,
+                is_best: Some(true),
+            }),
+            &farmer,
+        );
+        let best_header = importer.store.best_header();
+        assert_eq!(best_header.header.hash(), hash_of_4);
+        assert!(importer.store.header(hash_of_4).is_some());
+    });
+}
+
+#[test]
+fn test_recover_best_header_keeps_finalized_header_pruned() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.k_depth = 4;
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        add_headers_to_chain(&mut importer, &keypair, 6, None, &farmer);
+        let finalized_hash = importer.store.finalized_header().header.hash();
+
+        // prune every header from the finalized one up to the best header
+        for number in (3..=6).rev() {
+            let hash = importer.store.headers_at_number(number)[0].header.hash();
+            importer.store.prune_header(hash);
+        }
+        importer.store.prune_header(finalized_hash);
+        let header_count = importer.store.header_count();
+
+        // the pruned finalized header becomes best without being stored again
+        let best_header = importer
+            .transaction(|importer| importer.best_header())
+            .unwrap();
+        assert_eq!(best_header.header.hash(), finalized_hash);
+        assert_eq!(importer.store.best_header().header.hash(), finalized_hash);
+        assert!(importer.store.header(finalized_hash).is_none());
+        assert!(importer.store.headers_at_number(2).is_empty());
+        assert_eq!(importer.store.header_count(), header_count);
+    });
+}
+
+#[test]
+fn test_import_child_of_pruned_finalized_header() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.k_depth = 4;
+        let (store, _genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let hash_of_6 = add_headers_to_chain(&mut importer, &keypair, 6, None, &farmer);
+        let finalized_header = importer.store.finalized_header();
+        assert_eq!(*finalized_header.header.number(), 2);
+
+        // the store prunes the finalized header but keeps its metadata
+        let finalized_hash = finalized_header.header.hash();
+        importer.store.prune_header(finalized_hash);
+        assert!(importer.store.header(finalized_hash).is_none());
+        assert_eq!(
+            importer.store.finalized_header().header.hash(),
+            finalized_hash
+        );
+
+        // a late fork header building on the finalized header is still imported
+        let fork_hash_of_3 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: finalized_hash,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        let fork_header = importer.store.header(fork_hash_of_3).unwrap();
+        assert_eq!(*fork_header.header.parent_hash(), finalized_hash);
+        assert_eq!(importer.store.headers_at_number(3).len(), 2);
+        assert_eq!(importer.store.best_header().header.hash(), hash_of_6);
+
+        // the pruned finalized header is still found as the common ancestor
+        assert_eq!(
+            importer
+                .common_ancestor(hash_of_6, fork_hash_of_3)
+                .map(|header_ext| header_ext.header.hash()),
+            Some(finalized_hash)
+        );
+    });
+}
+
+#[test]
+fn test_chain_info() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.k_depth = 4;
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let chain_info = importer.chain_info();
+        assert_eq!(chain_info.best_hash, genesis_hash);
+        assert_eq!(chain_info.finalized_hash, genesis_hash);
+        assert_eq!(chain_info.header_count, 1);
+
+        let hash_of_6 = add_headers_to_chain(&mut importer, &keypair, 6, None, &farmer);
+        let hash_of_2 = importer.store.headers_at_number(2)[0].header.hash();
+        let best_total_weight = importer.store.header(hash_of_6).unwrap().total_weight;
+        assert_eq!(
+            importer.chain_info(),
+            ChainInfo {
+                best_number: 6,
+                best_hash: hash_of_6,
+                best_total_weight,
+                finalized_number: 2,
+                finalized_hash: hash_of_2,
+                header_count: 7,
+            }
+        );
+
+        // fork headers are counted as well
+        add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: hash_of_2,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+        let chain_info = importer.chain_info();
+        assert_eq!(chain_info.best_hash, hash_of_6);
+        assert_eq!(chain_info.header_count, 8);
+
+        // chain info is codec stable, so it can be sent over RPC
+        let encoded = chain_info.encode();
+        assert_eq!(
+            ChainInfo::<Header>::decode(&mut encoded.as_slice()).unwrap(),
+            chain_info
+        );
+    });
+}
+
+#[test]
+fn test_verify_constants() {
+    new_test_ext().execute_with(|| {
+        let mut constants = default_test_constants();
+        constants.k_depth = 7;
+        let (store, _genesis_hash) = initialize_store(constants.clone(), true, None);
+        let importer = HeaderImporter::new(store);
+        assert_ok!(importer.verify_constants(&constants));
+
+        // store initialized with different constants is detected
+        constants.k_depth = 10;
+        assert_err!(
+            importer.verify_constants(&constants),
+            ImportError::ConstantsMismatch
+        );
+    });
+}
+
+#[test]
+fn test_tips() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let mut constants = default_test_constants();
+        constants.k_depth = 10;
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+        let tips = importer.tips();
+        assert_eq!(tips.len(), 1);
+        assert_eq!(tips[0].header.hash(), genesis_hash);
+
+        let hash_of_4 = add_headers_to_chain(&mut importer, &keypair, 4, None, &farmer);
+        let hash_of_2 = importer.store.headers_at_number(2)[0].header.hash();
+        let fork_hash_of_3 = add_headers_to_chain(
+            &mut importer,
+            &keypair,
+            1,
+            Some(ForkAt {
+                parent_hash: hash_of_2,
+                is_best: Some(false),
+            }),
+            &farmer,
+        );
+
+        // heavier tip comes first
+        let tips = importer
+            .tips()
+            .into_iter()
+            .map(|tip| tip.header.hash())
+            .collect::<Vec<_>>();
+        assert_eq!(tips, vec![hash_of_4, fork_hash_of_3]);
+    });
+}
+
+#[test]
+fn test_import_error_display_and_source() {
+    let hash = H256::repeat_byte(1);
+    let error = ImportError::<Header>::DigestError {
+        hash,
+        source: DigestError::Missing(ErrorDigestType::PreDigest),
+    };
+    assert_eq!(
+        error.to_string(),
+        format!("Digest error in header {hash:?}: Subspace PreDigest digest not found")
+    );
+    assert_eq!(
+        error.source().map(|source| source.to_string()),
+        Some("Subspace PreDigest digest not found".to_string())
+    );
+
+    let error =
+        ImportError::<Header>::HeaderIsBelowArchivingDepth(HeaderBelowArchivingDepthError {
+            current_finalized_number: 5,
+            header_number: 3,
+        });
+    assert_eq!(
+        error.to_string(),
+        "Header #3 is not above the finalized header #5"
+    );
+    assert!(error.source().is_none());
+
+    let error = ImportError::<Header>::MissingSegmentCommitment(SegmentIndex::ONE);
+    assert_eq!(
+        error.to_string(),
+        "Missing segment commitment for segment index 1"
+    );
+
+    let error = ImportError::<Header>::ArithmeticError(ArithmeticError::Overflow.into());
+    assert_eq!(error.to_string(), "Arithmetic error: Overflow");
+    assert_eq!(
+        error.source().map(|source| source.to_string()),
+        Some("Overflow".to_string())
+    );
+}
+
+#[test]
+fn test_header_ext_caches_slot_and_author() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let farmer = FarmerParameters::new();
+
+        let constants = default_test_constants();
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+
+        // genesis header is not imported, so nothing is cached and the values are extracted
+        let genesis_ext = importer.store.header(genesis_hash).unwrap();
+        assert_eq!(genesis_ext.maybe_slot, None);
+        assert_eq!(genesis_ext.maybe_author, None);
+        let genesis_pre_digest = extract_pre_digest(&genesis_ext.header).unwrap();
+        assert_eq!(genesis_ext.slot().unwrap(), genesis_pre_digest.slot());
+        assert_eq!(
+            genesis_ext.author().unwrap(),
+            genesis_pre_digest.solution().public_key
+        );
+
+        let best_hash = add_headers_to_chain(&mut importer, &keypair, 3, None, &farmer);
+        let mut hash = best_hash;
+        while hash != genesis_hash {
+            let header_ext = importer.store.header(hash).unwrap();
+            let pre_digest = extract_pre_digest(&header_ext.header).unwrap();
+            assert_eq!(header_ext.maybe_slot, Some(pre_digest.slot()));
+            assert_eq!(
+                header_ext.maybe_author.as_ref(),
+                Some(&pre_digest.solution().public_key)
+            );
+            assert_eq!(header_ext.slot().unwrap(), pre_digest.slot());
+            assert_eq!(
+                header_ext.author().unwrap(),
+                pre_digest.solution().public_key
+            );
+
+            // cached values survive encoding round-trip
+            let decoded = HeaderExt::<Header>::decode(&mut header_ext.encode().as_slice()).unwrap();
+            assert_eq!(decoded.maybe_slot, header_ext.maybe_slot);
+            assert_eq!(decoded.maybe_author, header_ext.maybe_author);
+
+            hash = *header_ext.header.parent_hash();
+        }
+    });
+}
+
+#[test]
+fn test_digest_error_carries_header_hash() {
+    new_test_ext().execute_with(|| {
+        let constants = default_test_constants();
+        let (store, genesis_hash) = initialize_store(constants, true, None);
+        let mut importer = HeaderImporter::new(store);
+
+        // header without pre-digest
+        let header = Header {
+            parent_hash: genesis_hash,
+            number: 1,
+            state_root: Default::default(),
+            extrinsics_root: H256::repeat_byte(1),
+            digest: Default::default(),
+        };
+        let hash = header.hash();
+
+        assert_err!(
+            importer.import_header(header),
+            ImportError::DigestError {
+                hash,
+                source: DigestError::Missing(ErrorDigestType::PreDigest),
+            }
+        );
+        assert!(importer.store.header(hash).is_none());
+    });
+}
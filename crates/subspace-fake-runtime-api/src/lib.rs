@@ -26,8 +26,8 @@ use sp_core::crypto::KeyTypeId;
 use sp_core::{OpaqueMetadata, H256};
 use sp_domains::bundle_producer_election::BundleProducerElectionParams;
 use sp_domains::{
-    DomainAllowlistUpdates, DomainId, DomainInstanceData, ExecutionReceiptFor, OpaqueBundle,
-    OperatorId, OperatorPublicKey,
+    DomainAllowlistUpdates, DomainId, DomainInstanceData, EpochIndex, ExecutionReceiptFor,
+    OpaqueBundle, OperatorId, OperatorPublicKey, OperatorStatusInfo, PendingWithdrawal,
 };
 use sp_domains_fraud_proof::fraud_proof::FraudProof;
 use sp_domains_fraud_proof::storage_proof::FraudProofStorageKeyRequest;
@@ -36,7 +36,7 @@ use sp_messenger::messages::{
 };
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity};
-use sp_runtime::{ApplyExtrinsicResult, ExtrinsicInclusionMode};
+use sp_runtime::{ApplyExtrinsicResult, ExtrinsicInclusionMode, Perbill};
 use sp_subspace_mmr::ConsensusChainMmrLeafProof;
 use sp_version::RuntimeVersion;
 use std::collections::btree_map::BTreeMap;
@@ -310,6 +310,30 @@ sp_api::impl_runtime_apis! {
         fn domain_sudo_call(_domain_id: DomainId) -> Option<Vec<u8>> {
             unreachable!()
         }
+
+        fn operator_rankings(_domain_id: DomainId) -> Vec<(OperatorId, Balance, Perbill)> {
+            unreachable!()
+        }
+
+        fn operator_stake_at(_operator_id: OperatorId, _epoch_index: EpochIndex) -> Option<Balance> {
+            unreachable!()
+        }
+
+        fn min_stake_to_enter(_domain_id: DomainId) -> Balance {
+            unreachable!()
+        }
+
+        fn pending_deposit(_operator_id: OperatorId, _nominator_id: AccountId) -> Option<Balance> {
+            unreachable!()
+        }
+
+        fn pending_withdrawal(_operator_id: OperatorId, _nominator_id: AccountId) -> Option<PendingWithdrawal<Balance, Balance>> {
+            unreachable!()
+        }
+
+        fn operator_status(_operator_id: OperatorId) -> Option<OperatorStatusInfo> {
+            unreachable!()
+        }
     }
 
     impl sp_domains::BundleProducerElectionApi<Block, Balance> for Runtime {
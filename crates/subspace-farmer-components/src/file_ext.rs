@@ -92,6 +92,10 @@ pub trait FileExt {
     /// desirable, on Windows this can only be set when file is opened, see [`OpenOptionsExt`]
     fn advise_sequential_access(&self) -> Result<()>;
 
+    /// Advise OS/file system that specified range of the file will be read soon, such that it can
+    /// be loaded into page cache ahead of time, only has impact on Linux
+    fn advise_will_need(&self, offset: u64, len: u64) -> Result<()>;
+
     /// Read exact number of bytes at a specific offset
     fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<()>;
 
@@ -163,6 +167,30 @@ impl FileExt for File {
         Ok(())
     }
 
+    #[cfg(target_os = "linux")]
+    fn advise_will_need(&self, offset: u64, len: u64) -> Result<()> {
+        use std::os::unix::io::AsRawFd;
+        let err = unsafe {
+            libc::posix_fadvise(
+                self.as_raw_fd(),
+                offset as libc::off_t,
+                len as libc::off_t,
+                libc::POSIX_FADV_WILLNEED,
+            )
+        };
+        if err != 0 {
+            Err(std::io::Error::from_raw_os_error(err))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn advise_will_need(&self, _offset: u64, _len: u64) -> Result<()> {
+        // Not supported
+        Ok(())
+    }
+
     #[cfg(unix)]
     fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<()> {
         std::os::unix::fs::FileExt::read_exact_at(self, buf, offset)
@@ -13,7 +13,7 @@ use std::str::FromStr;
 use std::time::Duration;
 use subspace_farmer::cluster::cache::cache_service;
 use subspace_farmer::cluster::nats_client::NatsClient;
-use subspace_farmer::disk_piece_cache::DiskPieceCache;
+use subspace_farmer::disk_piece_cache::{DiskPieceCache, DiskPieceCacheOptions};
 use subspace_farmer::utils::AsyncJoinOnDrop;
 
 /// Interval between cache self-identification broadcast messages
@@ -160,12 +160,23 @@ pub(super) async fn cache(
     let caches = disk_caches
         .iter()
         .map(|disk_cache| {
-            DiskPieceCache::open(
+            DiskPieceCache::open_with_options(
                 &disk_cache.directory,
-                u32::try_from(disk_cache.allocated_space / DiskPieceCache::element_size() as u64)
-                    .unwrap_or(u32::MAX),
+                u32::try_from(
+                    disk_cache
+                        .allocated_space
+                        .saturating_sub(DiskPieceCache::HEADER_SIZE)
+                        / u64::from(DiskPieceCache::element_size()),
+                )
+                .unwrap_or(u32::MAX),
                 None,
                 Some(registry),
+                // Capacity follows allocated space, which might have changed since the cache was
+                // created
+                DiskPieceCacheOptions {
+                    allow_resize: true,
+                    ..Default::default()
+                },
             )
             .map_err(|error| {
                 anyhow!(
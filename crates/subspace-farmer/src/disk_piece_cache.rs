@@ -15,11 +15,17 @@ use async_trait::async_trait;
 use bytes::BytesMut;
 use futures::channel::mpsc;
 use futures::{stream, SinkExt, Stream, StreamExt};
+#[cfg(not(windows))]
+use memmap2::{Advice, Mmap};
+use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
 use prometheus_client::registry::Registry;
+use std::collections::{BTreeMap, HashMap};
 #[cfg(not(windows))]
 use std::fs::{File, OpenOptions};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::task::Poll;
 use std::{fs, io, mem};
@@ -33,9 +39,8 @@ use tokio::runtime::Handle;
 use tokio::task;
 use tracing::{debug, info, warn};
 
-/// How many pieces should be skipped before stopping to check the rest of contents, this allows to
-/// not miss most of the pieces after one or two corrupted pieces
-const CONTENTS_READ_SKIP_LIMIT: usize = 3;
+/// Magic bytes at the beginning of the cache file header
+const FILE_MAGIC: [u8; 8] = *b"SUBCACHE";
 
 /// Disk piece cache open error
 #[derive(Debug, Error)]
@@ -58,8 +63,225 @@ pub enum DiskPieceCacheError {
     #[error("Cache size has zero capacity, this is not supported, cache size needs to be larger")]
     ZeroCapacity,
     /// Checksum mismatch
-    #[error("Checksum mismatch")]
-    ChecksumMismatch,
+    #[error("Checksum mismatch at offset {offset}")]
+    ChecksumMismatch {
+        /// Offset
+        offset: u32,
+    },
+    /// No piece stored at offset
+    #[error("No piece stored at offset {offset}")]
+    OffsetEmpty {
+        /// Offset
+        offset: u32,
+    },
+    /// No free offset left in the cache
+    #[error("No free offset left in the cache")]
+    Full,
+    /// Offset is already occupied by another piece
+    #[error("Offset {offset} is already occupied by another piece")]
+    OffsetOccupied {
+        /// Offset
+        offset: u32,
+    },
+    /// Cache file size is not aligned to disk sector size, file was truncated by something else
+    #[error("Cache file was truncated to {size} bytes, which is not aligned to disk sector size")]
+    Truncated {
+        /// Size of the file
+        size: u64,
+    },
+    /// Cache file size doesn't match capacity, read-only cache can't resize the file
+    #[error("Cache file size {actual} doesn't match expected size {expected} for capacity")]
+    UnexpectedFileSize {
+        /// Expected size of the file
+        expected: u64,
+        /// Actual size of the file
+        actual: u64,
+    },
+    /// Capacity doesn't match the capacity of existing cache file
+    #[error("Capacity {expected} doesn't match capacity {actual} of existing cache file")]
+    CapacityMismatch {
+        /// Requested capacity
+        expected: u32,
+        /// Capacity of existing cache file
+        actual: u32,
+    },
+    /// Piece bytes have unexpected length
+    #[error("Piece bytes have length {actual}, expected {expected}")]
+    InvalidPieceLength {
+        /// Expected length of piece bytes
+        expected: usize,
+        /// Actual length of piece bytes
+        actual: usize,
+    },
+    /// Cache file doesn't exist
+    #[error("Cache file doesn't exist")]
+    NotFound,
+    /// Cache file already exists
+    #[error("Cache file already exists")]
+    AlreadyExists,
+    /// Cache was opened in read-only mode
+    #[error("Cache was opened in read-only mode")]
+    ReadOnly,
+    /// Destination cache is too small to store all pieces at their offsets
+    #[error(
+        "Destination cache capacity {capacity} is too small for piece stored at offset {offset}"
+    )]
+    DestinationTooSmall {
+        /// Highest occupied offset in the source cache
+        offset: u32,
+        /// Capacity of the destination cache
+        capacity: u32,
+    },
+    /// Cache file layout version is not supported, files without a header (created before the
+    /// layout was versioned) are reported as version `0` when opened in read-only mode and wiped
+    /// otherwise
+    #[error("Unsupported cache file version {found}, expected {expected}")]
+    UnsupportedVersion {
+        /// Version found in the cache file
+        found: u8,
+        /// Version supported by this implementation
+        expected: u8,
+    },
+    /// Cache file was written with different element size
+    #[error("Cache file element size {found} doesn't match expected element size {expected}")]
+    UnexpectedElementSize {
+        /// Element size found in the cache file
+        found: u32,
+        /// Element size of this implementation
+        expected: u32,
+    },
+    /// Shrinking cache would lose piece stored at offset
+    #[error("Shrinking cache would lose piece stored at offset {offset}")]
+    ShrinkWouldLoseData {
+        /// Offset
+        offset: u32,
+    },
+}
+
+/// Header stored at the beginning of the cache file, describes layout of elements that follow it
+#[derive(Debug, Encode, Decode)]
+struct CacheFileHeader {
+    magic: [u8; 8],
+    version: u8,
+    element_size: u32,
+}
+
+impl CacheFileHeader {
+    fn new() -> Self {
+        Self {
+            magic: FILE_MAGIC,
+            version: DiskPieceCache::FORMAT_VERSION,
+            element_size: DiskPieceCache::element_size(),
+        }
+    }
+
+    #[inline]
+    fn encoded_size() -> usize {
+        Self::new().encoded_size()
+    }
+}
+
+/// In-memory index of pieces stored in the cache
+#[derive(Debug)]
+struct PieceIndexMap {
+    offsets: HashMap<PieceIndex, PieceCacheOffset>,
+    /// Piece index stored at each offset
+    piece_indices: Vec<Option<PieceIndex>>,
+    /// Number of offsets with a piece stored
+    occupied: u32,
+}
+
+impl PieceIndexMap {
+    fn new(capacity: u32) -> Self {
+        Self {
+            offsets: HashMap::new(),
+            piece_indices: vec![None; capacity as usize],
+            occupied: 0,
+        }
+    }
+
+    fn insert(&mut self, offset: u32, piece_index: PieceIndex) {
+        self.remove(offset);
+        self.piece_indices[offset as usize].replace(piece_index);
+        self.offsets.insert(piece_index, PieceCacheOffset(offset));
+        self.occupied += 1;
+    }
+
+    fn remove(&mut self, offset: u32) {
+        let Some(piece_index) = self.piece_indices[offset as usize].take() else {
+            return;
+        };
+        self.occupied -= 1;
+
+        if let Some(PieceCacheOffset(existing_offset)) = self.offsets.get(&piece_index)
+            && *existing_offset == offset
+        {
+            self.offsets.remove(&piece_index);
+        }
+    }
+}
+
+/// How [`DiskPieceCache`] treats existing or missing cache file on opening
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum DiskPieceCacheOpenMode {
+    /// Open existing cache file or create a new one if it doesn't exist
+    #[default]
+    OpenOrCreate,
+    /// Open existing cache file, [`DiskPieceCacheError::NotFound`] is returned if it doesn't exist
+    OpenExisting,
+    /// Create a new cache file, [`DiskPieceCacheError::AlreadyExists`] is returned if it already
+    /// exists
+    Create,
+}
+
+/// Options for opening [`DiskPieceCache`]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DiskPieceCacheOptions {
+    /// How to treat existing or missing cache file
+    pub open_mode: DiskPieceCacheOpenMode,
+    /// Serve reads from a read-only memory mapping of the cache file, see
+    /// [`DiskPieceCache::open_mmap()`]
+    pub mmap: bool,
+    /// Sync written data to disk before returning from every write.
+    ///
+    /// This guarantees written pieces survive power failure, but makes writes much slower since
+    /// every write waits for the disk instead of being buffered by the OS. Consider calling
+    /// [`DiskPieceCache::flush()`] after a batch of writes instead.
+    pub sync_writes: bool,
+    /// Open cache file in read-only mode, see [`DiskPieceCache::open_read_only()`]
+    pub read_only: bool,
+    /// Resize existing cache file to requested capacity instead of returning
+    /// [`DiskPieceCacheError::CapacityMismatch`] error, see [`DiskPieceCache::resize()`]
+    pub allow_resize: bool,
+}
+
+/// Usage statistics of [`DiskPieceCache`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DiskPieceCacheStats {
+    /// Total number of elements in the cache
+    pub capacity: u32,
+    /// Number of elements with a piece stored
+    pub occupied: u32,
+    /// Number of empty elements
+    pub free: u32,
+}
+
+/// Read hit/miss counters of [`DiskPieceCache`], see [`DiskPieceCache::metrics()`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DiskPieceCacheReadMetrics {
+    /// Number of piece reads that found a piece at requested offset
+    pub hits: u64,
+    /// Number of piece reads that found requested offset empty
+    pub misses: u64,
+}
+
+/// Result of [`DiskPieceCache::compact()`]
+#[derive(Debug, Clone)]
+pub struct CompactionReport {
+    /// Pieces that were moved as `(old_offset, new_offset)` pairs
+    pub moved: Vec<(PieceCacheOffset, PieceCacheOffset)>,
+    /// Highest occupied offset after compaction, `None` if cache is empty
+    pub highest_occupied_offset: Option<PieceCacheOffset>,
 }
 
 #[derive(Debug)]
@@ -69,7 +291,17 @@ struct Inner {
     file: File,
     #[cfg(windows)]
     file: UnbufferedIoFileWindows,
+    /// Read-only memory mapping of the file, reads are served from it when present
+    #[cfg(not(windows))]
+    mmap: Option<Mmap>,
     max_num_elements: u32,
+    sync_writes: bool,
+    read_only: bool,
+    /// Serializes writes, such that file contents and in-memory index stay consistent
+    write_lock: Mutex<()>,
+    piece_index_map: Mutex<PieceIndexMap>,
+    read_hits: AtomicU64,
+    read_misses: AtomicU64,
     metrics: Option<DiskPieceCacheMetrics>,
 }
 
@@ -77,6 +309,10 @@ struct Inner {
 /// faster.
 ///
 /// Implementation is backed by a file on disk.
+///
+/// Reads use positional I/O without shared file cursor, so they can be done concurrently from
+/// multiple threads. Writes are serialized internally, but reads and writes of the same offset
+/// may still race.
 #[derive(Debug, Clone)]
 pub struct DiskPieceCache {
     inner: Arc<Inner>,
@@ -184,37 +420,302 @@ impl farm::PieceCache for DiskPieceCache {
 
 impl DiskPieceCache {
     pub(crate) const FILE_NAME: &'static str = "piece_cache.bin";
+    /// Version of the cache file layout, stored in the cache file header
+    pub const FORMAT_VERSION: u8 = 1;
+    /// Space reserved for the header at the beginning of the cache file, elements are stored right
+    /// after it
+    pub const HEADER_SIZE: u64 = DISK_SECTOR_SIZE as u64;
 
-    /// Open cache, capacity is measured in elements of [`DiskPieceCache::element_size()`] size
+    /// Open cache, capacity is measured in elements of [`DiskPieceCache::element_size()`] size.
+    ///
+    /// Cache file is created if it doesn't exist yet, see [`DiskPieceCache::open_existing()`] and
+    /// [`DiskPieceCache::create()`] for stricter alternatives. Capacity of existing cache file must
+    /// match, [`DiskPieceCacheError::CapacityMismatch`] error is returned otherwise, see
+    /// [`DiskPieceCache::open_auto()`] and [`DiskPieceCache::resize()`].
     pub fn open(
         directory: &Path,
         capacity: u32,
         id: Option<PieceCacheId>,
         registry: Option<&mut Registry>,
     ) -> Result<Self, DiskPieceCacheError> {
+        Self::open_with_options(directory, capacity, id, registry, Default::default())
+    }
+
+    /// Same as [`DiskPieceCache::open()`], but returns [`DiskPieceCacheError::NotFound`] error if
+    /// cache file doesn't exist instead of creating it
+    pub fn open_existing(
+        directory: &Path,
+        capacity: u32,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+    ) -> Result<Self, DiskPieceCacheError> {
+        Self::open_with_options(
+            directory,
+            capacity,
+            id,
+            registry,
+            DiskPieceCacheOptions {
+                open_mode: DiskPieceCacheOpenMode::OpenExisting,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`DiskPieceCache::open_existing()`], but capacity is derived from the size of the
+    /// cache file
+    pub fn open_auto(
+        directory: &Path,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+    ) -> Result<Self, DiskPieceCacheError> {
+        let file_size = match fs::metadata(directory.join(Self::FILE_NAME)) {
+            Ok(metadata) => metadata.len(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Err(DiskPieceCacheError::NotFound);
+            }
+            Err(error) => {
+                return Err(error.into());
+            }
+        };
+
+        Self::open_existing(
+            directory,
+            Self::capacity_from_file_size(file_size),
+            id,
+            registry,
+        )
+    }
+
+    /// Same as [`DiskPieceCache::open()`], but returns [`DiskPieceCacheError::AlreadyExists`] error
+    /// if cache file already exists instead of opening it, such that existing cache is never
+    /// clobbered
+    pub fn create(
+        directory: &Path,
+        capacity: u32,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+    ) -> Result<Self, DiskPieceCacheError> {
+        Self::open_with_options(
+            directory,
+            capacity,
+            id,
+            registry,
+            DiskPieceCacheOptions {
+                open_mode: DiskPieceCacheOpenMode::Create,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`DiskPieceCache::open()`], but reads are served from a read-only memory mapping of
+    /// the cache file, which avoids syscall overhead for read-heavy workloads.
+    ///
+    /// Writes still go through regular file I/O, the mapping is shared with the page cache, so
+    /// written pieces are visible through the mapping right away.
+    ///
+    /// NOTE: cache file must not be truncated by anything else while the cache is open, accessing
+    /// the mapping would crash the process otherwise. On Windows memory mapping is not supported
+    /// together with unbuffered I/O used there, so regular file I/O is used instead.
+    pub fn open_mmap(
+        directory: &Path,
+        capacity: u32,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+    ) -> Result<Self, DiskPieceCacheError> {
+        Self::open_with_options(
+            directory,
+            capacity,
+            id,
+            registry,
+            DiskPieceCacheOptions {
+                mmap: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`DiskPieceCache::open()`], but cache file is opened in read-only mode, such that it
+    /// can be inspected while being used by another process.
+    ///
+    /// All writes return [`DiskPieceCacheError::ReadOnly`] error. Cache file must already exist
+    /// with size matching the capacity.
+    ///
+    /// NOTE: reads go to the file, so they reflect writes done by another process, but in-memory
+    /// index (used by [`DiskPieceCache::offset_of()`], [`DiskPieceCache::contains()`],
+    /// [`DiskPieceCache::stats()`] and similar) is a snapshot taken on opening.
+    pub fn open_read_only(
+        directory: &Path,
+        capacity: u32,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+    ) -> Result<Self, DiskPieceCacheError> {
+        Self::open_with_options(
+            directory,
+            capacity,
+            id,
+            registry,
+            DiskPieceCacheOptions {
+                read_only: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [`DiskPieceCache::open()`], but also checks every element of the cache, returning
+    /// offsets of corrupted elements along with the cache.
+    ///
+    /// Corrupted elements found while loading the cache are reset to empty like with
+    /// [`DiskPieceCache::open()`], the rest are found with [`DiskPieceCache::scan_integrity()`]
+    /// and left as is. Either way caller can re-download pieces that were stored at returned
+    /// offsets.
+    pub fn open_and_verify(
+        directory: &Path,
+        capacity: u32,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+    ) -> Result<(Self, Vec<PieceCacheOffset>), DiskPieceCacheError> {
+        let (piece_cache, mut corrupted_offsets) =
+            Self::open_internal(directory, capacity, id, registry, Default::default())?;
+        corrupted_offsets.extend(
+            piece_cache
+                .scan_integrity()
+                .into_iter()
+                .map(|PieceCacheOffset(offset)| offset),
+        );
+        corrupted_offsets.sort_unstable();
+        corrupted_offsets.dedup();
+
+        Ok((
+            piece_cache,
+            corrupted_offsets
+                .into_iter()
+                .map(PieceCacheOffset)
+                .collect(),
+        ))
+    }
+
+    /// Same as [`DiskPieceCache::open()`], but with custom options
+    pub fn open_with_options(
+        directory: &Path,
+        capacity: u32,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+        options: DiskPieceCacheOptions,
+    ) -> Result<Self, DiskPieceCacheError> {
+        Self::open_internal(directory, capacity, id, registry, options)
+            .map(|(piece_cache, _corrupted_offsets)| piece_cache)
+    }
+
+    /// Opens the cache, returning offsets of corrupted elements found while loading it
+    fn open_internal(
+        directory: &Path,
+        capacity: u32,
+        id: Option<PieceCacheId>,
+        registry: Option<&mut Registry>,
+        options: DiskPieceCacheOptions,
+    ) -> Result<(Self, Vec<u32>), DiskPieceCacheError> {
+        let DiskPieceCacheOptions {
+            open_mode,
+            mmap,
+            sync_writes,
+            read_only,
+            allow_resize,
+        } = options;
+
         if capacity == 0 {
             return Err(DiskPieceCacheError::ZeroCapacity);
         }
 
+        let create_new = match open_mode {
+            DiskPieceCacheOpenMode::OpenOrCreate => false,
+            DiskPieceCacheOpenMode::OpenExisting => {
+                if !directory.join(Self::FILE_NAME).try_exists()? {
+                    return Err(DiskPieceCacheError::NotFound);
+                }
+                false
+            }
+            DiskPieceCacheOpenMode::Create => {
+                if read_only {
+                    return Err(DiskPieceCacheError::ReadOnly);
+                }
+                true
+            }
+        };
+        // Existence is checked atomically with creation, such that concurrently created cache
+        // file is never clobbered
+        let map_open_error = |error: io::Error| {
+            if error.kind() == io::ErrorKind::AlreadyExists {
+                DiskPieceCacheError::AlreadyExists
+            } else {
+                DiskPieceCacheError::Io(error)
+            }
+        };
+
         #[cfg(not(windows))]
         let file = OpenOptions::new()
             .read(true)
-            .write(true)
-            .create(true)
+            .write(!read_only)
+            .create(!read_only)
+            .create_new(create_new)
             .advise_random_access()
-            .open(directory.join(Self::FILE_NAME))?;
+            .open(directory.join(Self::FILE_NAME))
+            .map_err(map_open_error)?;
 
         #[cfg(not(windows))]
         file.advise_random_access()?;
 
         #[cfg(windows)]
-        let file = UnbufferedIoFileWindows::open(&directory.join(Self::FILE_NAME))?;
+        let file = if read_only {
+            UnbufferedIoFileWindows::open_read_only(&directory.join(Self::FILE_NAME))?
+        } else if create_new {
+            UnbufferedIoFileWindows::create_new(&directory.join(Self::FILE_NAME))
+                .map_err(map_open_error)?
+        } else {
+            UnbufferedIoFileWindows::open(&directory.join(Self::FILE_NAME))?
+        };
 
-        let expected_size = u64::from(Self::element_size()) * u64::from(capacity);
+        let expected_size =
+            Self::HEADER_SIZE + u64::from(Self::element_size()) * u64::from(capacity);
         // Align plot file size for disk sector size
         let expected_size =
             expected_size.div_ceil(DISK_SECTOR_SIZE as u64) * DISK_SECTOR_SIZE as u64;
-        if file.size()? != expected_size {
+        let mut file_size = file.size()?;
+        // Cache file is always resized in multiples of disk sector size
+        if file_size % DISK_SECTOR_SIZE as u64 != 0 {
+            return Err(DiskPieceCacheError::Truncated { size: file_size });
+        }
+        // Layout of existing file must be checked before resizing it
+        if file_size != 0 {
+            match Self::verify_file_header(&file) {
+                Ok(()) => {}
+                // Files created before the layout was versioned have no header, they are wiped and
+                // created from scratch since cache contents can always be downloaded again
+                Err(DiskPieceCacheError::UnsupportedVersion { found: 0, .. }) if !read_only => {
+                    info!(
+                        path = %directory.join(Self::FILE_NAME).display(),
+                        "Cache file without header created by older version found, wiping it"
+                    );
+                    file.set_len(0)?;
+                    file_size = 0;
+                }
+                Err(error) => {
+                    return Err(error);
+                }
+            }
+        }
+        if read_only && file_size != expected_size {
+            return Err(DiskPieceCacheError::UnexpectedFileSize {
+                expected: expected_size,
+                actual: file_size,
+            });
+        }
+        if file_size != 0 && file_size != expected_size && !allow_resize {
+            return Err(DiskPieceCacheError::CapacityMismatch {
+                expected: capacity,
+                actual: Self::capacity_from_file_size(file_size),
+            });
+        }
+        if file_size != expected_size {
             // Allocating the whole file (`set_len` below can create a sparse file, which will cause
             // writes to fail later)
             file.preallocate(expected_size)
@@ -222,19 +723,123 @@ impl DiskPieceCache {
             // Truncating file (if necessary)
             file.set_len(expected_size)?;
         }
+        // Newly created file, read-only cache would have failed on file size check above
+        if file_size == 0 {
+            file.write_all_at(&CacheFileHeader::new().encode(), 0)?;
+        }
+
+        #[cfg(not(windows))]
+        let mmap = if mmap {
+            // SAFETY: File is exclusively owned by this cache and its size doesn't change while it
+            // is open
+            let mmap = unsafe { Mmap::map(&file)? };
+            mmap.advise(Advice::Random)?;
+            Some(mmap)
+        } else {
+            None
+        };
+        #[cfg(windows)]
+        if mmap {
+            debug!("Memory mapping is not supported on Windows, using regular file I/O instead");
+        }
 
         // ID for cache is ephemeral unless provided explicitly
         let id = id.unwrap_or_else(PieceCacheId::new);
         let metrics = registry.map(|registry| DiskPieceCacheMetrics::new(registry, &id, capacity));
 
-        Ok(Self {
+        let piece_cache = Self {
             inner: Arc::new(Inner {
                 id,
                 file,
+                #[cfg(not(windows))]
+                mmap,
                 max_num_elements: capacity,
+                sync_writes,
+                read_only,
+                write_lock: Mutex::default(),
+                piece_index_map: Mutex::new(PieceIndexMap::new(capacity)),
+                read_hits: AtomicU64::new(0),
+                read_misses: AtomicU64::new(0),
                 metrics,
             }),
-        })
+        };
+
+        let mut corrupted_offsets = Vec::new();
+        {
+            let mut piece_index_map = PieceIndexMap::new(capacity);
+            for (offset, result) in piece_cache.contents_internal() {
+                match result {
+                    Ok(Some(piece_index)) => {
+                        piece_index_map.insert(offset, piece_index);
+                    }
+                    Ok(None) => {
+                        // Empty
+                    }
+                    Err(DiskPieceCacheError::ChecksumMismatch { .. }) => {
+                        corrupted_offsets.push(offset);
+                    }
+                    Err(error) => {
+                        warn!(%error, %offset, "Failed to read cache element");
+                    }
+                }
+            }
+            *piece_cache.inner.piece_index_map.lock() = piece_index_map;
+
+            // Corrupted elements (for example due to torn writes) are reset to empty, such that
+            // bad data is never returned and offsets can be reused
+            for &offset in &corrupted_offsets {
+                if read_only {
+                    warn!(%offset, "Corrupted cache element, can't reset in read-only mode");
+                } else {
+                    warn!(%offset, "Resetting corrupted cache element");
+                    piece_cache.remove_piece(PieceCacheOffset(offset))?;
+                }
+            }
+        }
+
+        Ok((piece_cache, corrupted_offsets))
+    }
+
+    /// Change capacity of the cache in the directory, existing pieces keep their offsets.
+    ///
+    /// Shrinking is only allowed when no pieces are stored at offsets beyond the new capacity.
+    ///
+    /// NOTE: cache must not be open while it is being resized.
+    pub fn resize(directory: &Path, new_capacity: u32) -> Result<(), DiskPieceCacheError> {
+        if new_capacity == 0 {
+            return Err(DiskPieceCacheError::ZeroCapacity);
+        }
+
+        let file_size = fs::metadata(directory.join(Self::FILE_NAME))?.len();
+        let current_capacity = Self::capacity_from_file_size(file_size);
+
+        if new_capacity < current_capacity {
+            let piece_cache = Self::open(directory, current_capacity, None, None)?;
+            let mut element = vec![0; Self::element_size() as usize];
+            for offset in new_capacity..current_capacity {
+                // Corrupted elements are treated as occupied to be on the safe side
+                if !matches!(
+                    piece_cache.read_piece_internal(offset, &mut element),
+                    Ok(None)
+                ) {
+                    return Err(DiskPieceCacheError::ShrinkWouldLoseData { offset });
+                }
+            }
+        }
+
+        Self::open_with_options(
+            directory,
+            new_capacity,
+            None,
+            None,
+            DiskPieceCacheOptions {
+                open_mode: DiskPieceCacheOpenMode::OpenExisting,
+                allow_resize: true,
+                ..Default::default()
+            },
+        )?;
+
+        Ok(())
     }
 
     /// Size of a single piece cache element
@@ -242,52 +847,96 @@ impl DiskPieceCache {
         (PieceIndex::SIZE + Piece::SIZE + mem::size_of::<Blake3Hash>()) as u32
     }
 
-    /// Contents of this piece cache
+    /// Capacity of the cache file with specified size
+    fn capacity_from_file_size(file_size: u64) -> u32 {
+        // File size is aligned to disk sector size, which is smaller than element size
+        u32::try_from(file_size.saturating_sub(Self::HEADER_SIZE) / u64::from(Self::element_size()))
+            .unwrap_or(u32::MAX)
+    }
+
+    /// Position of the element at specified offset in the cache file
+    fn element_offset(offset: u32) -> u64 {
+        Self::HEADER_SIZE + u64::from(offset) * u64::from(Self::element_size())
+    }
+
+    /// Check that cache file header matches layout of this implementation
+    fn verify_file_header<F>(file: &F) -> Result<(), DiskPieceCacheError>
+    where
+        F: FileExt,
+    {
+        let mut header_bytes = vec![0; CacheFileHeader::encoded_size()];
+        file.read_exact_at(&mut header_bytes, 0)?;
+
+        let header = match CacheFileHeader::decode(&mut header_bytes.as_slice()) {
+            Ok(header) if header.magic == FILE_MAGIC => header,
+            _ => {
+                return Err(DiskPieceCacheError::UnsupportedVersion {
+                    found: 0,
+                    expected: Self::FORMAT_VERSION,
+                });
+            }
+        };
+        if header.version != Self::FORMAT_VERSION {
+            return Err(DiskPieceCacheError::UnsupportedVersion {
+                found: header.version,
+                expected: Self::FORMAT_VERSION,
+            });
+        }
+        if header.element_size != Self::element_size() {
+            return Err(DiskPieceCacheError::UnexpectedElementSize {
+                found: header.element_size,
+                expected: Self::element_size(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Contents of this piece cache, without reading the cache file.
     ///
-    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure this
-    /// doesn't happen for the same piece being accessed!
+    /// Contents are served from in-memory index, which was built on opening while every element
+    /// was verified anyway, so cache file doesn't need to be read again. Contents are collected
+    /// upfront, so pieces written or removed while iterating are not reflected in the returned
+    /// iterator.
     pub(crate) fn contents(
         &self,
     ) -> impl ExactSizeIterator<Item = (PieceCacheOffset, Option<PieceIndex>)> + '_ {
+        if let Some(metrics) = &self.inner.metrics {
+            metrics.contents.inc();
+        }
+        let piece_indices = self.inner.piece_index_map.lock().piece_indices.clone();
+
+        piece_indices
+            .into_iter()
+            .enumerate()
+            .map(|(offset, maybe_piece_index)| (PieceCacheOffset(offset as u32), maybe_piece_index))
+    }
+
+    /// Reads contents of the cache element by element, pieces can be stored after any number of
+    /// empty elements, so the whole file is read.
+    fn contents_internal(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (u32, Result<Option<PieceIndex>, DiskPieceCacheError>)> + '_
+    {
         let mut element = vec![0; Self::element_size() as usize];
         let count_total = self
             .inner
             .metrics
             .as_ref()
-            .map(|metrics| {
-                metrics.contents.inc();
-                metrics.capacity_used.get() == 0
-            })
+            .map(|metrics| metrics.capacity_used.get() == 0)
             .unwrap_or_default();
-        let mut current_skip = 0;
 
         // TODO: Parallelize or read in larger batches
         (0..self.inner.max_num_elements).map(move |offset| {
-            if current_skip > CONTENTS_READ_SKIP_LIMIT {
-                return (PieceCacheOffset(offset), None);
+            let result = self.read_piece_internal(offset, &mut element);
+            if let Ok(Some(_piece_index)) = &result
+                && count_total
+                && let Some(metrics) = &self.inner.metrics
+            {
+                metrics.capacity_used.inc();
             }
 
-            match self.read_piece_internal(offset, &mut element) {
-                Ok(maybe_piece_index) => {
-                    if maybe_piece_index.is_none() {
-                        current_skip += 1;
-                    } else {
-                        if count_total && let Some(metrics) = &self.inner.metrics {
-                            metrics.capacity_used.inc();
-                        }
-                        current_skip = 0;
-                    }
-
-                    (PieceCacheOffset(offset), maybe_piece_index)
-                }
-                Err(error) => {
-                    warn!(%error, %offset, "Failed to read cache element");
-
-                    current_skip += 1;
-
-                    (PieceCacheOffset(offset), None)
-                }
-            }
+            (offset, result)
         })
     }
 
@@ -300,6 +949,21 @@ impl DiskPieceCache {
         offset: PieceCacheOffset,
         piece_index: PieceIndex,
         piece: &Piece,
+    ) -> Result<(), DiskPieceCacheError> {
+        self.write_piece_bytes(offset, piece_index, piece.as_ref())
+    }
+
+    /// Same as [`Self::write_piece()`], but takes piece as raw bytes, for example straight from a
+    /// decoding path.
+    ///
+    /// Length of bytes must be exactly [`Piece::SIZE`], otherwise
+    /// [`DiskPieceCacheError::InvalidPieceLength`] error is returned, writing bytes of a different
+    /// length would misalign all subsequent elements in the file.
+    pub fn write_piece_bytes(
+        &self,
+        offset: PieceCacheOffset,
+        piece_index: PieceIndex,
+        piece: &[u8],
     ) -> Result<(), DiskPieceCacheError> {
         let PieceCacheOffset(offset) = offset;
         if offset >= self.inner.max_num_elements {
@@ -308,7 +972,50 @@ impl DiskPieceCache {
                 max: self.inner.max_num_elements - 1,
             });
         }
+        if piece.len() != Piece::SIZE {
+            return Err(DiskPieceCacheError::InvalidPieceLength {
+                expected: Piece::SIZE,
+                actual: piece.len(),
+            });
+        }
+
+        self.ensure_writable()?;
+        let _write_guard = self.inner.write_lock.lock();
+        self.write_element(offset, piece_index, piece)
+    }
+
+    /// Write piece to the first free offset of the cache, returns offset it was written to.
+    ///
+    /// [`DiskPieceCacheError::Full`] error is returned if there are no free offsets left.
+    pub fn push_piece(
+        &self,
+        piece_index: PieceIndex,
+        piece: &Piece,
+    ) -> Result<PieceCacheOffset, DiskPieceCacheError> {
+        self.ensure_writable()?;
+        // Write lock is held until piece is written, such that concurrent pushes don't pick the
+        // same offset
+        let _write_guard = self.inner.write_lock.lock();
+        let offset = self
+            .inner
+            .piece_index_map
+            .lock()
+            .piece_indices
+            .iter()
+            .position(Option::is_none)
+            .ok_or(DiskPieceCacheError::Full)? as u32;
+        self.write_element(offset, piece_index, piece.as_ref())?;
+
+        Ok(PieceCacheOffset(offset))
+    }
 
+    /// Write element at specified offset, caller must hold write lock
+    fn write_element(
+        &self,
+        offset: u32,
+        piece_index: PieceIndex,
+        piece: &[u8],
+    ) -> Result<(), DiskPieceCacheError> {
         if let Some(metrics) = &self.inner.metrics {
             metrics.write_piece.inc();
             let capacity_used = i64::from(offset + 1);
@@ -316,7 +1023,7 @@ impl DiskPieceCache {
                 metrics.capacity_used.set(capacity_used);
             }
         }
-        let element_offset = u64::from(offset) * u64::from(Self::element_size());
+        let element_offset = Self::element_offset(offset);
 
         let piece_index_bytes = piece_index.to_bytes();
         self.inner
@@ -324,11 +1031,80 @@ impl DiskPieceCache {
             .write_all_at(&piece_index_bytes, element_offset)?;
         self.inner
             .file
-            .write_all_at(piece.as_ref(), element_offset + PieceIndex::SIZE as u64)?;
+            .write_all_at(piece, element_offset + PieceIndex::SIZE as u64)?;
         self.inner.file.write_all_at(
-            &blake3_hash_list(&[&piece_index_bytes, piece.as_ref()]),
+            &blake3_hash_list(&[&piece_index_bytes, piece]),
             element_offset + PieceIndex::SIZE as u64 + Piece::SIZE as u64,
         )?;
+        self.maybe_sync()?;
+        self.inner
+            .piece_index_map
+            .lock()
+            .insert(offset, piece_index);
+
+        Ok(())
+    }
+
+    /// Store multiple pieces in cache at specified offsets, replacing existing pieces if there are
+    /// any.
+    ///
+    /// All offsets are checked before anything is written, so out of range offset results in no
+    /// pieces being written. Pieces at adjacent offsets are written with a single write.
+    ///
+    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure this
+    /// doesn't happen for the same piece being accessed!
+    pub fn write_pieces<'a>(
+        &self,
+        pieces: impl IntoIterator<Item = (PieceCacheOffset, PieceIndex, &'a Piece)>,
+    ) -> Result<(), DiskPieceCacheError> {
+        let pieces = pieces.into_iter().collect::<Vec<_>>();
+        if let Some((PieceCacheOffset(offset), ..)) = pieces
+            .iter()
+            .find(|(PieceCacheOffset(offset), ..)| *offset >= self.inner.max_num_elements)
+        {
+            return Err(DiskPieceCacheError::OffsetOutsideOfRange {
+                provided: *offset,
+                max: self.inner.max_num_elements - 1,
+            });
+        }
+
+        self.ensure_writable()?;
+        let _write_guard = self.inner.write_lock.lock();
+        let element_size = Self::element_size() as usize;
+        // Offsets are below `max_num_elements` at this point, so `+ 1` can't overflow
+        for run in
+            pieces.chunk_by(|(PieceCacheOffset(a), ..), (PieceCacheOffset(b), ..)| a + 1 == *b)
+        {
+            let mut elements = Vec::with_capacity(run.len() * element_size);
+            for (_offset, piece_index, piece) in run {
+                let piece_index_bytes = piece_index.to_bytes();
+                elements.extend_from_slice(&piece_index_bytes);
+                elements.extend_from_slice(piece.as_ref());
+                elements
+                    .extend_from_slice(&blake3_hash_list(&[&piece_index_bytes, piece.as_ref()]));
+            }
+
+            let PieceCacheOffset(first_offset) = run[0].0;
+            self.inner
+                .file
+                .write_all_at(&elements, Self::element_offset(first_offset))?;
+            self.maybe_sync()?;
+
+            let mut piece_index_map = self.inner.piece_index_map.lock();
+            for &(PieceCacheOffset(offset), piece_index, _piece) in run {
+                piece_index_map.insert(offset, piece_index);
+            }
+        }
+
+        if let Some(metrics) = &self.inner.metrics
+            && let Some((PieceCacheOffset(last_offset), ..)) = pieces.last()
+        {
+            metrics.write_piece.inc_by(pieces.len() as u64);
+            let capacity_used = i64::from(last_offset + 1);
+            if metrics.capacity_used.get() != capacity_used {
+                metrics.capacity_used.set(capacity_used);
+            }
+        }
 
         Ok(())
     }
@@ -358,6 +1134,63 @@ impl DiskPieceCache {
         self.read_piece_internal(offset, &mut vec![0; Self::element_size() as usize])
     }
 
+    /// Read piece indices stored at specified offsets, `None` is returned for offsets without a
+    /// piece stored or with a corrupted element.
+    ///
+    /// Elements are verified the same way as in [`Self::read_piece_index()`], but elements at
+    /// adjacent offsets are read with a single read, which is much cheaper for many offsets.
+    pub fn read_piece_index_batch(
+        &self,
+        offsets: &[PieceCacheOffset],
+    ) -> Result<Vec<Option<PieceIndex>>, DiskPieceCacheError> {
+        if let Some(&PieceCacheOffset(offset)) = offsets
+            .iter()
+            .find(|PieceCacheOffset(offset)| *offset >= self.inner.max_num_elements)
+        {
+            warn!(%offset, "Trying to read piece out of range, this must be an implementation bug");
+            return Err(DiskPieceCacheError::OffsetOutsideOfRange {
+                provided: offset,
+                max: self.inner.max_num_elements - 1,
+            });
+        }
+
+        if let Some(metrics) = &self.inner.metrics {
+            metrics.read_piece_index.inc_by(offsets.len() as u64);
+        }
+        let element_size = Self::element_size() as usize;
+        let mut piece_indices = Vec::with_capacity(offsets.len());
+        let mut elements = Vec::new();
+        // Offsets are below `max_num_elements` at this point, so `+ 1` can't overflow
+        for run in offsets.chunk_by(|PieceCacheOffset(a), PieceCacheOffset(b)| a + 1 == *b) {
+            let PieceCacheOffset(first_offset) = run[0];
+            elements.resize(run.len() * element_size, 0);
+            self.read_element_at(&mut elements, Self::element_offset(first_offset))?;
+
+            for (&PieceCacheOffset(offset), element) in
+                run.iter().zip(elements.chunks_exact(element_size))
+            {
+                let (piece_index_bytes, remaining_bytes) = element.split_at(PieceIndex::SIZE);
+                let (piece_bytes, expected_checksum) = remaining_bytes.split_at(Piece::SIZE);
+
+                let maybe_piece_index = match Self::verify_element(
+                    offset,
+                    piece_index_bytes,
+                    piece_bytes,
+                    expected_checksum,
+                ) {
+                    Ok(maybe_piece_index) => maybe_piece_index,
+                    Err(error) => {
+                        warn!(%error, %offset, "Failed to read cache element");
+                        None
+                    }
+                };
+                piece_indices.push(maybe_piece_index);
+            }
+        }
+
+        Ok(piece_indices)
+    }
+
     /// Read piece from cache at specified offset.
     ///
     /// Returns `None` if offset is out of range.
@@ -381,7 +1214,9 @@ impl DiskPieceCache {
             metrics.read_piece.inc();
         }
         let mut element = BytesMut::zeroed(Self::element_size() as usize);
-        if let Some(piece_index) = self.read_piece_internal(offset, &mut element)? {
+        let maybe_piece_index = self.read_piece_internal(offset, &mut element)?;
+        self.record_read(maybe_piece_index.is_some());
+        if let Some(piece_index) = maybe_piece_index {
             let element = element.freeze();
             let piece =
                 Piece::try_from(element.slice_ref(&element[PieceIndex::SIZE..][..Piece::SIZE]))
@@ -392,22 +1227,494 @@ impl DiskPieceCache {
         }
     }
 
+    /// Read pieces from cache at a range of offsets with a single read, `None` is returned for
+    /// offsets without a piece stored.
+    ///
+    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure this
+    /// doesn't happen for the same piece being accessed!
+    pub fn read_pieces(
+        &self,
+        range: Range<u32>,
+    ) -> Result<Vec<(PieceCacheOffset, Option<Piece>)>, DiskPieceCacheError> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        if range.end > self.inner.max_num_elements {
+            warn!(
+                ?range,
+                "Trying to read pieces out of range, this must be an implementation bug"
+            );
+            return Err(DiskPieceCacheError::OffsetOutsideOfRange {
+                provided: range.end - 1,
+                max: self.inner.max_num_elements - 1,
+            });
+        }
+
+        if let Some(metrics) = &self.inner.metrics {
+            metrics.read_piece.inc_by(range.len() as u64);
+        }
+        let element_size = Self::element_size() as usize;
+        let mut elements = BytesMut::zeroed(range.len() * element_size);
+        self.read_element_at(&mut elements, Self::element_offset(range.start))?;
+        let elements = elements.freeze();
+
+        range
+            .zip(elements.chunks_exact(element_size))
+            .map(|(offset, element)| {
+                let (piece_index_bytes, remaining_bytes) = element.split_at(PieceIndex::SIZE);
+                let (piece_bytes, expected_checksum) = remaining_bytes.split_at(Piece::SIZE);
+
+                let maybe_piece = Self::verify_element(
+                    offset,
+                    piece_index_bytes,
+                    piece_bytes,
+                    expected_checksum,
+                )?
+                .map(|_piece_index| {
+                    Piece::try_from(elements.slice_ref(piece_bytes)).expect("Correct length; qed")
+                });
+                self.record_read(maybe_piece.is_some());
+
+                Ok((PieceCacheOffset(offset), maybe_piece))
+            })
+            .collect()
+    }
+
+    /// Read piece from cache at specified offset into provided buffer, returns `false` if there
+    /// is no piece stored at the offset (buffer contents is unspecified in that case).
+    ///
+    /// Unlike [`Self::read_piece()`] this doesn't allocate, which is useful on hot paths.
+    ///
+    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure this
+    /// doesn't happen for the same piece being accessed!
+    pub fn read_piece_into(
+        &self,
+        offset: PieceCacheOffset,
+        piece: &mut Piece,
+    ) -> Result<bool, DiskPieceCacheError> {
+        let PieceCacheOffset(offset) = offset;
+        if offset >= self.inner.max_num_elements {
+            warn!(%offset, "Trying to read piece out of range, this must be an implementation bug");
+            return Err(DiskPieceCacheError::OffsetOutsideOfRange {
+                provided: offset,
+                max: self.inner.max_num_elements - 1,
+            });
+        }
+
+        if let Some(metrics) = &self.inner.metrics {
+            metrics.read_piece.inc();
+        }
+        let element_offset = Self::element_offset(offset);
+        let mut piece_index_bytes = [0; PieceIndex::SIZE];
+        let mut expected_checksum = Blake3Hash::default();
+        self.read_element_at(&mut piece_index_bytes, element_offset)?;
+        self.read_element_at(piece.as_mut(), element_offset + PieceIndex::SIZE as u64)?;
+        self.read_element_at(
+            &mut expected_checksum,
+            element_offset + PieceIndex::SIZE as u64 + Piece::SIZE as u64,
+        )?;
+
+        let maybe_piece_index = Self::verify_element(
+            offset,
+            &piece_index_bytes,
+            piece.as_ref(),
+            &expected_checksum,
+        )?;
+        self.record_read(maybe_piece_index.is_some());
+        Ok(maybe_piece_index.is_some())
+    }
+
+    /// Advise OS to load `count` elements starting at `offset` into page cache ahead of actual
+    /// reads, for example when nearby pieces are likely to be requested next.
+    ///
+    /// Window is clamped to capacity, but `offset` itself must be within range. This is a hint
+    /// only, on platforms that don't support it this does nothing.
+    pub fn prefetch(
+        &self,
+        offset: PieceCacheOffset,
+        count: u32,
+    ) -> Result<(), DiskPieceCacheError> {
+        let PieceCacheOffset(offset) = offset;
+        if offset >= self.inner.max_num_elements {
+            return Err(DiskPieceCacheError::OffsetOutsideOfRange {
+                provided: offset,
+                max: self.inner.max_num_elements - 1,
+            });
+        }
+
+        let count = count.min(self.inner.max_num_elements - offset);
+        if count == 0 {
+            return Ok(());
+        }
+
+        self.inner.file.advise_will_need(
+            Self::element_offset(offset),
+            u64::from(count) * u64::from(Self::element_size()),
+        )?;
+
+        Ok(())
+    }
+
+    /// Move piece with its metadata from one offset to another, `to` offset must be empty.
+    ///
+    /// Piece is written at the new offset before the old offset is cleared, so the piece stays
+    /// readable during the move.
+    ///
+    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure this
+    /// doesn't happen for the same piece being accessed!
+    pub fn move_piece(
+        &self,
+        from: PieceCacheOffset,
+        to: PieceCacheOffset,
+    ) -> Result<(), DiskPieceCacheError> {
+        let PieceCacheOffset(from) = from;
+        let PieceCacheOffset(to) = to;
+        for offset in [from, to] {
+            if offset >= self.inner.max_num_elements {
+                return Err(DiskPieceCacheError::OffsetOutsideOfRange {
+                    provided: offset,
+                    max: self.inner.max_num_elements - 1,
+                });
+            }
+        }
+
+        self.ensure_writable()?;
+        let _write_guard = self.inner.write_lock.lock();
+        let mut element = vec![0; Self::element_size() as usize];
+        self.move_piece_internal(from, to, &mut element)
+    }
+
+    /// Move occupied elements towards the beginning of the cache, such that they occupy offsets
+    /// `0..occupied` without gaps left by removals, which makes contiguous batch reads more
+    /// efficient.
+    ///
+    /// Relative order of pieces is preserved. Offsets of moved pieces change, returned report
+    /// contains mapping from old to new offsets for callers that store offsets elsewhere.
+    ///
+    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure
+    /// moved pieces are not accessed concurrently with compaction!
+    pub fn compact(&self) -> Result<CompactionReport, DiskPieceCacheError> {
+        self.ensure_writable()?;
+        let _write_guard = self.inner.write_lock.lock();
+        let occupied_offsets = self
+            .inner
+            .piece_index_map
+            .lock()
+            .piece_indices
+            .iter()
+            .enumerate()
+            .filter_map(|(offset, maybe_piece_index)| {
+                maybe_piece_index.map(|_piece_index| offset as u32)
+            })
+            .collect::<Vec<_>>();
+
+        let mut element = vec![0; Self::element_size() as usize];
+        let mut moved = Vec::new();
+        for (to, from) in (0..).zip(occupied_offsets.iter().copied()) {
+            if from != to {
+                self.move_piece_internal(from, to, &mut element)?;
+                moved.push((PieceCacheOffset(from), PieceCacheOffset(to)));
+            }
+        }
+
+        Ok(CompactionReport {
+            moved,
+            highest_occupied_offset: occupied_offsets
+                .len()
+                .checked_sub(1)
+                .map(|offset| PieceCacheOffset(offset as u32)),
+        })
+    }
+
+    /// Copy all stored pieces into `destination` cache at the same offsets, for example to migrate
+    /// cache to a different disk. Returns number of pieces copied.
+    ///
+    /// Checksums are recomputed by the destination on write. Destination must be able to fit the
+    /// highest occupied offset of this cache, existing pieces in destination at other offsets are
+    /// left as is.
+    pub fn export_to(&self, destination: &Self) -> Result<u64, DiskPieceCacheError> {
+        let occupied_offsets = self
+            .cached_piece_indices()
+            .map(|(PieceCacheOffset(offset), _piece_index)| offset)
+            .collect::<Vec<_>>();
+        if let Some(&offset) = occupied_offsets.last()
+            && offset >= destination.capacity()
+        {
+            return Err(DiskPieceCacheError::DestinationTooSmall {
+                offset,
+                capacity: destination.capacity(),
+            });
+        }
+
+        let mut element = vec![0; Self::element_size() as usize];
+        let mut copied = 0;
+        for offset in occupied_offsets {
+            // Piece might have been removed since index was read, in which case it is skipped
+            let Some(piece_index) = self.read_piece_internal(offset, &mut element)? else {
+                continue;
+            };
+            let piece = Piece::try_from(&element[PieceIndex::SIZE..][..Piece::SIZE])
+                .expect("Correct length; qed");
+            destination.write_piece(PieceCacheOffset(offset), piece_index, &piece)?;
+            copied += 1;
+        }
+
+        Ok(copied)
+    }
+
+    /// Must be called with write lock held, offsets must be within range
+    fn move_piece_internal(
+        &self,
+        from: u32,
+        to: u32,
+        element: &mut [u8],
+    ) -> Result<(), DiskPieceCacheError> {
+        if self.read_piece_internal(to, element)?.is_some() {
+            return Err(DiskPieceCacheError::OffsetOccupied { offset: to });
+        }
+        let Some(piece_index) = self.read_piece_internal(from, element)? else {
+            return Err(DiskPieceCacheError::OffsetEmpty { offset: from });
+        };
+
+        // Element is written as is, piece index and checksum are already verified on read
+        self.inner
+            .file
+            .write_all_at(element, Self::element_offset(to))?;
+        self.maybe_sync()?;
+        self.inner.piece_index_map.lock().insert(to, piece_index);
+        element.fill(0);
+        self.inner
+            .file
+            .write_all_at(element, Self::element_offset(from))?;
+        self.maybe_sync()?;
+        self.inner.piece_index_map.lock().remove(from);
+
+        Ok(())
+    }
+
+    /// Remove piece stored at specified offset, if there is any, offset becomes empty afterwards.
+    ///
+    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure this
+    /// doesn't happen for the same piece being accessed!
+    pub fn remove_piece(&self, offset: PieceCacheOffset) -> Result<(), DiskPieceCacheError> {
+        let PieceCacheOffset(offset) = offset;
+        if offset >= self.inner.max_num_elements {
+            return Err(DiskPieceCacheError::OffsetOutsideOfRange {
+                provided: offset,
+                max: self.inner.max_num_elements - 1,
+            });
+        }
+
+        self.ensure_writable()?;
+        let _write_guard = self.inner.write_lock.lock();
+        // Empty element is all zeroes, there is no separate metadata to clear
+        self.inner.file.write_all_at(
+            &vec![0; Self::element_size() as usize],
+            Self::element_offset(offset),
+        )?;
+        self.maybe_sync()?;
+        self.inner.piece_index_map.lock().remove(offset);
+
+        Ok(())
+    }
+
+    /// Same as [`Self::remove_piece()`], but additionally waits for zeroes to be written to disk
+    /// before returning, such that removed piece can't be recovered from disk afterwards.
+    ///
+    /// [`Self::remove_piece()`] already overwrites the whole element with zeroes, but the write may
+    /// stay in OS buffers for a while. This costs an extra sync of the file on every call, so
+    /// prefer [`Self::remove_piece()`] followed by [`Self::flush()`] when removing many pieces.
+    pub fn remove_piece_securely(
+        &self,
+        offset: PieceCacheOffset,
+    ) -> Result<(), DiskPieceCacheError> {
+        self.remove_piece(offset)?;
+        if !self.inner.sync_writes {
+            self.inner.file.sync_data()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sync all written data to disk, so that it survives power failure.
+    ///
+    /// Not needed when cache was opened with [`DiskPieceCacheOptions::sync_writes`].
+    pub fn flush(&self) -> Result<(), DiskPieceCacheError> {
+        self.inner.file.sync_data()?;
+
+        Ok(())
+    }
+
+    /// Read hit/miss counters since the cache was opened, covers [`Self::read_piece()`],
+    /// [`Self::read_piece_into()`] and [`Self::read_pieces()`]
+    pub fn metrics(&self) -> DiskPieceCacheReadMetrics {
+        DiskPieceCacheReadMetrics {
+            hits: self.inner.read_hits.load(Ordering::Relaxed),
+            misses: self.inner.read_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record_read(&self, hit: bool) {
+        if hit {
+            self.inner.read_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.inner.read_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn ensure_writable(&self) -> Result<(), DiskPieceCacheError> {
+        if self.inner.read_only {
+            return Err(DiskPieceCacheError::ReadOnly);
+        }
+
+        Ok(())
+    }
+
+    fn maybe_sync(&self) -> io::Result<()> {
+        if self.inner.sync_writes {
+            self.inner.file.sync_data()?;
+        }
+
+        Ok(())
+    }
+
+    /// Check every element of the cache and return offsets of elements with checksum mismatch.
+    ///
+    /// Unlike [`Self::contents()`], this reads the whole cache file, so corruption that happened
+    /// since the cache was opened is found too.
+    ///
+    /// NOTE: it is possible to do concurrent reads and writes, higher level logic must ensure this
+    /// doesn't happen for the same piece being accessed!
+    pub fn scan_integrity(&self) -> Vec<PieceCacheOffset> {
+        let mut element = vec![0; Self::element_size() as usize];
+
+        (0..self.inner.max_num_elements)
+            .filter(
+                |&offset| match self.read_piece_internal(offset, &mut element) {
+                    Ok(_maybe_piece_index) => false,
+                    Err(DiskPieceCacheError::ChecksumMismatch { .. }) => true,
+                    Err(error) => {
+                        warn!(%error, %offset, "Failed to read cache element");
+                        false
+                    }
+                },
+            )
+            .map(PieceCacheOffset)
+            .collect()
+    }
+
+    /// Offsets and indices of stored pieces in ascending order of offsets, without reading the
+    /// cache file
+    pub fn cached_piece_indices(&self) -> impl Iterator<Item = (PieceCacheOffset, PieceIndex)> {
+        let piece_indices = self
+            .inner
+            .piece_index_map
+            .lock()
+            .piece_indices
+            .iter()
+            .enumerate()
+            .filter_map(|(offset, maybe_piece_index)| {
+                maybe_piece_index.map(|piece_index| (PieceCacheOffset(offset as u32), piece_index))
+            })
+            .collect::<Vec<_>>();
+
+        piece_indices.into_iter()
+    }
+
+    /// Offsets of stored pieces by their indices, without reading the cache file.
+    ///
+    /// Unlike [`Self::cached_piece_indices()`], pieces are ordered by piece index, which allows
+    /// range queries over piece indices.
+    pub fn index_to_offset_map(&self) -> BTreeMap<PieceIndex, PieceCacheOffset> {
+        self.inner
+            .piece_index_map
+            .lock()
+            .offsets
+            .iter()
+            .map(|(&piece_index, &offset)| (piece_index, offset))
+            .collect()
+    }
+
+    /// Offsets without a piece stored in ascending order, without reading the cache file.
+    ///
+    /// Offsets are collected upfront, so pieces written or removed while iterating are not
+    /// reflected in the returned iterator.
+    pub fn free_offsets(&self) -> impl Iterator<Item = PieceCacheOffset> {
+        let free_offsets = self
+            .inner
+            .piece_index_map
+            .lock()
+            .piece_indices
+            .iter()
+            .enumerate()
+            .filter(|(_offset, maybe_piece_index)| maybe_piece_index.is_none())
+            .map(|(offset, _maybe_piece_index)| PieceCacheOffset(offset as u32))
+            .collect::<Vec<_>>();
+
+        free_offsets.into_iter()
+    }
+
+    /// Number of elements the cache can store, valid offsets are `0..capacity`
+    pub fn capacity(&self) -> u32 {
+        self.inner.max_num_elements
+    }
+
+    /// Usage statistics of the cache, without reading the cache file
+    pub fn stats(&self) -> DiskPieceCacheStats {
+        let occupied = self.inner.piece_index_map.lock().occupied;
+
+        DiskPieceCacheStats {
+            capacity: self.inner.max_num_elements,
+            occupied,
+            free: self.inner.max_num_elements - occupied,
+        }
+    }
+
+    /// Whether piece with specified index is stored in the cache, without reading the cache file
+    pub fn contains(&self, piece_index: PieceIndex) -> bool {
+        self.inner
+            .piece_index_map
+            .lock()
+            .offsets
+            .contains_key(&piece_index)
+    }
+
+    /// Find offset at which piece with specified index is stored, without reading the cache file
+    pub fn offset_of(&self, piece_index: PieceIndex) -> Option<PieceCacheOffset> {
+        self.inner
+            .piece_index_map
+            .lock()
+            .offsets
+            .get(&piece_index)
+            .copied()
+    }
+
     fn read_piece_internal(
         &self,
         offset: u32,
         element: &mut [u8],
     ) -> Result<Option<PieceIndex>, DiskPieceCacheError> {
-        self.inner
-            .file
-            .read_exact_at(element, u64::from(offset) * u64::from(Self::element_size()))?;
+        self.read_element_at(element, Self::element_offset(offset))?;
 
         let (piece_index_bytes, remaining_bytes) = element.split_at(PieceIndex::SIZE);
         let (piece_bytes, expected_checksum) = remaining_bytes.split_at(Piece::SIZE);
 
+        Self::verify_element(offset, piece_index_bytes, piece_bytes, expected_checksum)
+    }
+
+    fn verify_element(
+        offset: u32,
+        piece_index_bytes: &[u8],
+        piece_bytes: &[u8],
+        expected_checksum: &[u8],
+    ) -> Result<Option<PieceIndex>, DiskPieceCacheError> {
         // Verify checksum
         let actual_checksum = blake3_hash_list(&[piece_index_bytes, piece_bytes]);
         if actual_checksum != expected_checksum {
-            if element.iter().all(|&byte| byte == 0) {
+            if [piece_index_bytes, piece_bytes, expected_checksum]
+                .iter()
+                .all(|bytes| bytes.iter().all(|&byte| byte == 0))
+            {
                 return Ok(None);
             }
 
@@ -417,7 +1724,7 @@ impl DiskPieceCache {
                 "Hash doesn't match, corrupted piece in cache"
             );
 
-            return Err(DiskPieceCacheError::ChecksumMismatch);
+            return Err(DiskPieceCacheError::ChecksumMismatch { offset });
         }
 
         let piece_index = PieceIndex::from_bytes(
@@ -428,6 +1735,26 @@ impl DiskPieceCache {
         Ok(Some(piece_index))
     }
 
+    fn read_element_at(&self, element: &mut [u8], element_offset: u64) -> io::Result<()> {
+        #[cfg(not(windows))]
+        if let Some(mmap) = &self.inner.mmap {
+            let element_bytes = usize::try_from(element_offset)
+                .ok()
+                .and_then(|element_offset| mmap.get(element_offset..)?.get(..element.len()))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Element is outside of the memory mapped file",
+                    )
+                })?;
+            element.copy_from_slice(element_bytes);
+
+            return Ok(());
+        }
+
+        self.inner.file.read_exact_at(element, element_offset)
+    }
+
     pub(crate) fn wipe(directory: &Path) -> io::Result<()> {
         let piece_cache = directory.join(Self::FILE_NAME);
         if !piece_cache.exists() {
@@ -1,7 +1,18 @@
-use crate::disk_piece_cache::{DiskPieceCache, DiskPieceCacheError, PieceCacheOffset};
+use crate::disk_piece_cache::{
+    CacheFileHeader, DiskPieceCache, DiskPieceCacheError, DiskPieceCacheOptions,
+    DiskPieceCacheReadMetrics, DiskPieceCacheStats, PieceCacheOffset,
+};
+use crate::farm;
+use crate::single_disk_farm::unbuffered_io_file_windows::DISK_SECTOR_SIZE;
+use futures::future::join_all;
+use parity_scale_codec::Encode;
 use rand::prelude::*;
 use std::assert_matches::assert_matches;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::{fs, thread};
 use subspace_core_primitives::{Piece, PieceIndex};
+use subspace_farmer_components::file_ext::FileExt;
 use tempfile::tempdir;
 
 #[test]
@@ -141,3 +152,1781 @@ fn basic() {
         );
     }
 }
+
+#[test]
+fn mmap_reads() {
+    let path = tempdir().unwrap();
+    let pieces = (0..3)
+        .map(|index| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            (PieceIndex::from(index), piece)
+        })
+        .collect::<Vec<_>>();
+
+    {
+        let disk_piece_cache = DiskPieceCache::open_mmap(path.as_ref(), 4, None, None).unwrap();
+
+        for (offset, (piece_index, piece)) in pieces.iter().enumerate() {
+            let offset = PieceCacheOffset(offset as u32);
+            assert!(disk_piece_cache.read_piece(offset).unwrap().is_none());
+
+            disk_piece_cache
+                .write_piece(offset, *piece_index, piece)
+                .unwrap();
+        }
+
+        // Written pieces are visible through the mapping right away
+        for (offset, (piece_index, piece)) in pieces.iter().enumerate() {
+            let offset = PieceCacheOffset(offset as u32);
+            assert_eq!(
+                disk_piece_cache.read_piece_index(offset).unwrap(),
+                Some(*piece_index)
+            );
+            assert_eq!(
+                disk_piece_cache.read_piece(offset).unwrap(),
+                Some((*piece_index, piece.clone()))
+            );
+        }
+        assert!(disk_piece_cache
+            .read_piece(PieceCacheOffset(3))
+            .unwrap()
+            .is_none());
+        assert_matches!(
+            disk_piece_cache.read_piece(PieceCacheOffset(4)),
+            Err(DiskPieceCacheError::OffsetOutsideOfRange { .. })
+        );
+    }
+
+    // Regular and memory mapped reads return the same data
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+        let mmap_disk_piece_cache =
+            DiskPieceCache::open_mmap(path.as_ref(), 4, None, None).unwrap();
+
+        for offset in 0..4 {
+            let offset = PieceCacheOffset(offset);
+            assert_eq!(
+                disk_piece_cache.read_piece(offset).unwrap(),
+                mmap_disk_piece_cache.read_piece(offset).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn move_piece() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+
+    let piece_index = PieceIndex::from(7);
+    let piece = {
+        let mut piece = Piece::default();
+        thread_rng().fill(piece.as_mut());
+        piece
+    };
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), piece_index, &piece)
+        .unwrap();
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(2), PieceIndex::ZERO, &Piece::default())
+        .unwrap();
+
+    // Can't move into occupied offset, from empty offset or out of range
+    assert_matches!(
+        disk_piece_cache.move_piece(PieceCacheOffset(0), PieceCacheOffset(2)),
+        Err(DiskPieceCacheError::OffsetOccupied { offset: 2 })
+    );
+    assert_matches!(
+        disk_piece_cache.move_piece(PieceCacheOffset(1), PieceCacheOffset(0)),
+        Err(DiskPieceCacheError::OffsetOccupied { offset: 0 })
+    );
+    assert_matches!(
+        disk_piece_cache.move_piece(PieceCacheOffset(1), PieceCacheOffset(1)),
+        Err(DiskPieceCacheError::OffsetEmpty { offset: 1 })
+    );
+    assert_matches!(
+        disk_piece_cache.move_piece(PieceCacheOffset(0), PieceCacheOffset(3)),
+        Err(DiskPieceCacheError::OffsetOutsideOfRange { .. })
+    );
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(0)).unwrap(),
+        Some((piece_index, piece.clone()))
+    );
+
+    disk_piece_cache
+        .move_piece(PieceCacheOffset(0), PieceCacheOffset(1))
+        .unwrap();
+
+    // Readable at the new offset and gone from the old one
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(1)).unwrap(),
+        Some((piece_index, piece))
+    );
+    assert_eq!(
+        disk_piece_cache
+            .read_piece_index(PieceCacheOffset(0))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        disk_piece_cache
+            .contents()
+            .filter(|(_offset, maybe_piece_index)| maybe_piece_index.is_some())
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn write_pieces() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+
+    let pieces = (0..3)
+        .map(|_| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        })
+        .collect::<Vec<_>>();
+
+    // Out of range offset anywhere in the batch results in nothing being written
+    assert_matches!(
+        disk_piece_cache.write_pieces([
+            (PieceCacheOffset(0), PieceIndex::from(10), &pieces[0]),
+            (PieceCacheOffset(4), PieceIndex::from(11), &pieces[1]),
+        ]),
+        Err(DiskPieceCacheError::OffsetOutsideOfRange {
+            provided: 4,
+            max: 3
+        })
+    );
+    assert_eq!(
+        disk_piece_cache
+            .contents()
+            .filter(|(_offset, maybe_piece_index)| maybe_piece_index.is_some())
+            .count(),
+        0
+    );
+
+    disk_piece_cache
+        .write_pieces(pieces.iter().enumerate().map(|(i, piece)| {
+            (
+                PieceCacheOffset(i as u32 + 1),
+                PieceIndex::from(i as u64 + 10),
+                piece,
+            )
+        }))
+        .unwrap();
+
+    assert_eq!(
+        disk_piece_cache
+            .contents()
+            .map(|(PieceCacheOffset(offset), maybe_piece_index)| (offset, maybe_piece_index))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, None),
+            (1, Some(PieceIndex::from(10))),
+            (2, Some(PieceIndex::from(11))),
+            (3, Some(PieceIndex::from(12))),
+        ]
+    );
+    for (i, piece) in pieces.into_iter().enumerate() {
+        assert_eq!(
+            disk_piece_cache
+                .read_piece(PieceCacheOffset(i as u32 + 1))
+                .unwrap(),
+            Some((PieceIndex::from(i as u64 + 10), piece))
+        );
+    }
+}
+
+#[test]
+fn offset_of() {
+    let path = tempdir().unwrap();
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+
+        assert_matches!(disk_piece_cache.offset_of(PieceIndex::from(1)), None);
+
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &Piece::default())
+            .unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &Piece::default())
+            .unwrap();
+        assert_matches!(
+            disk_piece_cache.offset_of(PieceIndex::from(1)),
+            Some(PieceCacheOffset(0))
+        );
+        assert_matches!(
+            disk_piece_cache.offset_of(PieceIndex::from(2)),
+            Some(PieceCacheOffset(1))
+        );
+
+        // Overwriting evicts old piece index
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(0), PieceIndex::from(3), &Piece::default())
+            .unwrap();
+        assert_matches!(disk_piece_cache.offset_of(PieceIndex::from(1)), None);
+        assert_matches!(
+            disk_piece_cache.offset_of(PieceIndex::from(3)),
+            Some(PieceCacheOffset(0))
+        );
+
+        // Moving updates the offset
+        disk_piece_cache
+            .move_piece(PieceCacheOffset(1), PieceCacheOffset(2))
+            .unwrap();
+        assert_matches!(
+            disk_piece_cache.offset_of(PieceIndex::from(2)),
+            Some(PieceCacheOffset(2))
+        );
+    }
+
+    // Map is rebuilt from disk on reopening
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+
+        assert_matches!(disk_piece_cache.offset_of(PieceIndex::from(1)), None);
+        assert_matches!(
+            disk_piece_cache.offset_of(PieceIndex::from(2)),
+            Some(PieceCacheOffset(2))
+        );
+        assert_matches!(
+            disk_piece_cache.offset_of(PieceIndex::from(3)),
+            Some(PieceCacheOffset(0))
+        );
+    }
+}
+
+#[test]
+fn reopen_with_gap() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+    let capacity = 12;
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), capacity, None, None).unwrap();
+        for (offset, piece_index) in [(0, 1), (9, 2), (11, 3)] {
+            disk_piece_cache
+                .write_piece(
+                    PieceCacheOffset(offset),
+                    PieceIndex::from(piece_index),
+                    &Piece::default(),
+                )
+                .unwrap();
+        }
+    }
+    // Piece after the gap is corrupted, like after a torn write
+    flip_byte(
+        &file_path,
+        DiskPieceCache::HEADER_SIZE
+            + 11 * u64::from(DiskPieceCache::element_size())
+            + PieceIndex::SIZE as u64,
+    );
+
+    // Pieces after any number of empty elements are indexed on reopening
+    let (disk_piece_cache, corrupted_offsets) =
+        DiskPieceCache::open_and_verify(path.as_ref(), capacity, None, None).unwrap();
+    assert_eq!(corrupted_offsets, vec![PieceCacheOffset(11)]);
+    assert_eq!(
+        disk_piece_cache.offset_of(PieceIndex::from(2)),
+        Some(PieceCacheOffset(9))
+    );
+    assert!(disk_piece_cache.contains(PieceIndex::from(2)));
+    assert!(!disk_piece_cache.contains(PieceIndex::from(3)));
+    assert_eq!(disk_piece_cache.stats().occupied, 2);
+    assert!(!disk_piece_cache
+        .free_offsets()
+        .any(|offset| offset == PieceCacheOffset(9)));
+    assert_eq!(
+        disk_piece_cache
+            .index_to_offset_map()
+            .get(&PieceIndex::from(2)),
+        Some(&PieceCacheOffset(9))
+    );
+
+    // Corrupted element after the gap was reset and free offsets are used without overwriting
+    // pieces after the gap
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(11)).unwrap(),
+        None
+    );
+    for _ in 1..capacity - 1 {
+        let offset = disk_piece_cache
+            .push_piece(PieceIndex::from(100), &Piece::default())
+            .unwrap();
+        assert_ne!(offset, PieceCacheOffset(0));
+        assert_ne!(offset, PieceCacheOffset(9));
+    }
+    assert_matches!(
+        disk_piece_cache.push_piece(PieceIndex::from(100), &Piece::default()),
+        Err(DiskPieceCacheError::Full)
+    );
+    assert_eq!(
+        disk_piece_cache
+            .read_piece_index(PieceCacheOffset(9))
+            .unwrap(),
+        Some(PieceIndex::from(2))
+    );
+}
+
+#[test]
+fn contents_with_holes() {
+    let path = tempdir().unwrap();
+    let capacity = 8;
+    let expected_contents = (0..capacity)
+        .map(|offset| match offset {
+            0 => (offset, Some(PieceIndex::from(1))),
+            6 => (offset, Some(PieceIndex::from(2))),
+            _ => (offset, None),
+        })
+        .collect::<Vec<_>>();
+    let contents = |disk_piece_cache: &DiskPieceCache| {
+        disk_piece_cache
+            .contents()
+            .map(|(PieceCacheOffset(offset), maybe_piece_index)| (offset, maybe_piece_index))
+            .collect::<Vec<_>>()
+    };
+
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), capacity, None, None).unwrap();
+        for offset in 0..capacity - 1 {
+            disk_piece_cache
+                .write_piece(
+                    PieceCacheOffset(offset),
+                    PieceIndex::from(u64::from(offset) + 1),
+                    &Piece::default(),
+                )
+                .unwrap();
+        }
+        // Hole larger than a few elements in front of a stored piece
+        for offset in 1..6 {
+            disk_piece_cache
+                .remove_piece(PieceCacheOffset(offset))
+                .unwrap();
+        }
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(6), PieceIndex::from(2), &Piece::default())
+            .unwrap();
+
+        assert_eq!(contents(&disk_piece_cache), expected_contents);
+    }
+
+    // Pieces after the hole are still reported after reopening
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), capacity, None, None).unwrap();
+    assert_eq!(contents(&disk_piece_cache), expected_contents);
+}
+
+#[test]
+fn checksum_mismatch() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+
+        let piece = {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        };
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &piece)
+            .unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &piece)
+            .unwrap();
+
+        assert!(disk_piece_cache.scan_integrity().is_empty());
+
+        // Flip a byte of the piece stored at offset 1
+        flip_byte(
+            &file_path,
+            DiskPieceCache::HEADER_SIZE
+                + u64::from(DiskPieceCache::element_size())
+                + PieceIndex::SIZE as u64
+                + 10,
+        );
+
+        assert_matches!(
+            disk_piece_cache.read_piece(PieceCacheOffset(1)),
+            Err(DiskPieceCacheError::ChecksumMismatch { offset: 1 })
+        );
+        assert_matches!(
+            disk_piece_cache.read_piece(PieceCacheOffset(0)),
+            Ok(Some(_))
+        );
+        assert_matches!(
+            disk_piece_cache.scan_integrity().as_slice(),
+            [PieceCacheOffset(1)]
+        );
+    }
+
+    // Corrupted element is reset to empty on reopening
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+    assert_matches!(disk_piece_cache.read_piece(PieceCacheOffset(1)), Ok(None));
+    assert!(disk_piece_cache.scan_integrity().is_empty());
+    assert_eq!(disk_piece_cache.stats().occupied, 1);
+}
+
+#[test]
+fn open_and_verify() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+    {
+        let (disk_piece_cache, corrupted_offsets) =
+            DiskPieceCache::open_and_verify(path.as_ref(), 3, None, None).unwrap();
+        assert!(corrupted_offsets.is_empty());
+
+        let piece = {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        };
+        for offset in 0..3 {
+            disk_piece_cache
+                .write_piece(
+                    PieceCacheOffset(offset),
+                    PieceIndex::from(u64::from(offset)),
+                    &piece,
+                )
+                .unwrap();
+        }
+    }
+
+    // Flip a byte of the piece stored at offset 2
+    flip_byte(
+        &file_path,
+        DiskPieceCache::HEADER_SIZE
+            + 2 * u64::from(DiskPieceCache::element_size())
+            + PieceIndex::SIZE as u64
+            + 10,
+    );
+
+    let (disk_piece_cache, corrupted_offsets) =
+        DiskPieceCache::open_and_verify(path.as_ref(), 3, None, None).unwrap();
+    assert_matches!(corrupted_offsets.as_slice(), [PieceCacheOffset(2)]);
+    assert_matches!(disk_piece_cache.read_piece(PieceCacheOffset(2)), Ok(None));
+    assert_eq!(disk_piece_cache.stats().occupied, 2);
+
+    // Nothing is reported once corrupted element was reset
+    drop(disk_piece_cache);
+    let (_disk_piece_cache, corrupted_offsets) =
+        DiskPieceCache::open_and_verify(path.as_ref(), 3, None, None).unwrap();
+    assert!(corrupted_offsets.is_empty());
+}
+
+fn flip_byte(file_path: &Path, offset: u64) {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(file_path)
+        .unwrap();
+    let mut byte = [0];
+    file.read_exact_at(&mut byte, offset).unwrap();
+    byte[0] ^= 1;
+    file.write_all_at(&byte, offset).unwrap();
+}
+
+#[test]
+fn torn_write() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+        for offset in 0..3 {
+            disk_piece_cache
+                .write_piece(
+                    PieceCacheOffset(offset),
+                    PieceIndex::from(u64::from(offset)),
+                    &Piece::default(),
+                )
+                .unwrap();
+        }
+    }
+
+    // Length that is not aligned to disk sector size is reported as truncation
+    let file = OpenOptions::new().write(true).open(&file_path).unwrap();
+    let element_size = u64::from(DiskPieceCache::element_size());
+    file.set_len(DiskPieceCache::HEADER_SIZE + element_size + element_size / 2)
+        .unwrap();
+    assert_matches!(
+        DiskPieceCache::open(path.as_ref(), 3, None, None),
+        Err(DiskPieceCacheError::Truncated { .. })
+    );
+
+    // Aligned length that cuts element at offset 1 in the middle, element is reset to empty
+    file.set_len(
+        (DiskPieceCache::HEADER_SIZE + element_size + element_size / 2) / DISK_SECTOR_SIZE as u64
+            * DISK_SECTOR_SIZE as u64,
+    )
+    .unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+    assert_eq!(
+        disk_piece_cache
+            .read_piece_index(PieceCacheOffset(0))
+            .unwrap(),
+        Some(PieceIndex::ZERO)
+    );
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(1)).unwrap(),
+        None
+    );
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(2)).unwrap(),
+        None
+    );
+    assert!(disk_piece_cache.scan_integrity().is_empty());
+    assert_eq!(disk_piece_cache.stats().occupied, 1);
+}
+
+#[test]
+fn remove_piece() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &Piece::default())
+        .unwrap();
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &Piece::default())
+        .unwrap();
+
+    assert_matches!(
+        disk_piece_cache.remove_piece(PieceCacheOffset(3)),
+        Err(DiskPieceCacheError::OffsetOutsideOfRange {
+            provided: 3,
+            max: 2
+        })
+    );
+
+    disk_piece_cache.remove_piece(PieceCacheOffset(0)).unwrap();
+
+    assert_eq!(
+        disk_piece_cache
+            .contents()
+            .filter(|(_offset, maybe_piece_index)| maybe_piece_index.is_some())
+            .count(),
+        1
+    );
+    assert_eq!(
+        disk_piece_cache
+            .read_piece_index(PieceCacheOffset(0))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(0)).unwrap(),
+        None
+    );
+    assert_matches!(disk_piece_cache.offset_of(PieceIndex::from(1)), None);
+
+    // Removing from empty offset is fine
+    disk_piece_cache.remove_piece(PieceCacheOffset(2)).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn async_read_write() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+
+    let pieces = (0..4)
+        .map(|_| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        })
+        .collect::<Vec<_>>();
+
+    // Blocking I/O is offloaded by the `PieceCache` implementation, so concurrent calls don't block
+    // the runtime
+    join_all(pieces.iter().enumerate().map(|(offset, piece)| {
+        farm::PieceCache::write_piece(
+            &disk_piece_cache,
+            PieceCacheOffset(offset as u32),
+            PieceIndex::from(offset as u64),
+            piece,
+        )
+    }))
+    .await
+    .into_iter()
+    .collect::<Result<Vec<()>, _>>()
+    .unwrap();
+
+    let read_pieces =
+        join_all((0..4).map(|offset| {
+            farm::PieceCache::read_piece(&disk_piece_cache, PieceCacheOffset(offset))
+        }))
+        .await;
+    for (offset, (read_piece, piece)) in read_pieces.into_iter().zip(pieces).enumerate() {
+        assert_eq!(
+            read_piece.unwrap(),
+            Some((PieceIndex::from(offset as u64), piece))
+        );
+    }
+}
+
+#[test]
+fn stats() {
+    let path = tempdir().unwrap();
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+
+        assert_eq!(
+            disk_piece_cache.stats(),
+            DiskPieceCacheStats {
+                capacity: 4,
+                occupied: 0,
+                free: 4,
+            }
+        );
+
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &Piece::default())
+            .unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &Piece::default())
+            .unwrap();
+        // Overwriting doesn't change occupancy
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(1), PieceIndex::from(3), &Piece::default())
+            .unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(2), PieceIndex::from(4), &Piece::default())
+            .unwrap();
+        assert_eq!(
+            disk_piece_cache.stats(),
+            DiskPieceCacheStats {
+                capacity: 4,
+                occupied: 3,
+                free: 1,
+            }
+        );
+
+        disk_piece_cache.remove_piece(PieceCacheOffset(1)).unwrap();
+        assert_eq!(
+            disk_piece_cache.stats(),
+            DiskPieceCacheStats {
+                capacity: 4,
+                occupied: 2,
+                free: 2,
+            }
+        );
+    }
+
+    // Stats are restored on reopening
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+    assert_eq!(
+        disk_piece_cache.stats(),
+        DiskPieceCacheStats {
+            capacity: 4,
+            occupied: 2,
+            free: 2,
+        }
+    );
+}
+
+#[test]
+fn read_piece_into() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+
+    let pieces = (0..3)
+        .map(|_| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        })
+        .collect::<Vec<_>>();
+    for (offset, piece) in pieces.iter().enumerate() {
+        disk_piece_cache
+            .write_piece(
+                PieceCacheOffset(offset as u32),
+                PieceIndex::from(offset as u64),
+                piece,
+            )
+            .unwrap();
+    }
+
+    // The same buffer is reused for all reads
+    let mut buffer = Piece::default();
+    for (offset, piece) in pieces.iter().enumerate() {
+        assert!(disk_piece_cache
+            .read_piece_into(PieceCacheOffset(offset as u32), &mut buffer)
+            .unwrap());
+        assert_eq!(&buffer, piece);
+    }
+    assert!(!disk_piece_cache
+        .read_piece_into(PieceCacheOffset(3), &mut buffer)
+        .unwrap());
+    assert_matches!(
+        disk_piece_cache.read_piece_into(PieceCacheOffset(4), &mut buffer),
+        Err(DiskPieceCacheError::OffsetOutsideOfRange { .. })
+    );
+}
+
+#[test]
+fn cached_piece_indices() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 5, None, None).unwrap();
+
+    assert_eq!(disk_piece_cache.cached_piece_indices().count(), 0);
+
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), PieceIndex::from(7), &Piece::default())
+        .unwrap();
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(3), PieceIndex::from(2), &Piece::default())
+        .unwrap();
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(1), PieceIndex::from(5), &Piece::default())
+        .unwrap();
+
+    assert_eq!(
+        disk_piece_cache
+            .cached_piece_indices()
+            .map(|(PieceCacheOffset(offset), piece_index)| (offset, piece_index))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, PieceIndex::from(7)),
+            (1, PieceIndex::from(5)),
+            (3, PieceIndex::from(2)),
+        ]
+    );
+}
+
+#[test]
+fn read_piece_index_batch() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 5, None, None).unwrap();
+
+    // Piece index zero is distinguishable from an empty element
+    for (offset, piece_index) in [(0, 0), (1, 7), (3, 2), (4, 9)] {
+        disk_piece_cache
+            .write_piece(
+                PieceCacheOffset(offset),
+                PieceIndex::from(piece_index),
+                &Piece::default(),
+            )
+            .unwrap();
+    }
+    disk_piece_cache.remove_piece(PieceCacheOffset(4)).unwrap();
+
+    let offsets = [4, 0, 3, 2, 1, 3]
+        .into_iter()
+        .map(PieceCacheOffset)
+        .collect::<Vec<_>>();
+    let piece_indices = disk_piece_cache.read_piece_index_batch(&offsets).unwrap();
+    assert_eq!(
+        piece_indices,
+        offsets
+            .iter()
+            .map(|&offset| disk_piece_cache.read_piece_index(offset).unwrap())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        piece_indices,
+        vec![
+            None,
+            Some(PieceIndex::from(0)),
+            Some(PieceIndex::from(2)),
+            None,
+            Some(PieceIndex::from(7)),
+            Some(PieceIndex::from(2)),
+        ]
+    );
+
+    // Corrupted element is reported as empty
+    flip_byte(
+        &path.path().join(DiskPieceCache::FILE_NAME),
+        DiskPieceCache::HEADER_SIZE
+            + 3 * u64::from(DiskPieceCache::element_size())
+            + PieceIndex::SIZE as u64
+            + 10,
+    );
+    assert_eq!(
+        disk_piece_cache
+            .read_piece_index_batch(&[PieceCacheOffset(1), PieceCacheOffset(3)])
+            .unwrap(),
+        vec![Some(PieceIndex::from(7)), None]
+    );
+
+    assert!(disk_piece_cache
+        .read_piece_index_batch(&[])
+        .unwrap()
+        .is_empty());
+    assert_matches!(
+        disk_piece_cache.read_piece_index_batch(&[PieceCacheOffset(1), PieceCacheOffset(5)]),
+        Err(DiskPieceCacheError::OffsetOutsideOfRange {
+            provided: 5,
+            max: 4
+        })
+    );
+}
+
+#[test]
+fn index_to_offset_map() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 5, None, None).unwrap();
+
+    assert!(disk_piece_cache.index_to_offset_map().is_empty());
+
+    for (offset, piece_index) in [(0, 9), (1, 4), (2, 7), (4, 1)] {
+        disk_piece_cache
+            .write_piece(
+                PieceCacheOffset(offset),
+                PieceIndex::from(piece_index),
+                &Piece::default(),
+            )
+            .unwrap();
+    }
+    disk_piece_cache.remove_piece(PieceCacheOffset(2)).unwrap();
+
+    let index_to_offset_map = disk_piece_cache.index_to_offset_map();
+    assert_eq!(
+        index_to_offset_map
+            .iter()
+            .map(|(&piece_index, &PieceCacheOffset(offset))| (piece_index, offset))
+            .collect::<Vec<_>>(),
+        vec![
+            (PieceIndex::from(1), 4),
+            (PieceIndex::from(4), 1),
+            (PieceIndex::from(9), 0),
+        ]
+    );
+    assert_eq!(
+        index_to_offset_map
+            .range(PieceIndex::from(2)..PieceIndex::from(9))
+            .map(|(&piece_index, _offset)| piece_index)
+            .collect::<Vec<_>>(),
+        vec![PieceIndex::from(4)]
+    );
+}
+
+#[test]
+fn file_header() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &Piece::default())
+            .unwrap();
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&file_path)
+        .unwrap();
+    let mut original_header = vec![0; CacheFileHeader::encoded_size()];
+    file.read_exact_at(&mut original_header, 0).unwrap();
+
+    // Header is written on creation and preserved on reopening
+    assert_eq!(original_header, CacheFileHeader::new().encode());
+    assert!(DiskPieceCache::open(path.as_ref(), 2, None, None)
+        .unwrap()
+        .contains(PieceIndex::from(1)));
+
+    // File written with a newer layout version is rejected
+    let header = CacheFileHeader {
+        version: DiskPieceCache::FORMAT_VERSION + 1,
+        ..CacheFileHeader::new()
+    };
+    file.write_all_at(&header.encode(), 0).unwrap();
+    assert_matches!(
+        DiskPieceCache::open(path.as_ref(), 2, None, None),
+        Err(DiskPieceCacheError::UnsupportedVersion { found, expected })
+            if found == DiskPieceCache::FORMAT_VERSION + 1
+                && expected == DiskPieceCache::FORMAT_VERSION
+    );
+
+    // File written with different element size is rejected
+    let header = CacheFileHeader {
+        element_size: DiskPieceCache::element_size() + 1,
+        ..CacheFileHeader::new()
+    };
+    file.write_all_at(&header.encode(), 0).unwrap();
+    assert_matches!(
+        DiskPieceCache::open(path.as_ref(), 2, None, None),
+        Err(DiskPieceCacheError::UnexpectedElementSize { .. })
+    );
+
+    // Rejected file is not resized
+    let header = CacheFileHeader {
+        version: DiskPieceCache::FORMAT_VERSION + 1,
+        ..CacheFileHeader::new()
+    };
+    file.write_all_at(&header.encode(), 0).unwrap();
+    assert_matches!(
+        DiskPieceCache::open(path.as_ref(), 3, None, None),
+        Err(DiskPieceCacheError::UnsupportedVersion { .. })
+    );
+    assert_eq!(
+        file.metadata().unwrap().len(),
+        (DiskPieceCache::HEADER_SIZE + 2 * u64::from(DiskPieceCache::element_size()))
+            .div_ceil(DISK_SECTOR_SIZE as u64)
+            * DISK_SECTOR_SIZE as u64
+    );
+
+    // Original header makes the file usable again
+    file.write_all_at(&original_header, 0).unwrap();
+    assert!(DiskPieceCache::open(path.as_ref(), 2, None, None)
+        .unwrap()
+        .contains(PieceIndex::from(1)));
+
+    // File without a header is reported as version 0 in read-only mode
+    file.write_all_at(&vec![0; original_header.len()], 0)
+        .unwrap();
+    assert_matches!(
+        DiskPieceCache::open_read_only(path.as_ref(), 2, None, None),
+        Err(DiskPieceCacheError::UnsupportedVersion { found: 0, .. })
+    );
+}
+
+#[test]
+fn legacy_file_without_header() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+    let capacity = 2;
+
+    // Layout before the header was introduced: elements right from the beginning of the file
+    {
+        let legacy_size = (u64::from(DiskPieceCache::element_size()) * u64::from(capacity))
+            .div_ceil(DISK_SECTOR_SIZE as u64)
+            * DISK_SECTOR_SIZE as u64;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&file_path)
+            .unwrap();
+        file.set_len(legacy_size).unwrap();
+        let mut element = vec![0; DiskPieceCache::element_size() as usize];
+        element[..PieceIndex::SIZE].copy_from_slice(&PieceIndex::from(1).to_bytes());
+        thread_rng().fill(&mut element[PieceIndex::SIZE..]);
+        file.write_all_at(&element, 0).unwrap();
+    }
+
+    // Farm opens the legacy file by wiping it instead of failing to start
+    let disk_piece_cache = crate::single_disk_farm::open_piece_cache(
+        path.as_ref(),
+        capacity,
+        farm::PieceCacheId::new(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(disk_piece_cache.capacity(), capacity);
+    assert_eq!(disk_piece_cache.stats().occupied, 0);
+    assert!(!disk_piece_cache.contains(PieceIndex::from(1)));
+
+    // Wiped cache is usable and has a header now
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &Piece::default())
+        .unwrap();
+    drop(disk_piece_cache);
+    let file = OpenOptions::new().read(true).open(&file_path).unwrap();
+    let mut header = vec![0; CacheFileHeader::encoded_size()];
+    file.read_exact_at(&mut header, 0).unwrap();
+    assert_eq!(header, CacheFileHeader::new().encode());
+    assert_eq!(
+        file.metadata().unwrap().len(),
+        (DiskPieceCache::HEADER_SIZE
+            + u64::from(capacity) * u64::from(DiskPieceCache::element_size()))
+        .div_ceil(DISK_SECTOR_SIZE as u64)
+            * DISK_SECTOR_SIZE as u64
+    );
+    assert_eq!(
+        DiskPieceCache::open(path.as_ref(), capacity, None, None)
+            .unwrap()
+            .offset_of(PieceIndex::from(2)),
+        Some(PieceCacheOffset(1))
+    );
+}
+
+#[test]
+fn free_offsets() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 5, None, None).unwrap();
+
+    assert_eq!(
+        disk_piece_cache
+            .free_offsets()
+            .map(|PieceCacheOffset(offset)| offset)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 4]
+    );
+
+    for offset in [0, 1, 3] {
+        disk_piece_cache
+            .write_piece(
+                PieceCacheOffset(offset),
+                PieceIndex::from(u64::from(offset)),
+                &Piece::default(),
+            )
+            .unwrap();
+    }
+    disk_piece_cache.remove_piece(PieceCacheOffset(1)).unwrap();
+
+    assert_eq!(
+        disk_piece_cache
+            .free_offsets()
+            .map(|PieceCacheOffset(offset)| offset)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 4]
+    );
+    assert_eq!(
+        disk_piece_cache.free_offsets().count() as u32,
+        disk_piece_cache.stats().free
+    );
+
+    // Every free offset reads back as empty
+    for offset in disk_piece_cache.free_offsets() {
+        assert!(disk_piece_cache.read_piece_index(offset).unwrap().is_none());
+    }
+}
+
+#[test]
+fn push_piece() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+    let pieces = (0..4)
+        .map(|_| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        })
+        .collect::<Vec<_>>();
+
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(1), PieceIndex::from(100), &pieces[0])
+        .unwrap();
+
+    // Pieces land at free offsets, skipping occupied ones
+    for (piece_index, expected_offset) in [(1, 0), (2, 2)] {
+        assert_eq!(
+            disk_piece_cache
+                .push_piece(PieceIndex::from(piece_index), &pieces[piece_index as usize])
+                .unwrap(),
+            PieceCacheOffset(expected_offset)
+        );
+        assert_eq!(
+            disk_piece_cache
+                .read_piece(PieceCacheOffset(expected_offset))
+                .unwrap(),
+            Some((
+                PieceIndex::from(piece_index),
+                pieces[piece_index as usize].clone()
+            ))
+        );
+    }
+
+    assert_matches!(
+        disk_piece_cache.push_piece(PieceIndex::from(3), &pieces[3]),
+        Err(DiskPieceCacheError::Full)
+    );
+    assert!(!disk_piece_cache.contains(PieceIndex::from(3)));
+
+    // Removed offset is reused
+    disk_piece_cache.remove_piece(PieceCacheOffset(1)).unwrap();
+    assert_eq!(
+        disk_piece_cache
+            .push_piece(PieceIndex::from(3), &pieces[3])
+            .unwrap(),
+        PieceCacheOffset(1)
+    );
+}
+
+#[test]
+fn resize() {
+    let path = tempdir().unwrap();
+    let pieces = (0..2)
+        .map(|_| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        })
+        .collect::<Vec<_>>();
+
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+        for (offset, piece) in pieces.iter().enumerate() {
+            disk_piece_cache
+                .write_piece(
+                    PieceCacheOffset(offset as u32),
+                    PieceIndex::from(offset as u64),
+                    piece,
+                )
+                .unwrap();
+        }
+    }
+
+    DiskPieceCache::resize(path.as_ref(), 4).unwrap();
+
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+        for (offset, piece) in pieces.iter().enumerate() {
+            assert_eq!(
+                disk_piece_cache
+                    .read_piece(PieceCacheOffset(offset as u32))
+                    .unwrap(),
+                Some((PieceIndex::from(offset as u64), piece.clone()))
+            );
+        }
+        // New offsets are available for writes
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(3), PieceIndex::from(3), &Piece::default())
+            .unwrap();
+    }
+
+    // Can't shrink below occupied offsets
+    assert_matches!(
+        DiskPieceCache::resize(path.as_ref(), 3),
+        Err(DiskPieceCacheError::ShrinkWouldLoseData { offset: 3 })
+    );
+    assert_matches!(
+        DiskPieceCache::resize(path.as_ref(), 0),
+        Err(DiskPieceCacheError::ZeroCapacity)
+    );
+
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+        disk_piece_cache.remove_piece(PieceCacheOffset(3)).unwrap();
+    }
+
+    // Shrinking without losing pieces is fine
+    DiskPieceCache::resize(path.as_ref(), 2).unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+    assert_eq!(disk_piece_cache.stats().occupied, 2);
+}
+
+#[test]
+fn flush() {
+    let path = tempdir().unwrap();
+    let piece = {
+        let mut piece = Piece::default();
+        thread_rng().fill(piece.as_mut());
+        piece
+    };
+
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &piece)
+            .unwrap();
+        disk_piece_cache.flush().unwrap();
+    }
+    {
+        let disk_piece_cache = DiskPieceCache::open_with_options(
+            path.as_ref(),
+            2,
+            None,
+            None,
+            DiskPieceCacheOptions {
+                sync_writes: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            disk_piece_cache.read_piece(PieceCacheOffset(0)).unwrap(),
+            Some((PieceIndex::from(1), piece.clone()))
+        );
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &piece)
+            .unwrap();
+    }
+
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(1)).unwrap(),
+        Some((PieceIndex::from(2), piece))
+    );
+}
+
+#[test]
+fn concurrent_access() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 8, None, None).unwrap();
+
+    let pieces = (0..8)
+        .map(|_| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        })
+        .collect::<Vec<_>>();
+
+    // Concurrent writes to distinct offsets
+    thread::scope(|scope| {
+        for (offset, piece) in pieces.iter().enumerate() {
+            let disk_piece_cache = &disk_piece_cache;
+            scope.spawn(move || {
+                disk_piece_cache
+                    .write_piece(
+                        PieceCacheOffset(offset as u32),
+                        PieceIndex::from(offset as u64),
+                        piece,
+                    )
+                    .unwrap();
+            });
+        }
+    });
+    assert_eq!(disk_piece_cache.stats().occupied, 8);
+
+    // Concurrent reads of distinct offsets don't interfere with each other
+    thread::scope(|scope| {
+        for (offset, piece) in pieces.iter().enumerate() {
+            let disk_piece_cache = &disk_piece_cache;
+            scope.spawn(move || {
+                let mut buffer = Piece::default();
+                for _ in 0..10 {
+                    assert_eq!(
+                        disk_piece_cache
+                            .read_piece(PieceCacheOffset(offset as u32))
+                            .unwrap(),
+                        Some((PieceIndex::from(offset as u64), piece.clone()))
+                    );
+                    assert!(disk_piece_cache
+                        .read_piece_into(PieceCacheOffset(offset as u32), &mut buffer)
+                        .unwrap());
+                    assert_eq!(&buffer, piece);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn contains() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+
+    assert!(!disk_piece_cache.contains(PieceIndex::from(1)));
+
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &Piece::default())
+        .unwrap();
+    assert!(disk_piece_cache.contains(PieceIndex::from(1)));
+
+    // Overwritten piece is no longer cached
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), PieceIndex::from(2), &Piece::default())
+        .unwrap();
+    assert!(!disk_piece_cache.contains(PieceIndex::from(1)));
+    assert!(disk_piece_cache.contains(PieceIndex::from(2)));
+
+    // Removed piece is no longer cached
+    disk_piece_cache.remove_piece(PieceCacheOffset(0)).unwrap();
+    assert!(!disk_piece_cache.contains(PieceIndex::from(2)));
+}
+
+#[test]
+fn read_pieces() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 6, None, None).unwrap();
+
+    // Populate every other offset
+    for offset in (0..6).step_by(2) {
+        let mut piece = Piece::default();
+        thread_rng().fill(piece.as_mut());
+        disk_piece_cache
+            .write_piece(
+                PieceCacheOffset(offset),
+                PieceIndex::from(u64::from(offset)),
+                &piece,
+            )
+            .unwrap();
+    }
+
+    let pieces = disk_piece_cache.read_pieces(1..6).unwrap();
+    assert_eq!(pieces.len(), 5);
+    for (PieceCacheOffset(offset), maybe_piece) in pieces {
+        assert_eq!(
+            maybe_piece,
+            disk_piece_cache
+                .read_piece(PieceCacheOffset(offset))
+                .unwrap()
+                .map(|(_piece_index, piece)| piece)
+        );
+        assert_eq!(maybe_piece.is_some(), offset % 2 == 0);
+    }
+
+    assert!(disk_piece_cache.read_pieces(3..3).unwrap().is_empty());
+    assert_matches!(
+        disk_piece_cache.read_pieces(4..7),
+        Err(DiskPieceCacheError::OffsetOutsideOfRange {
+            provided: 6,
+            max: 5
+        })
+    );
+}
+
+#[test]
+fn read_only() {
+    let path = tempdir().unwrap();
+
+    // Read-only cache doesn't create the file
+    assert_matches!(
+        DiskPieceCache::open_read_only(path.as_ref(), 2, None, None),
+        Err(DiskPieceCacheError::Io(_))
+    );
+
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+
+    let mut piece = Piece::default();
+    thread_rng().fill(piece.as_mut());
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &piece)
+        .unwrap();
+
+    // Capacity must match the file, read-only cache can't resize it
+    assert_matches!(
+        DiskPieceCache::open_read_only(path.as_ref(), 3, None, None),
+        Err(DiskPieceCacheError::UnexpectedFileSize { .. })
+    );
+
+    let read_only_cache = DiskPieceCache::open_read_only(path.as_ref(), 2, None, None).unwrap();
+    assert_eq!(
+        read_only_cache.read_piece(PieceCacheOffset(0)).unwrap(),
+        Some((PieceIndex::from(1), piece.clone()))
+    );
+
+    // Writes done by the writer after opening are visible to the reader
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &piece)
+        .unwrap();
+    assert_eq!(
+        read_only_cache.read_piece(PieceCacheOffset(1)).unwrap(),
+        Some((PieceIndex::from(2), piece.clone()))
+    );
+
+    assert_matches!(
+        read_only_cache.write_piece(PieceCacheOffset(1), PieceIndex::from(3), &piece),
+        Err(DiskPieceCacheError::ReadOnly)
+    );
+    assert_matches!(
+        read_only_cache.remove_piece(PieceCacheOffset(0)),
+        Err(DiskPieceCacheError::ReadOnly)
+    );
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(0)).unwrap(),
+        Some((PieceIndex::from(1), piece))
+    );
+}
+
+#[test]
+fn capacity() {
+    let path = tempdir().unwrap();
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+        assert_eq!(disk_piece_cache.capacity(), 3);
+
+        // Last valid offset is right below capacity
+        disk_piece_cache
+            .write_piece(
+                PieceCacheOffset(disk_piece_cache.capacity() - 1),
+                PieceIndex::from(1),
+                &Piece::default(),
+            )
+            .unwrap();
+        assert_matches!(
+            disk_piece_cache.write_piece(
+                PieceCacheOffset(disk_piece_cache.capacity()),
+                PieceIndex::from(2),
+                &Piece::default(),
+            ),
+            Err(DiskPieceCacheError::OffsetOutsideOfRange { .. })
+        );
+    }
+
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 3, None, None).unwrap();
+    assert_eq!(disk_piece_cache.capacity(), 3);
+}
+
+#[test]
+fn zero_capacity() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+
+    // Zero-capacity cache is rejected upfront instead of failing every write later
+    assert_matches!(
+        DiskPieceCache::open(path.as_ref(), 0, None, None),
+        Err(DiskPieceCacheError::ZeroCapacity)
+    );
+    assert_matches!(
+        DiskPieceCache::create(path.as_ref(), 0, None, None),
+        Err(DiskPieceCacheError::ZeroCapacity)
+    );
+    assert_matches!(
+        DiskPieceCache::open_read_only(path.as_ref(), 0, None, None),
+        Err(DiskPieceCacheError::ZeroCapacity)
+    );
+    // Rejected cache doesn't leave a file behind
+    assert!(!file_path.exists());
+
+    // Existing cache can't be reopened with zero capacity, file is left intact
+    DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+    let file_size = fs::metadata(&file_path).unwrap().len();
+    assert_matches!(
+        DiskPieceCache::open_existing(path.as_ref(), 0, None, None),
+        Err(DiskPieceCacheError::ZeroCapacity)
+    );
+    assert_eq!(fs::metadata(&file_path).unwrap().len(), file_size);
+
+    // Empty file has zero capacity
+    fs::write(&file_path, []).unwrap();
+    assert_matches!(
+        DiskPieceCache::open_auto(path.as_ref(), None, None),
+        Err(DiskPieceCacheError::ZeroCapacity)
+    );
+}
+
+#[test]
+fn open_auto() {
+    let path = tempdir().unwrap();
+
+    assert_matches!(
+        DiskPieceCache::open_auto(path.as_ref(), None, None),
+        Err(DiskPieceCacheError::NotFound)
+    );
+
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(3), PieceIndex::from(3), &Piece::default())
+            .unwrap();
+    }
+
+    let disk_piece_cache = DiskPieceCache::open_auto(path.as_ref(), None, None).unwrap();
+    assert_eq!(disk_piece_cache.capacity(), 4);
+    assert_eq!(
+        disk_piece_cache.offset_of(PieceIndex::from(3)),
+        Some(PieceCacheOffset(3))
+    );
+    drop(disk_piece_cache);
+
+    // Capacity of existing file is validated rather than trusted
+    assert_matches!(
+        DiskPieceCache::open(path.as_ref(), 2, None, None),
+        Err(DiskPieceCacheError::CapacityMismatch {
+            expected: 2,
+            actual: 4
+        })
+    );
+    assert_matches!(
+        DiskPieceCache::open_existing(path.as_ref(), 5, None, None),
+        Err(DiskPieceCacheError::CapacityMismatch {
+            expected: 5,
+            actual: 4
+        })
+    );
+    // File is left intact
+    assert_eq!(
+        DiskPieceCache::open_auto(path.as_ref(), None, None)
+            .unwrap()
+            .capacity(),
+        4
+    );
+
+    // Resizing has to be requested explicitly
+    let disk_piece_cache = DiskPieceCache::open_with_options(
+        path.as_ref(),
+        5,
+        None,
+        None,
+        DiskPieceCacheOptions {
+            allow_resize: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(disk_piece_cache.capacity(), 5);
+}
+
+#[test]
+fn compact() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 6, None, None).unwrap();
+
+    // Empty cache has nothing to compact
+    let report = disk_piece_cache.compact().unwrap();
+    assert!(report.moved.is_empty());
+    assert!(report.highest_occupied_offset.is_none());
+
+    let pieces = (0..6)
+        .map(|_| {
+            let mut piece = Piece::default();
+            thread_rng().fill(piece.as_mut());
+            piece
+        })
+        .collect::<Vec<_>>();
+    for (offset, piece) in pieces.iter().enumerate() {
+        disk_piece_cache
+            .write_piece(
+                PieceCacheOffset(offset as u32),
+                PieceIndex::from(offset as u64),
+                piece,
+            )
+            .unwrap();
+    }
+
+    // Remove interior entries
+    disk_piece_cache.remove_piece(PieceCacheOffset(1)).unwrap();
+    disk_piece_cache.remove_piece(PieceCacheOffset(3)).unwrap();
+
+    let report = disk_piece_cache.compact().unwrap();
+    assert_eq!(
+        report
+            .moved
+            .iter()
+            .map(|(PieceCacheOffset(from), PieceCacheOffset(to))| (*from, *to))
+            .collect::<Vec<_>>(),
+        vec![(2, 1), (4, 2), (5, 3)]
+    );
+    assert_matches!(report.highest_occupied_offset, Some(PieceCacheOffset(3)));
+
+    // Surviving pieces are contiguous from offset 0 and in the same order
+    for (new_offset, old_offset) in [0, 2, 4, 5].into_iter().enumerate() {
+        assert_eq!(
+            disk_piece_cache
+                .read_piece(PieceCacheOffset(new_offset as u32))
+                .unwrap(),
+            Some((
+                PieceIndex::from(old_offset as u64),
+                pieces[old_offset].clone()
+            ))
+        );
+        assert_matches!(
+            disk_piece_cache.offset_of(PieceIndex::from(old_offset as u64)),
+            Some(PieceCacheOffset(offset)) if offset == new_offset as u32
+        );
+    }
+    for offset in 4..6 {
+        assert!(disk_piece_cache
+            .read_piece(PieceCacheOffset(offset))
+            .unwrap()
+            .is_none());
+    }
+    assert_eq!(disk_piece_cache.stats().occupied, 4);
+}
+
+#[test]
+fn read_metrics() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+    assert_eq!(
+        disk_piece_cache.metrics(),
+        DiskPieceCacheReadMetrics { hits: 0, misses: 0 }
+    );
+
+    assert!(disk_piece_cache
+        .read_piece(PieceCacheOffset(0))
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        disk_piece_cache.metrics(),
+        DiskPieceCacheReadMetrics { hits: 0, misses: 1 }
+    );
+
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &Piece::default())
+        .unwrap();
+    assert!(disk_piece_cache
+        .read_piece(PieceCacheOffset(0))
+        .unwrap()
+        .is_some());
+    assert!(disk_piece_cache
+        .read_piece_into(PieceCacheOffset(0), &mut Piece::default())
+        .unwrap());
+    assert_eq!(
+        disk_piece_cache.metrics(),
+        DiskPieceCacheReadMetrics { hits: 2, misses: 1 }
+    );
+
+    // Batch reads count every offset
+    disk_piece_cache.read_pieces(0..2).unwrap();
+    assert_eq!(
+        disk_piece_cache.metrics(),
+        DiskPieceCacheReadMetrics { hits: 3, misses: 2 }
+    );
+}
+
+#[test]
+fn export_to() {
+    let source_path = tempdir().unwrap();
+    let source = DiskPieceCache::open(source_path.as_ref(), 4, None, None).unwrap();
+
+    for offset in [0, 2, 3] {
+        let mut piece = Piece::default();
+        thread_rng().fill(piece.as_mut());
+        source
+            .write_piece(
+                PieceCacheOffset(offset),
+                PieceIndex::from(u64::from(offset)),
+                &piece,
+            )
+            .unwrap();
+    }
+
+    // Destination must fit the highest occupied offset
+    {
+        let destination_path = tempdir().unwrap();
+        let destination = DiskPieceCache::open(destination_path.as_ref(), 3, None, None).unwrap();
+        assert_matches!(
+            source.export_to(&destination),
+            Err(DiskPieceCacheError::DestinationTooSmall {
+                offset: 3,
+                capacity: 3
+            })
+        );
+        assert_eq!(destination.stats().occupied, 0);
+    }
+
+    let destination_path = tempdir().unwrap();
+    let destination = DiskPieceCache::open(destination_path.as_ref(), 6, None, None).unwrap();
+    assert_eq!(source.export_to(&destination).unwrap(), 3);
+
+    for offset in 0..4 {
+        assert_eq!(
+            destination.read_piece(PieceCacheOffset(offset)).unwrap(),
+            source.read_piece(PieceCacheOffset(offset)).unwrap()
+        );
+    }
+    for offset in 4..6 {
+        assert!(destination
+            .read_piece(PieceCacheOffset(offset))
+            .unwrap()
+            .is_none());
+    }
+}
+
+#[test]
+fn open_modes() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+
+    // Opening existing cache fails if there is no cache yet and doesn't create the file
+    assert_matches!(
+        DiskPieceCache::open_existing(path.as_ref(), 2, None, None),
+        Err(DiskPieceCacheError::NotFound)
+    );
+    assert!(!file_path.exists());
+
+    // Creating cache works when there is no cache yet
+    {
+        let disk_piece_cache = DiskPieceCache::create(path.as_ref(), 2, None, None).unwrap();
+        disk_piece_cache
+            .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &Piece::default())
+            .unwrap();
+    }
+
+    // Creating cache again fails and doesn't clobber existing cache
+    assert_matches!(
+        DiskPieceCache::create(path.as_ref(), 2, None, None),
+        Err(DiskPieceCacheError::AlreadyExists)
+    );
+
+    // Existing cache can be opened now
+    {
+        let disk_piece_cache = DiskPieceCache::open_existing(path.as_ref(), 2, None, None).unwrap();
+        assert!(disk_piece_cache.contains(PieceIndex::from(1)));
+    }
+
+    // Regular open works in both cases
+    {
+        let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+        assert!(disk_piece_cache.contains(PieceIndex::from(1)));
+    }
+    let other_path = tempdir().unwrap();
+    DiskPieceCache::open(other_path.as_ref(), 2, None, None).unwrap();
+    assert!(other_path.path().join(DiskPieceCache::FILE_NAME).exists());
+}
+
+#[test]
+fn remove_piece_securely() {
+    let path = tempdir().unwrap();
+    let file_path = path.path().join(DiskPieceCache::FILE_NAME);
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+
+    let mut piece = Piece::default();
+    thread_rng().fill(piece.as_mut());
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(0), PieceIndex::from(1), &piece)
+        .unwrap();
+    disk_piece_cache
+        .write_piece(PieceCacheOffset(1), PieceIndex::from(2), &piece)
+        .unwrap();
+
+    disk_piece_cache
+        .remove_piece_securely(PieceCacheOffset(1))
+        .unwrap();
+    assert!(!disk_piece_cache.contains(PieceIndex::from(2)));
+
+    let element_size = DiskPieceCache::element_size() as usize;
+    let contents = fs::read(&file_path).unwrap();
+    assert!(
+        contents[DiskPieceCache::HEADER_SIZE as usize + element_size..][..element_size]
+            .iter()
+            .all(|&byte| byte == 0)
+    );
+    // Other element is not affected
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(0)).unwrap(),
+        Some((PieceIndex::from(1), piece))
+    );
+}
+
+#[test]
+fn invalid_piece_length() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 2, None, None).unwrap();
+
+    let mut piece = Piece::default();
+    thread_rng().fill(piece.as_mut());
+    disk_piece_cache
+        .write_piece_bytes(PieceCacheOffset(1), PieceIndex::from(2), piece.as_ref())
+        .unwrap();
+
+    assert_matches!(
+        disk_piece_cache.write_piece_bytes(
+            PieceCacheOffset(0),
+            PieceIndex::from(1),
+            &piece.as_ref()[..Piece::SIZE - 1]
+        ),
+        Err(DiskPieceCacheError::InvalidPieceLength {
+            expected: Piece::SIZE,
+            actual
+        }) if actual == Piece::SIZE - 1
+    );
+
+    // Nothing was written and subsequent element is intact
+    assert!(disk_piece_cache
+        .read_piece(PieceCacheOffset(0))
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        disk_piece_cache.read_piece(PieceCacheOffset(1)).unwrap(),
+        Some((PieceIndex::from(2), piece))
+    );
+}
+
+#[test]
+fn prefetch() {
+    let path = tempdir().unwrap();
+    let disk_piece_cache = DiskPieceCache::open(path.as_ref(), 4, None, None).unwrap();
+
+    disk_piece_cache.prefetch(PieceCacheOffset(0), 4).unwrap();
+    disk_piece_cache.prefetch(PieceCacheOffset(3), 0).unwrap();
+    // Window is clamped to capacity
+    disk_piece_cache.prefetch(PieceCacheOffset(2), 10).unwrap();
+
+    assert_matches!(
+        disk_piece_cache.prefetch(PieceCacheOffset(4), 1),
+        Err(DiskPieceCacheError::OffsetOutsideOfRange {
+            provided: 4,
+            max: 3
+        })
+    );
+}
@@ -15,7 +15,7 @@ mod plotting;
 mod reward_signing;
 pub mod unbuffered_io_file_windows;
 
-use crate::disk_piece_cache::{DiskPieceCache, DiskPieceCacheError};
+use crate::disk_piece_cache::{DiskPieceCache, DiskPieceCacheError, DiskPieceCacheOptions};
 use crate::farm::{
     Farm, FarmId, FarmingError, FarmingNotification, HandlerFn, PieceCacheId, PieceReader,
     PlottedSectors, SectorUpdate,
@@ -856,14 +856,14 @@ impl SingleDiskFarm {
                 } else {
                     Some(task::block_in_place(|| {
                         if let Some(registry) = registry {
-                            DiskPieceCache::open(
+                            open_piece_cache(
                                 &directory,
                                 piece_cache_capacity,
-                                Some(id),
+                                id,
                                 Some(*registry.lock()),
                             )
                         } else {
-                            DiskPieceCache::open(&directory, piece_cache_capacity, Some(id), None)
+                            open_piece_cache(&directory, piece_cache_capacity, id, None)
                         }
                     })?)
                 },
@@ -1430,7 +1430,8 @@ impl SingleDiskFarm {
                 - fixed_space_usage
                 - plot_file_size
                 - (sector_metadata_size as u64 * target_sector_count);
-            (cache_space / u64::from(DiskPieceCache::element_size())) as u32
+            (cache_space.saturating_sub(DiskPieceCache::HEADER_SIZE)
+                / u64::from(DiskPieceCache::element_size())) as u32
         } else {
             0
         };
@@ -2312,14 +2313,16 @@ impl SingleDiskFarm {
             };
 
             let element_size = DiskPieceCache::element_size();
-            let number_of_cached_elements = cache_size / u64::from(element_size);
+            let number_of_cached_elements =
+                cache_size.saturating_sub(DiskPieceCache::HEADER_SIZE) / u64::from(element_size);
             let dummy_element = vec![0; element_size as usize];
             (0..number_of_cached_elements)
                 .into_par_iter()
                 .map_with(vec![0; element_size as usize], |element, cache_offset| {
                     let _span_guard = span.enter();
 
-                    let offset = cache_offset * u64::from(element_size);
+                    let offset =
+                        DiskPieceCache::HEADER_SIZE + cache_offset * u64::from(element_size);
                     if let Err(error) = cache_file.read_exact_at(element, offset) {
                         warn!(
                             path = %file.display(),
@@ -2397,6 +2400,27 @@ impl SingleDiskFarm {
     }
 }
 
+/// Opens piece cache of the farm in the directory.
+///
+/// Capacity follows allocated space, which might have changed since the cache was created.
+pub(crate) fn open_piece_cache(
+    directory: &Path,
+    capacity: u32,
+    id: PieceCacheId,
+    registry: Option<&mut Registry>,
+) -> Result<DiskPieceCache, DiskPieceCacheError> {
+    DiskPieceCache::open_with_options(
+        directory,
+        capacity,
+        Some(id),
+        registry,
+        DiskPieceCacheOptions {
+            allow_resize: true,
+            ..Default::default()
+        },
+    )
+}
+
 fn write_dummy_sector_metadata(
     metadata_file: &File,
     metadata_file_path: &Path,
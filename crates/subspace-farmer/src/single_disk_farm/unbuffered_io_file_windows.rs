@@ -59,6 +59,11 @@ impl FileExt for UnbufferedIoFileWindows {
         Ok(())
     }
 
+    fn advise_will_need(&self, _offset: u64, _len: u64) -> io::Result<()> {
+        // Ignore, there is no page cache with unbuffered I/O
+        Ok(())
+    }
+
     fn read_exact_at(&self, buf: &mut [u8], mut offset: u64) -> io::Result<()> {
         if buf.is_empty() {
             return Ok(());
@@ -144,6 +149,36 @@ impl UnbufferedIoFileWindows {
             .truncate(false)
             .open(path)?;
 
+        Ok(Self::from_file(file))
+    }
+
+    /// Same as [`UnbufferedIoFileWindows::open()`], but file must not exist yet,
+    /// [`io::ErrorKind::AlreadyExists`] error is returned otherwise.
+    pub fn create_new(path: &Path) -> io::Result<Self> {
+        let mut open_options = OpenOptions::new();
+        #[cfg(windows)]
+        open_options.advise_unbuffered();
+        let file = open_options
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+
+        Ok(Self::from_file(file))
+    }
+
+    /// Same as [`UnbufferedIoFileWindows::open()`], but file is opened in read-only mode and must
+    /// already exist.
+    pub fn open_read_only(path: &Path) -> io::Result<Self> {
+        let mut open_options = OpenOptions::new();
+        #[cfg(windows)]
+        open_options.advise_unbuffered();
+        let file = open_options.read(true).open(path)?;
+
+        Ok(Self::from_file(file))
+    }
+
+    fn from_file(file: File) -> Self {
         // Physical sector size on many SSDs is smaller than 4096 and should improve performance
         let physical_sector_size = if file.read_at(&mut [0; 512], 512).is_ok() {
             512
@@ -151,7 +186,7 @@ impl UnbufferedIoFileWindows {
             DISK_SECTOR_SIZE
         };
 
-        Ok(Self {
+        Self {
             file,
             physical_sector_size,
             // In many cases we'll want to read this much at once, so pre-allocate it right away
@@ -159,7 +194,7 @@ impl UnbufferedIoFileWindows {
                 [0; DISK_SECTOR_SIZE];
                 MAX_READ_SIZE / DISK_SECTOR_SIZE
             ]),
-        })
+        }
     }
 
     /// Truncates or extends the underlying file, updating the size of this file to become `size`.
@@ -167,6 +202,11 @@ impl UnbufferedIoFileWindows {
         self.file.set_len(size)
     }
 
+    /// Synchronizes written data of the underlying file to disk.
+    pub fn sync_data(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
     fn read_exact_at_internal<'a>(
         &self,
         scratch_buffer: &'a mut Vec<[u8; DISK_SECTOR_SIZE]>,
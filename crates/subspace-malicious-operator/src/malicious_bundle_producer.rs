@@ -1,5 +1,7 @@
 use crate::malicious_bundle_tamper::MaliciousBundleTamper;
-use domain_client_operator::domain_bundle_producer::DomainBundleProducer;
+use domain_client_operator::domain_bundle_producer::{
+    BundleLimits, BundleOutcome, DomainBundleProducer,
+};
 use domain_client_operator::domain_bundle_proposer::DomainBundleProposer;
 use domain_client_operator::{OpaqueBundleFor, OperatorSlotInfo};
 use domain_runtime_primitives::opaque::Block as DomainBlock;
@@ -144,6 +146,8 @@ where
             domain_bundle_proposer,
             Arc::new(bundle_sender),
             operator_keystore.clone(),
+            BundleLimits::default(),
+            None,
             // The malicious operator doesn't skip empty bundle
             false,
             false,
@@ -173,6 +177,7 @@ where
         let slot = new_slot_info.slot;
         self.bundle_producer
             .produce_bundle(operator_id, new_slot_info)
+            .map_ok(BundleOutcome::into_opaque_bundle)
             .unwrap_or_else(move |error| {
                 tracing::error!(
                     ?slot,
@@ -1,7 +1,7 @@
 use crate::malicious_bundle_producer::MaliciousBundleProducer;
 use crate::{create_malicious_operator_configuration, DomainCli};
 use cross_domain_message_gossip::{ChainMsg, Message};
-use domain_client_operator::{BootstrapResult, OperatorStreams};
+use domain_client_operator::{BootstrapResult, BundleLimits, OperatorStreams};
 use domain_eth_service::provider::EthProvider;
 use domain_eth_service::DefaultEthConfig;
 use domain_runtime_primitives::opaque::Block as DomainBlock;
@@ -159,6 +159,7 @@ impl DomainInstanceStarter {
                     gossip_message_sink,
                     domain_message_receiver,
                     provider: eth_provider,
+                    bundle_limits: BundleLimits::default(),
                     skip_empty_bundle_production: true,
                     skip_out_of_order_slot: false,
                     // Always set it to `None` to not running the normal bundle producer
@@ -218,6 +219,7 @@ impl DomainInstanceStarter {
                     gossip_message_sink,
                     domain_message_receiver,
                     provider: DefaultProvider,
+                    bundle_limits: BundleLimits::default(),
                     skip_empty_bundle_production: true,
                     skip_out_of_order_slot: false,
                     // Always set it to `None` to not running the normal bundle producer
@@ -2,7 +2,7 @@ use crate::commands::run::shared::RpcOptions;
 use crate::commands::shared::{store_key_in_keystore, KeystoreOptions};
 use crate::Error;
 use clap::Parser;
-use domain_client_operator::{BootstrapResult, OperatorStreams};
+use domain_client_operator::{BootstrapResult, BundleLimits, OperatorStreams};
 use domain_eth_service::provider::EthProvider;
 use domain_eth_service::DefaultEthConfig;
 use domain_runtime_primitives::opaque::Block as DomainBlock;
@@ -490,6 +490,7 @@ pub(super) async fn run_domain(
                 gossip_message_sink,
                 domain_message_receiver,
                 provider: eth_provider,
+                bundle_limits: BundleLimits::default(),
                 skip_empty_bundle_production: true,
                 skip_out_of_order_slot: false,
                 maybe_operator_id: operator_id,
@@ -529,6 +530,7 @@ pub(super) async fn run_domain(
                 gossip_message_sink,
                 domain_message_receiver,
                 provider: DefaultProvider,
+                bundle_limits: BundleLimits::default(),
                 skip_empty_bundle_production: true,
                 skip_out_of_order_slot: false,
                 maybe_operator_id: operator_id,
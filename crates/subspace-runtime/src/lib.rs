@@ -64,8 +64,9 @@ use sp_core::{OpaqueMetadata, H256};
 use sp_domains::bundle_producer_election::BundleProducerElectionParams;
 use sp_domains::{
     ChannelId, DomainAllowlistUpdates, DomainId, DomainInstanceData, DomainsHoldIdentifier,
-    ExecutionReceiptFor, MessengerHoldIdentifier, OpaqueBundle, OperatorId, OperatorPublicKey,
-    StakingHoldIdentifier, DOMAIN_STORAGE_FEE_MULTIPLIER, INITIAL_DOMAIN_TX_RANGE,
+    EpochIndex, ExecutionReceiptFor, MessengerHoldIdentifier, OpaqueBundle, OperatorId,
+    OperatorPublicKey, OperatorStatusInfo, PendingWithdrawal, StakingHoldIdentifier,
+    DOMAIN_STORAGE_FEE_MULTIPLIER, INITIAL_DOMAIN_TX_RANGE,
 };
 use sp_domains_fraud_proof::fraud_proof::FraudProof;
 use sp_domains_fraud_proof::storage_proof::{
@@ -121,7 +122,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_version: 5,
     impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 0,
+    transaction_version: 1,
     state_version: 0,
     extrinsic_state_version: 0,
 };
@@ -848,8 +849,19 @@ pub type VersionCheckedMigrateDomainsV0ToV1<T> = VersionedMigration<
     <T as frame_system::Config>::DbWeight,
 >;
 
+pub type VersionCheckedMigrateDomainsV1ToV2<T> = VersionedMigration<
+    1,
+    2,
+    pallet_domains::migrations::VersionUncheckedMigrateV1ToV2<T>,
+    pallet_domains::Pallet<T>,
+    <T as frame_system::Config>::DbWeight,
+>;
+
 // TODO: remove once the migrations are done.
-pub type Migrations = (VersionCheckedMigrateDomainsV0ToV1<Runtime>,);
+pub type Migrations = (
+    VersionCheckedMigrateDomainsV0ToV1<Runtime>,
+    VersionCheckedMigrateDomainsV1ToV2<Runtime>,
+);
 
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
@@ -1273,6 +1285,30 @@ impl_runtime_apis! {
         fn domain_sudo_call(domain_id: DomainId) -> Option<Vec<u8>> {
             Domains::domain_sudo_call(domain_id)
         }
+
+        fn operator_rankings(domain_id: DomainId) -> Vec<(OperatorId, Balance, Perbill)> {
+            Domains::operator_rankings(domain_id)
+        }
+
+        fn operator_stake_at(operator_id: OperatorId, epoch_index: EpochIndex) -> Option<Balance> {
+            Domains::operator_stake_at(operator_id, epoch_index)
+        }
+
+        fn min_stake_to_enter(domain_id: DomainId) -> Balance {
+            Domains::min_stake_to_enter(domain_id)
+        }
+
+        fn pending_deposit(operator_id: OperatorId, nominator_id: AccountId) -> Option<Balance> {
+            Domains::pending_deposit(operator_id, nominator_id)
+        }
+
+        fn pending_withdrawal(operator_id: OperatorId, nominator_id: AccountId) -> Option<PendingWithdrawal<Balance, Balance>> {
+            Domains::pending_withdrawal(operator_id, nominator_id)
+        }
+
+        fn operator_status(operator_id: OperatorId) -> Option<OperatorStatusInfo> {
+            Domains::operator_status(operator_id)
+        }
     }
 
     impl sp_domains::BundleProducerElectionApi<Block, Balance> for Runtime {
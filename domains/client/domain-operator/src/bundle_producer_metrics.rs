@@ -0,0 +1,88 @@
+//! Bundle producer metrics
+
+use sp_domains::DomainId;
+use substrate_prometheus_endpoint::{
+    register, CounterVec, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry, U64,
+};
+
+const DOMAIN_ID_LABEL: &str = "domain_id";
+
+/// Prometheus metrics of the domain bundle producers, labelled by the domain id.
+///
+/// The metrics are registered once and shared by the bundle producers reporting to the same
+/// registry.
+#[derive(Clone)]
+pub struct BundleProducerMetrics {
+    bundles_produced: CounterVec<U64>,
+    slots_claimed: CounterVec<U64>,
+    slots_skipped: CounterVec<U64>,
+    bundle_extrinsic_count: HistogramVec,
+}
+
+impl BundleProducerMetrics {
+    pub fn new(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            bundles_produced: register(
+                CounterVec::new(
+                    Opts::new(
+                        "domain_bundle_producer_bundles_produced",
+                        "Total number of bundles produced",
+                    ),
+                    &[DOMAIN_ID_LABEL],
+                )?,
+                registry,
+            )?,
+            slots_claimed: register(
+                CounterVec::new(
+                    Opts::new(
+                        "domain_bundle_producer_slots_claimed",
+                        "Total number of slots with the bundle election won",
+                    ),
+                    &[DOMAIN_ID_LABEL],
+                )?,
+                registry,
+            )?,
+            slots_skipped: register(
+                CounterVec::new(
+                    Opts::new(
+                        "domain_bundle_producer_slots_skipped",
+                        "Total number of slots skipped by the bundle producer, e.g. due to lagging behind",
+                    ),
+                    &[DOMAIN_ID_LABEL],
+                )?,
+                registry,
+            )?,
+            bundle_extrinsic_count: register(
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "domain_bundle_producer_bundle_extrinsic_count",
+                        "Number of extrinsics in the produced bundles",
+                    )
+                    .buckets(vec![0.0, 1.0, 5.0, 10.0, 50.0, 100.0, 500.0, 1000.0, 5000.0]),
+                    &[DOMAIN_ID_LABEL],
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    pub(crate) fn on_slot_claimed(&self, domain_id: DomainId) {
+        self.slots_claimed
+            .with_label_values(&[&domain_id.to_string()])
+            .inc();
+    }
+
+    pub(crate) fn on_slot_skipped(&self, domain_id: DomainId) {
+        self.slots_skipped
+            .with_label_values(&[&domain_id.to_string()])
+            .inc();
+    }
+
+    pub(crate) fn on_bundle_produced(&self, domain_id: DomainId, extrinsic_count: usize) {
+        let domain_id = domain_id.to_string();
+        self.bundles_produced.with_label_values(&[&domain_id]).inc();
+        self.bundle_extrinsic_count
+            .with_label_values(&[&domain_id])
+            .observe(extrinsic_count as f64);
+    }
+}
@@ -1,26 +1,34 @@
 use crate::bundle_producer_election_solver::BundleProducerElectionSolver;
+use crate::bundle_producer_metrics::BundleProducerMetrics;
 use crate::domain_bundle_proposer::DomainBundleProposer;
 use crate::utils::OperatorSlotInfo;
-use crate::BundleSender;
+use crate::{BundleReceiver, BundleSender, CompactBundleFor};
 use codec::Decode;
+use futures::future::{self, Either, LocalBoxFuture};
+use futures::{stream, Stream, StreamExt};
 use sc_client_api::{AuxStore, BlockBackend};
+use sc_transaction_pool_api::InPoolTransaction;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::HeaderBackend;
 use sp_consensus_slots::Slot;
 use sp_domains::core_api::DomainCoreApi;
 use sp_domains::{
-    Bundle, BundleProducerElectionApi, DomainId, DomainsApi, OperatorId, OperatorPublicKey,
-    OperatorSignature, SealedBundleHeader,
+    Bundle, BundleProducerElectionApi, DomainId, DomainsApi, HeaderHashingFor, OperatorId,
+    OperatorPublicKey, OperatorSignature, SealedBundleHeader,
 };
 use sp_keystore::KeystorePtr;
 use sp_messenger::MessengerApi;
 use sp_runtime::traits::{Block as BlockT, NumberFor, Zero};
 use sp_runtime::RuntimeAppPublic;
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
+use std::collections::BTreeMap;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use subspace_runtime_primitives::Balance;
-use tracing::info;
+use tracing::{info, Instrument};
 
 type OpaqueBundle<Block, CBlock> = sp_domains::OpaqueBundle<
     NumberFor<CBlock>,
@@ -29,6 +37,127 @@ type OpaqueBundle<Block, CBlock> = sp_domains::OpaqueBundle<
     Balance,
 >;
 
+/// Log target of the bundle producer.
+///
+/// The logs are emitted within the `bundle_producer` span that has the `domain_id` field, so the
+/// logs of a single domain can be selected with a directive like
+/// `bundle-producer[bundle_producer{domain_id=0}]=debug`.
+pub const LOG_TARGET: &str = "bundle-producer";
+
+/// Default time limit of collecting transactions for a bundle, half of the consensus slot
+/// duration.
+pub const DEFAULT_PROPOSAL_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Operator side limits of the produced bundle, applied on top of the bundle limit defined by
+/// the runtime.
+///
+/// Transactions that don't fit into the bundle are left in the pool for the next slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleLimits {
+    /// Maximum total encoded size of the bundle extrinsics in bytes.
+    pub max_bundle_size_bytes: u32,
+    /// Maximum number of extrinsics in the bundle.
+    pub max_extrinsics: u32,
+}
+
+impl Default for BundleLimits {
+    fn default() -> Self {
+        Self {
+            max_bundle_size_bytes: u32::MAX,
+            max_extrinsics: u32::MAX,
+        }
+    }
+}
+
+/// Order in which the ready transactions of the pool are considered for inclusion into a bundle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransactionSelectionStrategy {
+    /// Transactions are considered in the order they are provided by the ready queue of the pool.
+    #[default]
+    FifoReady,
+    /// Transactions with higher priority, which is derived from the tip and the fee of the
+    /// transaction, are considered first, regardless of the order of the ready queue.
+    ///
+    /// A transaction that is considered before the transactions it depends on fails the validity
+    /// check and is left in the pool for the next bundle.
+    FeeDescending,
+}
+
+/// Heartbeat emitted by the bundle producer on every slot, regardless of the election outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleProducerHeartbeat {
+    /// Slot processed by the bundle producer.
+    pub slot: Slot,
+    /// Whether the operator claimed the bundle election at this slot.
+    pub claimed: bool,
+}
+
+/// Callback invoked with the [`BundleProducerHeartbeat`] on every slot.
+pub type BundleProducerHeartbeatFn = Arc<dyn Fn(BundleProducerHeartbeat) + Send + Sync>;
+
+/// Reason of skipping the bundle production at a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleSkipReason {
+    /// The operator is lagging behind the receipt chain on the consensus chain.
+    OperatorLagging,
+    /// The slot is not newer than the last processed slot.
+    OutOfOrderSlot,
+    /// There is neither extrinsic nor receipt to confirm for the bundle.
+    EmptyBundle,
+    /// The operator signing key is not available in the keystore.
+    MissingSigningKey,
+    /// The bundle production is cancelled as a newer slot arrived.
+    Cancelled,
+    /// The consensus block at which the election was solved is no longer canonical.
+    ConsensusChainReorg,
+    /// The bundle signature produced by the keystore doesn't match the operator signing key.
+    InvalidSignature,
+}
+
+/// Cancellation token of the in-flight bundle production, shared between the bundle producer
+/// and the slot notification handler.
+///
+/// The bundle production of a slot is cancelled once a newer slot is noted, the cancellation is
+/// checked at the points where the bundle production can be stopped without side effect.
+#[derive(Debug, Clone, Default)]
+pub struct BundleProductionCancellation {
+    latest_slot: Arc<AtomicU64>,
+}
+
+impl BundleProductionCancellation {
+    /// Note a new slot, cancels the bundle production of any older slot.
+    pub fn notify_new_slot(&self, slot: Slot) {
+        self.latest_slot
+            .fetch_max(u64::from(slot), Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the bundle production of the given slot is cancelled.
+    pub fn is_cancelled(&self, slot: Slot) -> bool {
+        self.latest_slot.load(Ordering::SeqCst) > u64::from(slot)
+    }
+}
+
+/// Outcome of the bundle production at a slot.
+#[derive(Debug, Clone)]
+pub enum BundleOutcome<Block: BlockT, CBlock: BlockT> {
+    /// The operator won the bundle election and produced a bundle.
+    Produced(OpaqueBundle<Block, CBlock>),
+    /// The operator didn't win the bundle election.
+    ElectionLost,
+    /// The bundle production is skipped.
+    Skipped(BundleSkipReason),
+}
+
+impl<Block: BlockT, CBlock: BlockT> BundleOutcome<Block, CBlock> {
+    /// Returns the produced bundle if any.
+    pub fn into_opaque_bundle(self) -> Option<OpaqueBundle<Block, CBlock>> {
+        match self {
+            Self::Produced(opaque_bundle) => Some(opaque_bundle),
+            Self::ElectionLost | Self::Skipped(_) => None,
+        }
+    }
+}
+
 pub struct DomainBundleProducer<Block, CBlock, Client, CClient, TransactionPool>
 where
     Block: BlockT,
@@ -41,6 +170,9 @@ where
     keystore: KeystorePtr,
     bundle_producer_election_solver: BundleProducerElectionSolver<Block, CBlock, CClient>,
     domain_bundle_proposer: DomainBundleProposer<Block, Client, CBlock, CClient, TransactionPool>,
+    bundle_limits: BundleLimits,
+    proposal_timeout: Duration,
+    transaction_selection_strategy: TransactionSelectionStrategy,
     // TODO: both `skip_empty_bundle_production` and `skip_out_of_order_slot` are only used in the
     // tests, we should introduce a trait for `DomainBundleProducer` and use a wrapper of `DomainBundleProducer`
     // in the test, both `skip_empty_bundle_production` and `skip_out_of_order_slot` should move into the wrapper
@@ -48,6 +180,9 @@ where
     skip_empty_bundle_production: bool,
     skip_out_of_order_slot: bool,
     last_processed_slot: Option<Slot>,
+    heartbeat: Option<BundleProducerHeartbeatFn>,
+    metrics: Option<BundleProducerMetrics>,
+    cancellation: BundleProductionCancellation,
 }
 
 impl<Block, CBlock, Client, CClient, TransactionPool> Clone
@@ -65,9 +200,15 @@ where
             keystore: self.keystore.clone(),
             bundle_producer_election_solver: self.bundle_producer_election_solver.clone(),
             domain_bundle_proposer: self.domain_bundle_proposer.clone(),
+            bundle_limits: self.bundle_limits,
+            proposal_timeout: self.proposal_timeout,
+            transaction_selection_strategy: self.transaction_selection_strategy,
             skip_empty_bundle_production: self.skip_empty_bundle_production,
             skip_out_of_order_slot: self.skip_out_of_order_slot,
             last_processed_slot: None,
+            heartbeat: self.heartbeat.clone(),
+            metrics: self.metrics.clone(),
+            cancellation: self.cancellation.clone(),
         }
     }
 }
@@ -103,6 +244,8 @@ where
         >,
         bundle_sender: Arc<BundleSender<Block, CBlock>>,
         keystore: KeystorePtr,
+        bundle_limits: BundleLimits,
+        metrics: Option<BundleProducerMetrics>,
         skip_empty_bundle_production: bool,
         skip_out_of_order_slot: bool,
     ) -> Self {
@@ -118,17 +261,125 @@ where
             keystore,
             bundle_producer_election_solver,
             domain_bundle_proposer,
+            bundle_limits,
+            proposal_timeout: DEFAULT_PROPOSAL_TIMEOUT,
+            transaction_selection_strategy: TransactionSelectionStrategy::default(),
             skip_empty_bundle_production,
             skip_out_of_order_slot,
             last_processed_slot: None,
+            heartbeat: None,
+            metrics,
+            cancellation: BundleProductionCancellation::default(),
         }
     }
 
+    /// Set the callback to be invoked on every slot, so that an external watchdog can tell an
+    /// idle operator apart from a stalled bundle producer.
+    pub fn with_heartbeat(mut self, heartbeat: BundleProducerHeartbeatFn) -> Self {
+        self.heartbeat.replace(heartbeat);
+        self
+    }
+
+    /// Set the time limit of collecting transactions for a bundle, the bundle is produced from
+    /// the transactions collected before the timeout.
+    pub fn with_proposal_timeout(mut self, proposal_timeout: Duration) -> Self {
+        self.proposal_timeout = proposal_timeout;
+        self
+    }
+
+    /// Set the order in which the ready transactions of the pool are considered for inclusion
+    /// into a bundle.
+    pub fn with_transaction_selection_strategy(
+        mut self,
+        transaction_selection_strategy: TransactionSelectionStrategy,
+    ) -> Self {
+        self.transaction_selection_strategy = transaction_selection_strategy;
+        self
+    }
+
+    /// Returns the cancellation token of the bundle production, the token should be notified
+    /// with the new slot as soon as it arrives.
+    pub fn cancellation(&self) -> BundleProductionCancellation {
+        self.cancellation.clone()
+    }
+
     pub async fn produce_bundle(
         &mut self,
         operator_id: OperatorId,
         slot_info: OperatorSlotInfo,
-    ) -> sp_blockchain::Result<Option<OpaqueBundle<Block, CBlock>>> {
+    ) -> sp_blockchain::Result<BundleOutcome<Block, CBlock>> {
+        let slot = slot_info.slot;
+        let mut claimed = false;
+        let span = tracing::info_span!(
+            target: LOG_TARGET,
+            "bundle_producer",
+            domain_id = %self.domain_id
+        );
+        let result = self
+            .produce_bundle_inner(operator_id, slot_info, &mut claimed)
+            .instrument(span)
+            .await;
+
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat(BundleProducerHeartbeat { slot, claimed });
+        }
+
+        result
+    }
+
+    /// Turns the bundle producer into a stream of the bundle production outcomes of the slots
+    /// coming from `slots`, the outcomes are yielded in the same order as the slots.
+    ///
+    /// The bundle production of a slot is cancelled as soon as the next slot is available in
+    /// `slots`, see [`BundleProductionCancellation`].
+    pub fn produce_bundle_stream<SlotStream>(
+        self,
+        operator_id: OperatorId,
+        slots: SlotStream,
+    ) -> impl Stream<Item = sp_blockchain::Result<BundleOutcome<Block, CBlock>>>
+    where
+        SlotStream: Stream<Item = OperatorSlotInfo>,
+    {
+        let cancellation = self.cancellation();
+        stream::unfold(
+            (self, Box::pin(slots.peekable())),
+            move |(mut bundle_producer, mut slots)| {
+                let cancellation = cancellation.clone();
+                async move {
+                    let slot_info = slots.next().await?;
+                    cancellation.notify_new_slot(slot_info.slot);
+
+                    let produce_bundle = bundle_producer.produce_bundle(operator_id, slot_info);
+                    // Watch for the next slot while producing the bundle so the in-flight bundle
+                    // production can be cancelled, the next slot is left in the stream. The watch
+                    // is polled first such that a slot that is already available cancels the
+                    // bundle production right away.
+                    let watch_next_slot = async {
+                        if let Some(next_slot_info) = slots.as_mut().peek().await {
+                            cancellation.notify_new_slot(next_slot_info.slot);
+                        }
+                        future::pending::<()>().await
+                    };
+                    let result =
+                        match future::select(pin!(watch_next_slot), pin!(produce_bundle)).await {
+                            Either::Left(((), _)) => {
+                                unreachable!("Watching the next slot never finishes; qed")
+                            }
+                            Either::Right((result, _)) => result,
+                        };
+
+                    Some((result, (bundle_producer, slots)))
+                }
+            },
+        )
+    }
+
+    async fn produce_bundle_inner(
+        &mut self,
+        operator_id: OperatorId,
+        slot_info: OperatorSlotInfo,
+        claimed: &mut bool,
+    ) -> sp_blockchain::Result<BundleOutcome<Block, CBlock>> {
         let OperatorSlotInfo {
             slot,
             proof_of_time,
@@ -136,7 +387,7 @@ where
 
         let domain_best_number = self.client.info().best_number;
         let consensus_chain_best_hash = self.consensus_client.info().best_hash;
-        let should_skip_slot = {
+        let maybe_skip_reason = {
             let head_receipt_number = self
                 .consensus_client
                 .runtime_api()
@@ -155,15 +406,43 @@ where
                     .map(|last_slot| last_slot >= slot)
                     .unwrap_or(false);
 
-            is_operator_lagging || skip_out_of_order_slot
+            if is_operator_lagging {
+                Some(BundleSkipReason::OperatorLagging)
+            } else if skip_out_of_order_slot {
+                Some(BundleSkipReason::OutOfOrderSlot)
+            } else {
+                None
+            }
         };
 
-        if should_skip_slot {
+        if let Some(skip_reason) = maybe_skip_reason {
             tracing::warn!(
+                target: LOG_TARGET,
+                domain_id = %self.domain_id,
+                %slot,
+                operator_id,
                 ?domain_best_number,
+                ?skip_reason,
                 "Skipping bundle production on slot {slot}"
             );
-            return Ok(None);
+            if let Some(metrics) = &self.metrics {
+                metrics.on_slot_skipped(self.domain_id);
+            }
+            return Ok(BundleOutcome::Skipped(skip_reason));
+        }
+
+        if self.cancellation.is_cancelled(slot) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                domain_id = %self.domain_id,
+                %slot,
+                operator_id,
+                "Bundle production on slot {slot} is cancelled by a newer slot"
+            );
+            if let Some(metrics) = &self.metrics {
+                metrics.on_slot_skipped(self.domain_id);
+            }
+            return Ok(BundleOutcome::Skipped(BundleSkipReason::Cancelled));
         }
 
         if let Some((proof_of_election, operator_signing_key)) =
@@ -175,7 +454,17 @@ where
                 proof_of_time,
             )?
         {
-            tracing::info!("📦 Claimed bundle at slot {slot}");
+            tracing::info!(
+                target: LOG_TARGET,
+                domain_id = %self.domain_id,
+                %slot,
+                operator_id,
+                "📦 Claimed bundle at slot {slot}"
+            );
+            *claimed = true;
+            if let Some(metrics) = &self.metrics {
+                metrics.on_slot_claimed(self.domain_id);
+            }
 
             let tx_range = self
                 .consensus_client
@@ -188,7 +477,14 @@ where
                 })?;
             let (bundle_header, extrinsics) = self
                 .domain_bundle_proposer
-                .propose_bundle_at(proof_of_election, tx_range, operator_id)
+                .propose_bundle_at(
+                    proof_of_election,
+                    tx_range,
+                    operator_id,
+                    self.bundle_limits,
+                    self.proposal_timeout,
+                    self.transaction_selection_strategy,
+                )
                 .await?;
 
             // if there are no extrinsics and no receipts to confirm, skip the bundle
@@ -200,55 +496,376 @@ where
                     .non_empty_er_exists(consensus_chain_best_hash, self.domain_id)?
             {
                 tracing::warn!(
+                    target: LOG_TARGET,
+                    domain_id = %self.domain_id,
+                    %slot,
+                    operator_id,
                     ?domain_best_number,
                     "Skipping empty bundle production on slot {slot}"
                 );
-                return Ok(None);
+                if let Some(metrics) = &self.metrics {
+                    metrics.on_slot_skipped(self.domain_id);
+                }
+                return Ok(BundleOutcome::Skipped(BundleSkipReason::EmptyBundle));
             }
 
-            self.last_processed_slot.replace(slot);
+            // The proposal may take a while, don't produce a bundle for a slot that is already past
+            if self.cancellation.is_cancelled(slot) {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    domain_id = %self.domain_id,
+                    %slot,
+                    operator_id,
+                    "Bundle production on slot {slot} is cancelled by a newer slot"
+                );
+                self.domain_bundle_proposer.unmark_bundled(&extrinsics);
+                if let Some(metrics) = &self.metrics {
+                    metrics.on_slot_skipped(self.domain_id);
+                }
+                return Ok(BundleOutcome::Skipped(BundleSkipReason::Cancelled));
+            }
 
-            info!("🔖 Producing bundle at slot {:?}", slot_info.slot);
+            // The consensus chain may reorg during the proposal, in which case the election
+            // result and the receipt may no longer be valid, thus skip the slot.
+            if !self.is_canonical_consensus_block(consensus_chain_best_hash)? {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    domain_id = %self.domain_id,
+                    %slot,
+                    operator_id,
+                    ?consensus_chain_best_hash,
+                    "Consensus block of the election is no longer canonical, skipping bundle production on slot {slot}"
+                );
+                self.domain_bundle_proposer.unmark_bundled(&extrinsics);
+                if let Some(metrics) = &self.metrics {
+                    metrics.on_slot_skipped(self.domain_id);
+                }
+                return Ok(BundleOutcome::Skipped(
+                    BundleSkipReason::ConsensusChainReorg,
+                ));
+            }
+
+            self.last_processed_slot.replace(slot);
 
             let to_sign = bundle_header.hash();
 
-            let signature = self
-                .keystore
-                .sr25519_sign(
-                    OperatorPublicKey::ID,
-                    operator_signing_key.as_ref(),
-                    to_sign.as_ref(),
-                )
-                .map_err(|error| {
-                    sp_blockchain::Error::Application(Box::from(format!(
-                        "Error occurred when signing the bundle: {error}"
-                    )))
-                })?
-                .ok_or_else(|| {
-                    sp_blockchain::Error::Application(Box::from(
-                        "This should not happen as the existence of key was just checked",
-                    ))
-                })?;
+            info!(
+                target: LOG_TARGET,
+                domain_id = %self.domain_id,
+                %slot,
+                operator_id,
+                extrinsics_count = extrinsics.len(),
+                bundle_header_hash = ?to_sign,
+                "🔖 Producing bundle at slot {slot}"
+            );
 
-            let signature = OperatorSignature::decode(&mut signature.as_ref()).map_err(|err| {
-                sp_blockchain::Error::Application(Box::from(format!(
-                    "Failed to decode the signature of bundle: {err}"
-                )))
-            })?;
+            // The extrinsics are not going to be bundled if the bundle is not produced, allow
+            // them to be included in the next bundle.
+            let maybe_signature = match self.sign_bundle(&operator_signing_key, to_sign.as_ref()) {
+                Ok(maybe_signature) => maybe_signature,
+                Err(error) => {
+                    self.domain_bundle_proposer.unmark_bundled(&extrinsics);
+                    return Err(error);
+                }
+            };
+
+            // The election and the signing are separate keystore calls, the key may be removed
+            // from the keystore in between (e.g. key rotation), skip the slot in such case.
+            let Some(signature) = maybe_signature else {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    domain_id = %self.domain_id,
+                    %slot,
+                    operator_id,
+                    ?operator_signing_key,
+                    "Operator signing key is not available in keystore, skipping bundle production on slot {slot}"
+                );
+                self.domain_bundle_proposer.unmark_bundled(&extrinsics);
+                if let Some(metrics) = &self.metrics {
+                    metrics.on_slot_skipped(self.domain_id);
+                }
+                return Ok(BundleOutcome::Skipped(BundleSkipReason::MissingSigningKey));
+            };
+
+            // A bundle with an invalid signature is rejected by the consensus chain, check it
+            // locally in case the keystore signed with an unexpected key.
+            if !operator_signing_key.verify(&to_sign, &signature) {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    domain_id = %self.domain_id,
+                    %slot,
+                    operator_id,
+                    ?operator_signing_key,
+                    "Bundle signature doesn't match the operator signing key, skipping bundle production on slot {slot}"
+                );
+                self.domain_bundle_proposer.unmark_bundled(&extrinsics);
+                if let Some(metrics) = &self.metrics {
+                    metrics.on_slot_skipped(self.domain_id);
+                }
+                return Ok(BundleOutcome::Skipped(BundleSkipReason::InvalidSignature));
+            }
 
             let bundle = Bundle {
                 sealed_header: SealedBundleHeader::new(bundle_header, signature),
                 extrinsics,
             };
 
-            // TODO: Re-enable the bundle gossip over X-Net when the compact bundle is supported.
-            // if let Err(e) = self.bundle_sender.unbounded_send(signed_bundle.clone()) {
-            // tracing::error!(error = ?e, "Failed to send transaction bundle");
-            // }
+            if let Some(metrics) = &self.metrics {
+                metrics.on_bundle_produced(self.domain_id, bundle.extrinsics.len());
+            }
+
+            // Only gossip the compact bundle if there is anyone listening to the bundle stream,
+            // the receiver is expected to reconstruct the full bundle from its transaction pool.
+            if !self.bundle_sender.is_closed() {
+                let compact_bundle = bundle.to_compact_bundle::<HeaderHashingFor<Block::Header>>();
+                if let Err(e) = self.bundle_sender.unbounded_send(compact_bundle) {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        domain_id = %self.domain_id,
+                        %slot,
+                        operator_id,
+                        error = ?e,
+                        "Failed to send compact bundle"
+                    );
+                }
+            }
 
-            Ok(Some(bundle.into_opaque_bundle()))
+            Ok(BundleOutcome::Produced(bundle.into_opaque_bundle()))
         } else {
-            Ok(None)
+            Ok(BundleOutcome::ElectionLost)
+        }
+    }
+
+    fn is_canonical_consensus_block(
+        &self,
+        consensus_block_hash: CBlock::Hash,
+    ) -> sp_blockchain::Result<bool> {
+        let Some(consensus_block_number) = self.consensus_client.number(consensus_block_hash)?
+        else {
+            return Ok(false);
+        };
+        Ok(self.consensus_client.hash(consensus_block_number)? == Some(consensus_block_hash))
+    }
+
+    fn sign_bundle(
+        &self,
+        operator_signing_key: &OperatorPublicKey,
+        to_sign: &[u8],
+    ) -> sp_blockchain::Result<Option<OperatorSignature>> {
+        let maybe_signature = self
+            .keystore
+            .sr25519_sign(
+                OperatorPublicKey::ID,
+                operator_signing_key.as_ref(),
+                to_sign,
+            )
+            .map_err(|error| {
+                sp_blockchain::Error::Application(Box::from(format!(
+                    "Error occurred when signing the bundle: {error}"
+                )))
+            })?;
+
+        maybe_signature
+            .map(|signature| {
+                OperatorSignature::decode(&mut signature.as_ref()).map_err(|err| {
+                    sp_blockchain::Error::Application(Box::from(format!(
+                        "Failed to decode the signature of bundle: {err}"
+                    )))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Bundle producer of a single domain, allows [`MultiDomainBundleProducer`] to drive the bundle
+/// producers of domains that run different domain runtimes.
+pub trait BundleProducer<Block: BlockT, CBlock: BlockT> {
+    /// Returns the domain that the bundles are produced for.
+    fn domain_id(&self) -> DomainId;
+
+    /// Produce the bundle of the domain at the given slot, see
+    /// [`DomainBundleProducer::produce_bundle`].
+    fn produce_bundle(
+        &mut self,
+        operator_id: OperatorId,
+        slot_info: OperatorSlotInfo,
+    ) -> LocalBoxFuture<'_, sp_blockchain::Result<BundleOutcome<Block, CBlock>>>;
+}
+
+impl<Block, CBlock, Client, CClient, TransactionPool> BundleProducer<Block, CBlock>
+    for DomainBundleProducer<Block, CBlock, Client, CClient, TransactionPool>
+where
+    Block: BlockT,
+    CBlock: BlockT,
+    NumberFor<Block>: Into<NumberFor<CBlock>>,
+    NumberFor<CBlock>: Into<NumberFor<Block>>,
+    Client: HeaderBackend<Block> + BlockBackend<Block> + AuxStore + ProvideRuntimeApi<Block>,
+    Client::Api: BlockBuilder<Block>
+        + DomainCoreApi<Block>
+        + TaggedTransactionQueue<Block>
+        + MessengerApi<Block, NumberFor<CBlock>, CBlock::Hash>,
+    CClient: HeaderBackend<CBlock> + ProvideRuntimeApi<CBlock>,
+    CClient::Api: DomainsApi<CBlock, Block::Header> + BundleProducerElectionApi<CBlock, Balance>,
+    TransactionPool:
+        sc_transaction_pool_api::TransactionPool<Block = Block, Hash = <Block as BlockT>::Hash>,
+{
+    fn domain_id(&self) -> DomainId {
+        self.domain_id
+    }
+
+    fn produce_bundle(
+        &mut self,
+        operator_id: OperatorId,
+        slot_info: OperatorSlotInfo,
+    ) -> LocalBoxFuture<'_, sp_blockchain::Result<BundleOutcome<Block, CBlock>>> {
+        Box::pin(DomainBundleProducer::produce_bundle(
+            self,
+            operator_id,
+            slot_info,
+        ))
+    }
+}
+
+/// Bundle producer of multiple domains.
+///
+/// Each domain is produced by its own [`BundleProducer`] with the operator registered on that
+/// domain, the domains may run different domain runtimes while the consensus client and the
+/// keystore are shared between the producers.
+pub struct MultiDomainBundleProducer<Block, CBlock>
+where
+    Block: BlockT,
+    CBlock: BlockT,
+{
+    bundle_producers:
+        BTreeMap<DomainId, (OperatorId, Box<dyn BundleProducer<Block, CBlock> + Send>)>,
+}
+
+impl<Block, CBlock> Default for MultiDomainBundleProducer<Block, CBlock>
+where
+    Block: BlockT,
+    CBlock: BlockT,
+{
+    fn default() -> Self {
+        Self {
+            bundle_producers: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Block, CBlock> MultiDomainBundleProducer<Block, CBlock>
+where
+    Block: BlockT,
+    CBlock: BlockT,
+{
+    /// Add the bundle producer of a domain along with the operator that produces the bundles of
+    /// the domain, returns the replaced bundle producer if the domain was already added.
+    pub fn add_domain(
+        &mut self,
+        operator_id: OperatorId,
+        bundle_producer: impl BundleProducer<Block, CBlock> + Send + 'static,
+    ) -> Option<Box<dyn BundleProducer<Block, CBlock> + Send>> {
+        self.bundle_producers
+            .insert(
+                bundle_producer.domain_id(),
+                (operator_id, Box::new(bundle_producer)),
+            )
+            .map(|(_operator_id, bundle_producer)| bundle_producer)
+    }
+
+    /// Remove the bundle producer of a domain.
+    pub fn remove_domain(
+        &mut self,
+        domain_id: DomainId,
+    ) -> Option<Box<dyn BundleProducer<Block, CBlock> + Send>> {
+        self.bundle_producers
+            .remove(&domain_id)
+            .map(|(_operator_id, bundle_producer)| bundle_producer)
+    }
+
+    /// Produce the bundles of all the domains at the given slot concurrently, returns the
+    /// bundle production outcome of every domain.
+    pub async fn produce_bundles(
+        &mut self,
+        slot_info: OperatorSlotInfo,
+    ) -> BTreeMap<DomainId, sp_blockchain::Result<BundleOutcome<Block, CBlock>>> {
+        future::join_all(self.bundle_producers.iter_mut().map(
+            |(domain_id, (operator_id, bundle_producer))| {
+                let slot_info = slot_info.clone();
+                async move {
+                    (
+                        *domain_id,
+                        bundle_producer
+                            .produce_bundle(*operator_id, slot_info)
+                            .await,
+                    )
+                }
+            },
+        ))
+        .await
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Reconstructs the full bundle from the compact bundle with the extrinsics in the transaction
+/// pool.
+///
+/// Returns the hash of the first extrinsic that is not in the transaction pool as error.
+pub(crate) fn reconstruct_compact_bundle<Block, CBlock, TransactionPool>(
+    transaction_pool: &TransactionPool,
+    compact_bundle: CompactBundleFor<Block, CBlock>,
+) -> Result<
+    Bundle<Block::Extrinsic, NumberFor<CBlock>, CBlock::Hash, Block::Header, Balance>,
+    Block::Hash,
+>
+where
+    Block: BlockT,
+    CBlock: BlockT,
+    TransactionPool:
+        sc_transaction_pool_api::TransactionPool<Block = Block, Hash = <Block as BlockT>::Hash>,
+{
+    compact_bundle.into_bundle(|xt_hash| {
+        transaction_pool
+            .ready_transaction(xt_hash)
+            .map(|tx| tx.data().clone())
+    })
+}
+
+/// Reconstructs the full bundles from the compact bundles gossiped through `bundle_receiver`, the
+/// extrinsics of each bundle are looked up by hash in the transaction pool.
+///
+/// NOTE: the compact bundles are not propagated over X-Net yet, so the reconstructed bundles are
+/// only logged, which keeps the bundle stream drained and its listener alive in the meantime.
+pub async fn reconstruct_compact_bundles<Block, CBlock, TransactionPool>(
+    domain_id: DomainId,
+    transaction_pool: Arc<TransactionPool>,
+    mut bundle_receiver: BundleReceiver<Block, CBlock>,
+) where
+    Block: BlockT,
+    CBlock: BlockT,
+    TransactionPool:
+        sc_transaction_pool_api::TransactionPool<Block = Block, Hash = <Block as BlockT>::Hash>,
+{
+    while let Some(compact_bundle) = bundle_receiver.next().await {
+        let slot = compact_bundle.sealed_header.slot_number();
+        let extrinsic_count = compact_bundle.extrinsics_hashes.len();
+        match reconstruct_compact_bundle(&*transaction_pool, compact_bundle) {
+            Ok(bundle) => tracing::debug!(
+                target: LOG_TARGET,
+                %domain_id,
+                slot,
+                extrinsic_count,
+                bundle_hash = ?bundle.hash(),
+                "Reconstructed compact bundle of slot {slot}"
+            ),
+            Err(missing_xt_hash) => tracing::debug!(
+                target: LOG_TARGET,
+                %domain_id,
+                slot,
+                extrinsic_count,
+                ?missing_xt_hash,
+                "Failed to reconstruct compact bundle of slot {slot}, extrinsic is not in the transaction pool"
+            ),
         }
     }
 }
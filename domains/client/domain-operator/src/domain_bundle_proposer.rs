@@ -1,3 +1,4 @@
+use crate::domain_bundle_producer::{BundleLimits, TransactionSelectionStrategy};
 use crate::ExecutionReceiptFor;
 use codec::Encode;
 use futures::{select, FutureExt};
@@ -16,6 +17,7 @@ use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT, Numb
 use sp_runtime::Percent;
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
 use sp_weights::Weight;
+use std::cmp::Reverse;
 use std::collections::HashSet;
 use std::marker::PhantomData;
 use std::sync::Arc;
@@ -58,6 +60,10 @@ impl<Block: BlockT, CBlock: BlockT> PreviousBundledTx<Block, CBlock> {
     fn add_bundled(&mut self, tx_hash: <Block as BlockT>::Hash) {
         self.tx_hashes.insert(tx_hash);
     }
+
+    fn remove_bundled(&mut self, tx_hash: &<Block as BlockT>::Hash) {
+        self.tx_hashes.remove(tx_hash);
+    }
 }
 
 pub struct DomainBundleProposer<Block: BlockT, Client, CBlock: BlockT, CClient, TransactionPool> {
@@ -207,7 +213,12 @@ where
         proof_of_election: ProofOfElection<CBlock::Hash>,
         tx_range: U256,
         operator_id: OperatorId,
+        bundle_limits: BundleLimits,
+        proposal_timeout: time::Duration,
+        transaction_selection_strategy: TransactionSelectionStrategy,
     ) -> sp_blockchain::Result<ProposeBundleOutput<Block, CBlock>> {
+        // `None` if the timeout is too large to be represented, in which case there is no deadline
+        let maybe_deadline = time::Instant::now().checked_add(proposal_timeout);
         let parent_number = self.client.info().best_number;
         let parent_hash = self.client.info().best_hash;
 
@@ -225,6 +236,16 @@ where
                 self.transaction_pool.ready()
             }
         };
+        let pending_iterator: Box<dyn Iterator<Item = _>> = match transaction_selection_strategy {
+            TransactionSelectionStrategy::FifoReady => Box::new(pending_iterator),
+            TransactionSelectionStrategy::FeeDescending => {
+                let mut pending_txs = pending_iterator.collect::<Vec<_>>();
+                // Stable sort keeps the ready queue order of the transactions with the same
+                // priority
+                pending_txs.sort_by_key(|pending_tx| Reverse(*pending_tx.priority()));
+                Box::new(pending_txs.into_iter())
+            }
+        };
 
         // Clear the previous bundled tx info whenever the consensus chain tip is changed,
         // this allow the operator to retry for the previous bundled tx in case the previous
@@ -237,6 +258,9 @@ where
         let bundle_vrf_hash = U256::from_be_bytes(proof_of_election.vrf_hash());
 
         let domain_bundle_limit = self.fetch_domain_bundle_limit()?;
+        let max_bundle_size = domain_bundle_limit
+            .max_bundle_size
+            .min(bundle_limits.max_bundle_size_bytes);
 
         let (storage_fund_balance, transaction_byte_fee) =
             self.fetch_storage_fund_balance_and_fee(operator_id)?;
@@ -262,6 +286,21 @@ where
             // between runtime calls done in this loop.
             let runtime_api_instance = self.client.runtime_api();
             for pending_tx in pending_iterator {
+                if extrinsics.len() >= bundle_limits.max_extrinsics as usize {
+                    break;
+                }
+
+                // Stop collecting transactions once the deadline is reached, the bundle is
+                // produced from the transactions collected so far so the slot is not missed.
+                if maybe_deadline.is_some_and(|deadline| time::Instant::now() >= deadline) {
+                    tracing::warn!(
+                        ?proposal_timeout,
+                        collected_extrinsics = extrinsics.len(),
+                        "Bundle proposal timed out, proceeding with the collected transactions"
+                    );
+                    break;
+                }
+
                 let pending_tx_data = pending_tx.data();
 
                 let is_within_tx_range = runtime_api_instance
@@ -309,10 +348,11 @@ where
                     }
                 }
 
-                let next_bundle_size = bundle_size + pending_tx_data.encoded_size() as u32;
-                if next_bundle_size > domain_bundle_limit.max_bundle_size {
+                let next_bundle_size =
+                    bundle_size.saturating_add(pending_tx_data.encoded_size() as u32);
+                if next_bundle_size > max_bundle_size {
                     if skipped < MAX_SKIPPED_TRANSACTIONS
-                        && Percent::from_rational(bundle_size, domain_bundle_limit.max_bundle_size)
+                        && Percent::from_rational(bundle_size, max_bundle_size)
                             < BUNDLE_UTILIZATION_THRESHOLD
                     {
                         skipped += 1;
@@ -394,6 +434,15 @@ where
         Ok((header, extrinsics))
     }
 
+    /// Forget the given extrinsics were bundled so they can be included in the next bundle, used
+    /// when the proposed bundle ends up not being produced.
+    pub(crate) fn unmark_bundled(&mut self, extrinsics: &[Block::Extrinsic]) {
+        for extrinsic in extrinsics {
+            self.previous_bundled_tx
+                .remove_bundled(&self.transaction_pool.hash_of(extrinsic));
+        }
+    }
+
     /// Returns the receipt in the next domain bundle.
     fn load_bundle_receipt(
         &self,
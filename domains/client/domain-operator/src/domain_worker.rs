@@ -15,10 +15,11 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::bundle_processor::BundleProcessor;
-use crate::domain_bundle_producer::DomainBundleProducer;
+use crate::domain_bundle_producer::{BundleOutcome, DomainBundleProducer};
 use crate::utils::{BlockInfo, OperatorSlotInfo};
 use crate::{NewSlotNotification, OperatorStreams};
 use futures::channel::mpsc;
+use futures::future::{self, Either};
 use futures::{SinkExt, Stream, StreamExt};
 use sc_client_api::{
     AuxStore, BlockBackend, BlockImportNotification, BlockchainEvents, Finalizer, ProofProvider,
@@ -126,7 +127,8 @@ pub(super) async fn start_worker<
 
     if let Some(operator_id) = maybe_operator_id {
         info!("👷 Running as Operator[{operator_id}]...");
-        let mut new_slot_notification_stream = pin!(new_slot_notification_stream);
+        let bundle_production_cancellation = bundle_producer.cancellation();
+        let mut new_slot_notification_stream = pin!(new_slot_notification_stream.peekable());
         let mut acknowledgement_sender_stream = pin!(acknowledgement_sender_stream);
         loop {
             tokio::select! {
@@ -135,7 +137,8 @@ pub(super) async fn start_worker<
                 biased;
 
                 Some((slot, proof_of_time)) = new_slot_notification_stream.next() => {
-                    let res = bundle_producer
+                    bundle_production_cancellation.notify_new_slot(slot);
+                    let produce_bundle = bundle_producer
                         .produce_bundle(
                             operator_id,
                             OperatorSlotInfo {
@@ -143,13 +146,31 @@ pub(super) async fn start_worker<
                                 proof_of_time,
                             },
                         )
-                        .instrument(span.clone())
-                        .await;
+                        .instrument(span.clone());
+                    // Watch for the next slot while producing the bundle so the in-flight bundle
+                    // production can be cancelled, the next slot is left in the stream and handled
+                    // in the next iteration.
+                    let watch_next_slot = async {
+                        if let Some((next_slot, _)) =
+                            new_slot_notification_stream.as_mut().peek().await
+                        {
+                            bundle_production_cancellation.notify_new_slot(*next_slot);
+                        }
+                        future::pending::<()>().await
+                    };
+                    let res = match future::select(pin!(produce_bundle), pin!(watch_next_slot))
+                        .await
+                    {
+                        Either::Left((res, _)) => res,
+                        Either::Right(((), _)) => {
+                            unreachable!("Watching the next slot never finishes; qed")
+                        }
+                    };
                     match res {
                         Err(err) => {
                             tracing::error!(?slot, ?err, "Error at producing bundle.");
                         }
-                        Ok(Some(opaque_bundle)) => {
+                        Ok(BundleOutcome::Produced(opaque_bundle)) => {
                             let best_hash = consensus_client.info().best_hash;
                             let mut runtime_api = consensus_client.runtime_api();
                             runtime_api.register_extension(consensus_offchain_tx_pool_factory.offchain_transaction_pool(best_hash));
@@ -157,7 +178,10 @@ pub(super) async fn start_worker<
                                 tracing::error!(?slot, ?err, "Error at submitting bundle.");
                             }
                         }
-                        Ok(None) => {}
+                        Ok(BundleOutcome::ElectionLost) => {}
+                        Ok(BundleOutcome::Skipped(skip_reason)) => {
+                            tracing::debug!(?slot, ?skip_reason, "Bundle production skipped.");
+                        }
                     }
                 }
                 Some(maybe_block_info) = throttled_block_import_notification_stream.next() => {
@@ -64,6 +64,7 @@
 mod aux_schema;
 mod bundle_processor;
 mod bundle_producer_election_solver;
+mod bundle_producer_metrics;
 mod domain_block_processor;
 pub mod domain_bundle_producer;
 pub mod domain_bundle_proposer;
@@ -76,6 +77,12 @@ mod tests;
 mod utils;
 
 pub use self::aux_schema::load_execution_receipt;
+pub use self::bundle_producer_metrics::BundleProducerMetrics;
+pub use self::domain_bundle_producer::{
+    reconstruct_compact_bundles, BundleLimits, BundleOutcome, BundleProducerHeartbeat,
+    BundleProducerHeartbeatFn, BundleProductionCancellation, BundleSkipReason,
+    TransactionSelectionStrategy,
+};
 pub use self::fetch_domain_bootstrap_info::{fetch_domain_bootstrap_info, BootstrapResult};
 pub use self::operator::Operator;
 pub use self::utils::{DomainBlockImportNotification, DomainImportNotifications, OperatorSlotInfo};
@@ -85,12 +92,12 @@ use futures::Stream;
 use sc_client_api::{AuxStore, BlockImportNotification};
 use sc_consensus::SharedBlockImport;
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
-use sc_utils::mpsc::TracingUnboundedSender;
+use sc_utils::mpsc::{TracingUnboundedReceiver, TracingUnboundedSender};
 use sp_blockchain::HeaderBackend;
 use sp_consensus::SyncOracle;
 use sp_consensus_slots::Slot;
 use sp_domain_digests::AsPredigest;
-use sp_domains::{Bundle, DomainId, ExecutionReceipt, OperatorId};
+use sp_domains::{CompactBundle, DomainId, ExecutionReceipt, OperatorId};
 use sp_keystore::KeystorePtr;
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 use sp_runtime::DigestItem;
@@ -98,6 +105,7 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 use subspace_core_primitives::PotOutput;
 use subspace_runtime_primitives::Balance;
+use substrate_prometheus_endpoint::Registry;
 
 pub type ExecutionReceiptFor<Block, CBlock> = ExecutionReceipt<
     NumberFor<CBlock>,
@@ -107,16 +115,18 @@ pub type ExecutionReceiptFor<Block, CBlock> = ExecutionReceipt<
     Balance,
 >;
 
-type BundleSender<Block, CBlock> = TracingUnboundedSender<
-    Bundle<
-        <Block as BlockT>::Extrinsic,
-        NumberFor<CBlock>,
-        <CBlock as BlockT>::Hash,
-        <Block as BlockT>::Header,
-        Balance,
-    >,
+type CompactBundleFor<Block, CBlock> = CompactBundle<
+    <Block as BlockT>::Hash,
+    NumberFor<CBlock>,
+    <CBlock as BlockT>::Hash,
+    <Block as BlockT>::Header,
+    Balance,
 >;
 
+type BundleSender<Block, CBlock> = TracingUnboundedSender<CompactBundleFor<Block, CBlock>>;
+
+type BundleReceiver<Block, CBlock> = TracingUnboundedReceiver<CompactBundleFor<Block, CBlock>>;
+
 /// Notification streams from the consensus chain driving the executor.
 pub struct OperatorStreams<CBlock, IBNS, CIBNS, NSNS, ASS> {
     /// Pause the consensus block import when the consensus chain client
@@ -175,6 +185,8 @@ pub struct OperatorParams<
     pub operator_streams: OperatorStreams<CBlock, IBNS, CIBNS, NSNS, ASS>,
     pub domain_confirmation_depth: NumberFor<Block>,
     pub block_import: SharedBlockImport<Block>,
+    pub bundle_limits: BundleLimits,
+    pub prometheus_registry: Option<Registry>,
     pub skip_empty_bundle_production: bool,
     pub skip_out_of_order_slot: bool,
 }
@@ -1,4 +1,5 @@
 use crate::bundle_processor::BundleProcessor;
+use crate::bundle_producer_metrics::BundleProducerMetrics;
 use crate::domain_block_processor::{DomainBlockProcessor, ReceiptsChecker};
 use crate::domain_bundle_producer::DomainBundleProducer;
 use crate::domain_bundle_proposer::DomainBundleProposer;
@@ -130,6 +131,17 @@ where
             params.transaction_pool.clone(),
         );
 
+        let bundle_producer_metrics = params.prometheus_registry.as_ref().and_then(|registry| {
+            BundleProducerMetrics::new(registry)
+                .map_err(|error| {
+                    tracing::error!(
+                        domain_id = %params.domain_id,
+                        ?error,
+                        "Failed to register bundle producer metrics"
+                    );
+                })
+                .ok()
+        });
         let bundle_producer = DomainBundleProducer::new(
             params.domain_id,
             params.consensus_client.clone(),
@@ -137,6 +149,8 @@ where
             domain_bundle_proposer,
             params.bundle_sender,
             params.keystore.clone(),
+            params.bundle_limits,
+            bundle_producer_metrics,
             params.skip_empty_bundle_production,
             params.skip_out_of_order_slot,
         );
@@ -1,38 +1,50 @@
 use crate::domain_block_processor::{DomainBlockProcessor, PendingConsensusBlocks};
-use crate::domain_bundle_producer::DomainBundleProducer;
+use crate::domain_bundle_producer::{
+    reconstruct_compact_bundle, BundleLimits, BundleOutcome, BundleProducerHeartbeat,
+    BundleSkipReason, DomainBundleProducer, MultiDomainBundleProducer,
+    TransactionSelectionStrategy, DEFAULT_PROPOSAL_TIMEOUT, LOG_TARGET,
+};
 use crate::domain_bundle_proposer::DomainBundleProposer;
 use crate::fraud_proof::{FraudProofGenerator, TraceDiffType};
 use crate::tests::TxPoolError::InvalidTransaction as TxPoolInvalidTransaction;
-use crate::OperatorSlotInfo;
+use crate::{BundleProducerMetrics, BundleSender, OperatorSlotInfo};
 use codec::{Decode, Encode};
 use cross_domain_message_gossip::ChannelStorage;
+use domain_runtime_primitives::opaque::Block;
 use domain_runtime_primitives::{AccountId20Converter, AccountIdConverter, Hash};
 use domain_test_primitives::{OnchainStateApi, TimestampApi};
 use domain_test_service::evm_domain_test_runtime::{Header, UncheckedExtrinsic};
 use domain_test_service::EcdsaKeyring::{Alice, Bob, Charlie, Eve};
 use domain_test_service::Sr25519Keyring::{self, Alice as Sr25519Alice, Ferdie};
-use domain_test_service::{construct_extrinsic_generic, AUTO_ID_DOMAIN_ID, EVM_DOMAIN_ID};
+use domain_test_service::{
+    construct_extrinsic_generic, EvmDomainNode, AUTO_ID_DOMAIN_ID, EVM_DOMAIN_ID,
+};
 use futures::StreamExt;
 use pallet_messenger::ChainAllowlistUpdate;
-use sc_client_api::{Backend, BlockBackend, BlockchainEvents, HeaderBackend};
+use sc_client_api::{AuxStore, Backend, BlockBackend, BlockchainEvents, HeaderBackend};
 use sc_consensus::SharedBlockImport;
 use sc_domains::generate_mmr_proof;
 use sc_service::{BasePath, Role};
 use sc_transaction_pool::error::Error as PoolError;
 use sc_transaction_pool_api::error::Error as TxPoolError;
-use sc_transaction_pool_api::TransactionPool;
+use sc_transaction_pool_api::{
+    ImportNotificationStream, PoolFuture, PoolStatus, ReadyTransactions, TransactionFor,
+    TransactionPool, TransactionStatusStreamFor, TxHash,
+};
 use sc_utils::mpsc::tracing_unbounded;
 use sp_api::{ProvideRuntimeApi, StorageProof};
+use sp_block_builder::BlockBuilder;
 use sp_consensus::SyncOracle;
+use sp_core::crypto::KeyTypeId;
 use sp_core::storage::StateVersion;
 use sp_core::traits::{FetchRuntimeCode, SpawnEssentialNamed};
-use sp_core::{Pair, H256};
+use sp_core::{ecdsa, ed25519, sr25519, Pair, H256};
 use sp_domain_digests::AsPredigest;
 use sp_domains::core_api::DomainCoreApi;
 use sp_domains::merkle_tree::MerkleTree;
 use sp_domains::{
-    Bundle, BundleValidity, ChainId, ChannelId, DomainsApi, HeaderHashingFor, InboxedBundle,
-    InvalidBundleType, Transfers,
+    Bundle, BundleProducerElectionApi, BundleValidity, ChainId, ChannelId, DomainId, DomainsApi,
+    HeaderHashingFor, InboxedBundle, InvalidBundleType, Transfers,
 };
 use sp_domains_fraud_proof::fraud_proof::{
     ApplyExtrinsicMismatch, ExecutionPhase, FinalizeBlockMismatch, FraudProofVariant,
@@ -40,12 +52,13 @@ use sp_domains_fraud_proof::fraud_proof::{
     InvalidExtrinsicsRootProof, InvalidTransfersProof,
 };
 use sp_domains_fraud_proof::InvalidTransactionCode;
+use sp_keystore::KeystorePtr;
 use sp_messenger::messages::{CrossDomainMessage, Proof};
 use sp_messenger::MessengerApi;
 use sp_mmr_primitives::{EncodableOpaqueLeaf, LeafProof as MmrProof};
 use sp_runtime::generic::{BlockId, DigestItem};
 use sp_runtime::traits::{
-    BlakeTwo256, Block as BlockT, Convert, Hash as HashT, Header as HeaderT, Zero,
+    BlakeTwo256, Block as BlockT, Convert, Hash as HashT, Header as HeaderT, NumberFor, Zero,
 };
 use sp_runtime::transaction_validity::{
     InvalidTransaction, TransactionSource, TransactionValidityError,
@@ -55,15 +68,21 @@ use sp_state_machine::backend::AsTrieBackend;
 use sp_subspace_mmr::ConsensusChainMmrLeafProof;
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
 use sp_weights::Weight;
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use subspace_core_primitives::PotOutput;
 use subspace_runtime_primitives::opaque::Block as CBlock;
 use subspace_runtime_primitives::{Balance, SSC};
 use subspace_test_service::{
     produce_block_with, produce_blocks, produce_blocks_until, MockConsensusNode,
 };
+use substrate_prometheus_endpoint::Registry;
 use tempfile::TempDir;
+use tracing_subscriber::layer::SubscriberExt;
 
 fn number_of(consensus_node: &MockConsensusNode, block_hash: Hash) -> u32 {
     consensus_node
@@ -3214,6 +3233,8 @@ async fn stale_and_in_future_bundle_should_be_rejected() {
             domain_bundle_proposer,
             Arc::new(bundle_sender),
             alice.operator.keystore.clone(),
+            BundleLimits::default(),
+            None,
             false,
             false,
         )
@@ -3273,16 +3294,19 @@ async fn stale_and_in_future_bundle_should_be_rejected() {
         .produce_bundle(operator_id, slot_info(valid_slot, valid_pot))
         .await
         .unwrap()
+        .into_opaque_bundle()
         .unwrap();
     let bundle_with_unknow_pot = bundle_producer
         .produce_bundle(operator_id, slot_info(valid_slot, unknow_pot))
         .await
         .unwrap()
+        .into_opaque_bundle()
         .unwrap();
     let bundle_with_slot_in_future = bundle_producer
         .produce_bundle(operator_id, slot_info(slot_in_future, valid_pot))
         .await
         .unwrap()
+        .into_opaque_bundle()
         .unwrap();
     for bundle in [
         bundle_with_unknow_pot.clone(),
@@ -4312,6 +4336,59 @@ async fn test_skip_empty_bundle_production() {
     assert_eq!(alice.client.info().best_number, domain_block_number + 1);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_skip_empty_bundle_production_with_pending_receipt() {
+    let directory = TempDir::new().expect("Must be able to create temporary directory");
+
+    let mut builder = sc_cli::LoggerBuilder::new("");
+    builder.with_colors(false);
+    let _ = builder.init();
+
+    let tokio_handle = tokio::runtime::Handle::current();
+
+    // Start Ferdie
+    let mut ferdie = MockConsensusNode::run(
+        tokio_handle.clone(),
+        Ferdie,
+        BasePath::new(directory.path().join("ferdie")),
+    );
+
+    // Run Alice (a evm domain authority node) with `skip_empty_bundle_production` set to `true`
+    let mut alice = domain_test_service::DomainNodeBuilder::new(
+        tokio_handle.clone(),
+        BasePath::new(directory.path().join("alice")),
+    )
+    .skip_empty_bundle()
+    .build_evm_node(Role::Authority, Alice, &mut ferdie)
+    .await;
+
+    // Wait for `BlockTreePruningDepth + 1` blocks which is 10 + 1 in test
+    // to enure the genesis ER is confirmed
+    produce_blocks!(ferdie, alice, 11).await.unwrap();
+
+    // Send a domain extrinsic so the next domain block is non-empty
+    alice.send_system_remark().await;
+    let (slot, _) = ferdie.produce_slot_and_wait_for_bundle_submission().await;
+    produce_block_with!(ferdie.produce_block_with_slot(slot), alice)
+        .await
+        .unwrap();
+    let domain_block_number = alice.client.info().best_number;
+
+    // The transaction pool is empty now, but the receipt of the non-empty domain block is
+    // not confirmed yet, so Alice must still produce a bundle to submit it
+    let slot = ferdie.produce_slot();
+    let bundle = ferdie
+        .notify_new_slot_and_wait_for_bundle(slot)
+        .await
+        .expect("bundle must be produced when there is receipt to submit");
+    assert!(bundle.extrinsics.is_empty());
+    assert_eq!(bundle.receipt().domain_block_number, domain_block_number);
+    produce_block_with!(ferdie.produce_block_with_slot(slot), alice)
+        .await
+        .unwrap();
+    assert_eq!(alice.client.info().best_number, domain_block_number + 1);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_bad_receipt_chain() {
     let directory = TempDir::new().expect("Must be able to create temporary directory");
@@ -4360,6 +4437,8 @@ async fn test_bad_receipt_chain() {
             domain_bundle_proposer,
             Arc::new(bundle_sender),
             alice.operator.keystore.clone(),
+            BundleLimits::default(),
+            None,
             false,
             false,
         )
@@ -4422,6 +4501,7 @@ async fn test_bad_receipt_chain() {
             )
             .await
             .expect("produce bundle must success")
+            .into_opaque_bundle()
             .expect("must win the challenge");
         let (receipt_hash, bad_submit_bundle_tx) = {
             let mut opaque_bundle = bundle;
@@ -5330,3 +5410,1450 @@ async fn test_stale_fork_xdm_true_invalid_fraud_proof() {
     ferdie.produce_blocks(1).await.unwrap();
     assert!(!ferdie.does_receipt_exist(bad_receipt_hash).unwrap());
 }
+
+/// Starts Ferdie and Alice (a evm domain authority node), the returned directory holds the data
+/// of both nodes thus must outlive them.
+async fn run_ferdie_and_alice() -> (TempDir, MockConsensusNode, EvmDomainNode) {
+    let directory = TempDir::new().expect("Must be able to create temporary directory");
+
+    let mut builder = sc_cli::LoggerBuilder::new("");
+    builder.with_colors(false);
+    let _ = builder.init();
+
+    let tokio_handle = tokio::runtime::Handle::current();
+
+    // Start Ferdie
+    let mut ferdie = MockConsensusNode::run(
+        tokio_handle.clone(),
+        Ferdie,
+        BasePath::new(directory.path().join("ferdie")),
+    );
+
+    // Run Alice (a evm domain authority node)
+    let alice = domain_test_service::DomainNodeBuilder::new(
+        tokio_handle.clone(),
+        BasePath::new(directory.path().join("alice")),
+    )
+    .build_evm_node(Role::Authority, Alice, &mut ferdie)
+    .await;
+
+    (directory, ferdie, alice)
+}
+
+/// Configuration of the bundle producer created by [`create_bundle_producer`].
+struct BundleProducerTestConfig {
+    keystore: KeystorePtr,
+    /// Sender of the gossiped compact bundles, nothing is gossiped if `None`.
+    bundle_sender: Option<BundleSender<Block, CBlock>>,
+    bundle_limits: BundleLimits,
+    metrics: Option<BundleProducerMetrics>,
+    skip_empty_bundle_production: bool,
+    skip_out_of_order_slot: bool,
+}
+
+impl BundleProducerTestConfig {
+    fn new(keystore: KeystorePtr) -> Self {
+        Self {
+            keystore,
+            bundle_sender: None,
+            bundle_limits: BundleLimits::default(),
+            metrics: None,
+            skip_empty_bundle_production: false,
+            skip_out_of_order_slot: false,
+        }
+    }
+}
+
+/// Creates the bundle producer of `domain_id` on top of the given domain client and transaction
+/// pool.
+fn create_bundle_producer<Client, CClient, TransactionPool>(
+    domain_id: DomainId,
+    consensus_client: Arc<CClient>,
+    client: Arc<Client>,
+    transaction_pool: Arc<TransactionPool>,
+    config: BundleProducerTestConfig,
+) -> DomainBundleProducer<Block, CBlock, Client, CClient, TransactionPool>
+where
+    Client: HeaderBackend<Block> + BlockBackend<Block> + AuxStore + ProvideRuntimeApi<Block>,
+    Client::Api: BlockBuilder<Block>
+        + DomainCoreApi<Block>
+        + TaggedTransactionQueue<Block>
+        + MessengerApi<Block, NumberFor<CBlock>, <CBlock as BlockT>::Hash>,
+    CClient: HeaderBackend<CBlock> + ProvideRuntimeApi<CBlock>,
+    CClient::Api:
+        DomainsApi<CBlock, <Block as BlockT>::Header> + BundleProducerElectionApi<CBlock, Balance>,
+    TransactionPool:
+        sc_transaction_pool_api::TransactionPool<Block = Block, Hash = <Block as BlockT>::Hash>,
+{
+    let BundleProducerTestConfig {
+        keystore,
+        bundle_sender,
+        bundle_limits,
+        metrics,
+        skip_empty_bundle_production,
+        skip_out_of_order_slot,
+    } = config;
+    let domain_bundle_proposer = DomainBundleProposer::new(
+        domain_id,
+        client.clone(),
+        consensus_client.clone(),
+        transaction_pool,
+    );
+    // The receiver is dropped right away, thus the bundle stream has no listener
+    let bundle_sender =
+        bundle_sender.unwrap_or_else(|| tracing_unbounded("domain_bundle_stream", 100).0);
+    DomainBundleProducer::new(
+        domain_id,
+        consensus_client,
+        client,
+        domain_bundle_proposer,
+        Arc::new(bundle_sender),
+        keystore,
+        bundle_limits,
+        metrics,
+        skip_empty_bundle_production,
+        skip_out_of_order_slot,
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_gossip_compact_bundle() {
+    let (_directory, mut ferdie, mut alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let (bundle_sender, mut bundle_receiver) = tracing_unbounded("domain_bundle_stream", 100);
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig {
+            bundle_sender: Some(bundle_sender),
+            ..BundleProducerTestConfig::new(alice.operator.keystore.clone())
+        },
+    );
+
+    alice
+        .construct_and_send_extrinsic(pallet_balances::Call::transfer_allow_death {
+            dest: Bob.to_account_id(),
+            value: 1,
+        })
+        .await
+        .expect("Failed to send extrinsic");
+
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert_eq!(bundle.extrinsics.len(), 1);
+
+    // The compact bundle is gossiped and the full bundle can be reconstructed from the tx pool
+    let compact_bundle = bundle_receiver.try_recv().unwrap();
+    assert_eq!(compact_bundle.sealed_header, bundle.sealed_header);
+    assert_eq!(compact_bundle.extrinsics_hashes.len(), 1);
+    let reconstructed_bundle =
+        reconstruct_compact_bundle(&*alice.operator.transaction_pool, compact_bundle).unwrap();
+    assert_eq!(reconstructed_bundle.into_opaque_bundle(), bundle);
+
+    // Nothing is gossiped when there is no listener of the bundle stream
+    drop(bundle_receiver);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    assert!(bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .is_some());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_respects_bundle_limits() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let bundle_producer = |bundle_limits| {
+        create_bundle_producer(
+            EVM_DOMAIN_ID,
+            ferdie.client.clone(),
+            alice.client.clone(),
+            alice.operator.transaction_pool.clone(),
+            BundleProducerTestConfig {
+                bundle_limits,
+                ..BundleProducerTestConfig::new(alice.operator.keystore.clone())
+            },
+        )
+    };
+
+    // Fill the tx pool with large transactions
+    let nonce = alice.account_nonce();
+    for i in 0..6 {
+        alice
+            .construct_and_send_extrinsic_with(
+                nonce + i,
+                0u32.into(),
+                frame_system::Call::remark {
+                    remark: vec![i as u8; 1024],
+                },
+            )
+            .await
+            .expect("Failed to send extrinsic");
+    }
+
+    // Bundle respects the extrinsics count limit
+    let mut count_limited_bundle_producer = bundle_producer(BundleLimits {
+        max_extrinsics: 2,
+        ..Default::default()
+    });
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = count_limited_bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert_eq!(bundle.extrinsics.len(), 2);
+
+    // The transactions that didn't fit are left for the next slot
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let next_bundle = count_limited_bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert_eq!(next_bundle.extrinsics.len(), 2);
+    assert!(next_bundle
+        .extrinsics
+        .iter()
+        .all(|xt| !bundle.extrinsics.contains(xt)));
+
+    // Bundle respects the bundle size limit
+    let xt_size = bundle.extrinsics[0].encoded_size() as u32;
+    let max_bundle_size_bytes = 3 * xt_size;
+    let mut size_limited_bundle_producer = bundle_producer(BundleLimits {
+        max_bundle_size_bytes,
+        ..Default::default()
+    });
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = size_limited_bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert_eq!(bundle.extrinsics.len(), 3);
+    assert!(
+        bundle
+            .extrinsics
+            .iter()
+            .map(|xt| xt.encoded_size() as u32)
+            .sum::<u32>()
+            <= max_bundle_size_bytes
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_fee_descending_selection() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig {
+            bundle_limits: BundleLimits {
+                max_extrinsics: 2,
+                ..Default::default()
+            },
+            ..BundleProducerTestConfig::new(alice.operator.keystore.clone())
+        },
+    )
+    .with_transaction_selection_strategy(TransactionSelectionStrategy::FeeDescending);
+
+    // Transactions from different senders with different tips
+    let mut txs = BTreeMap::new();
+    for (caller, tip) in [(Alice, 1_000u128), (Bob, 3_000), (Charlie, 2_000)] {
+        let tx = construct_extrinsic_generic::<evm_domain_test_runtime::Runtime, _>(
+            &alice.client,
+            pallet_balances::Call::transfer_allow_death {
+                dest: Eve.to_account_id(),
+                value: 1,
+            },
+            caller,
+            false,
+            0,
+            tip,
+        );
+        alice
+            .send_extrinsic(tx.clone())
+            .await
+            .expect("Failed to send extrinsic");
+        txs.insert(tip, OpaqueExtrinsic::from(tx));
+    }
+
+    // Only the transactions with the highest tips fit into the bundle
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert_eq!(
+        bundle.extrinsics,
+        vec![txs[&3_000].clone(), txs[&2_000].clone()]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_heartbeat() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let heartbeats = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let mut bundle_producer = {
+        let heartbeats = heartbeats.clone();
+        create_bundle_producer(
+            EVM_DOMAIN_ID,
+            ferdie.client.clone(),
+            alice.client.clone(),
+            alice.operator.transaction_pool.clone(),
+            BundleProducerTestConfig::new(alice.operator.keystore.clone()),
+        )
+        .with_heartbeat(Arc::new(move |heartbeat| heartbeats.lock().push(heartbeat)))
+    };
+
+    // Alice is the only operator so she wins the election of every slot, while operator 1
+    // is not registered thus never wins the election
+    let mut expected_heartbeats = Vec::new();
+    for operator_id in [0, 1, 0, 1] {
+        let (slot, proof_of_time) = ferdie.produce_slot();
+        let maybe_bundle = bundle_producer
+            .produce_bundle(
+                operator_id,
+                OperatorSlotInfo {
+                    slot,
+                    proof_of_time,
+                },
+            )
+            .await
+            .unwrap()
+            .into_opaque_bundle();
+        assert_eq!(maybe_bundle.is_some(), operator_id == 0);
+        expected_heartbeats.push(BundleProducerHeartbeat {
+            slot,
+            claimed: operator_id == 0,
+        });
+    }
+
+    assert_eq!(*heartbeats.lock(), expected_heartbeats);
+}
+
+/// Notifies that the VRF signature is requested and blocks it until resumed.
+type VrfSignPause = (
+    futures::channel::oneshot::Sender<()>,
+    std::sync::mpsc::Receiver<()>,
+);
+
+/// Keystore to emulate events happening between the bundle election and signing:
+/// - it can be switched to lose the sr25519 keys on signing, while still being able to produce
+///   the VRF signature, to emulate the key being removed between election and signing.
+/// - it can pause the VRF signing, to emulate the chain changing right after the election.
+struct TestKeystore {
+    inner: KeystorePtr,
+    lose_keys: AtomicBool,
+    sign_with_wrong_key: AtomicBool,
+    vrf_sign_pause: parking_lot::Mutex<Option<VrfSignPause>>,
+}
+
+impl TestKeystore {
+    fn new(inner: KeystorePtr) -> Self {
+        Self {
+            inner,
+            lose_keys: AtomicBool::new(false),
+            sign_with_wrong_key: AtomicBool::new(false),
+            vrf_sign_pause: parking_lot::Mutex::new(None),
+        }
+    }
+}
+
+impl sp_keystore::Keystore for TestKeystore {
+    fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+        self.inner.sr25519_public_keys(key_type)
+    }
+
+    fn sr25519_generate_new(
+        &self,
+        key_type: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<sr25519::Public, sp_keystore::Error> {
+        self.inner.sr25519_generate_new(key_type, seed)
+    }
+
+    fn sr25519_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &sr25519::Public,
+        msg: &[u8],
+    ) -> Result<Option<sr25519::Signature>, sp_keystore::Error> {
+        if self.lose_keys.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        if self.sign_with_wrong_key.load(Ordering::SeqCst) {
+            return Ok(Some(Sr25519Keyring::Bob.pair().sign(msg)));
+        }
+        self.inner.sr25519_sign(key_type, public, msg)
+    }
+
+    fn sr25519_vrf_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &sr25519::Public,
+        data: &sr25519::vrf::VrfSignData,
+    ) -> Result<Option<sr25519::vrf::VrfSignature>, sp_keystore::Error> {
+        let maybe_pause = self.vrf_sign_pause.lock().take();
+        if let Some((vrf_sign_requested, resume)) = maybe_pause {
+            let _ = vrf_sign_requested.send(());
+            let _ = resume.recv();
+        }
+        self.inner.sr25519_vrf_sign(key_type, public, data)
+    }
+
+    fn sr25519_vrf_pre_output(
+        &self,
+        key_type: KeyTypeId,
+        public: &sr25519::Public,
+        input: &sr25519::vrf::VrfInput,
+    ) -> Result<Option<sr25519::vrf::VrfPreOutput>, sp_keystore::Error> {
+        self.inner.sr25519_vrf_pre_output(key_type, public, input)
+    }
+
+    fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+        self.inner.ed25519_public_keys(key_type)
+    }
+
+    fn ed25519_generate_new(
+        &self,
+        key_type: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ed25519::Public, sp_keystore::Error> {
+        self.inner.ed25519_generate_new(key_type, seed)
+    }
+
+    fn ed25519_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &ed25519::Public,
+        msg: &[u8],
+    ) -> Result<Option<ed25519::Signature>, sp_keystore::Error> {
+        self.inner.ed25519_sign(key_type, public, msg)
+    }
+
+    fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+        self.inner.ecdsa_public_keys(key_type)
+    }
+
+    fn ecdsa_generate_new(
+        &self,
+        key_type: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ecdsa::Public, sp_keystore::Error> {
+        self.inner.ecdsa_generate_new(key_type, seed)
+    }
+
+    fn ecdsa_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &ecdsa::Public,
+        msg: &[u8],
+    ) -> Result<Option<ecdsa::Signature>, sp_keystore::Error> {
+        self.inner.ecdsa_sign(key_type, public, msg)
+    }
+
+    fn ecdsa_sign_prehashed(
+        &self,
+        key_type: KeyTypeId,
+        public: &ecdsa::Public,
+        msg: &[u8; 32],
+    ) -> Result<Option<ecdsa::Signature>, sp_keystore::Error> {
+        self.inner.ecdsa_sign_prehashed(key_type, public, msg)
+    }
+
+    fn insert(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+        self.inner.insert(key_type, suri, public)
+    }
+
+    fn keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>, sp_keystore::Error> {
+        self.inner.keys(key_type)
+    }
+
+    fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+        self.inner.has_keys(public_keys)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_skips_slot_on_missing_signing_key() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let keystore = Arc::new(TestKeystore::new(alice.operator.keystore.clone()));
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig::new(keystore.clone()),
+    );
+
+    // The key is lost after the election is won, the slot is skipped without error
+    keystore.lose_keys.store(true, Ordering::SeqCst);
+    let captured_logs = CapturedLogs::default();
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = {
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(captured_logs.clone()),
+        );
+        bundle_producer
+            .produce_bundle(
+                0,
+                OperatorSlotInfo {
+                    slot,
+                    proof_of_time,
+                },
+            )
+            .await
+            .expect("Missing signing key must not be a hard error")
+    };
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::MissingSigningKey)
+    ));
+
+    // The skip is logged with the same fields as the other bundle producer logs
+    let missing_key_log_fields = captured_logs
+        .0
+        .lock()
+        .iter()
+        .find(|(target, fields)| {
+            target == LOG_TARGET
+                && fields.get("message").is_some_and(|message| {
+                    message.contains("Operator signing key is not available")
+                })
+        })
+        .map(|(_, fields)| fields.clone())
+        .expect("Missing signing key log must be captured");
+    assert_eq!(
+        missing_key_log_fields["domain_id"],
+        EVM_DOMAIN_ID.to_string()
+    );
+    assert_eq!(missing_key_log_fields["slot"], slot.to_string());
+    assert_eq!(missing_key_log_fields["operator_id"], "0");
+
+    // Bundle is produced again once the key is back
+    keystore.lose_keys.store(false, Ordering::SeqCst);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(matches!(bundle_outcome, BundleOutcome::Produced(_)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_skips_slot_on_invalid_signature() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let keystore = Arc::new(TestKeystore::new(alice.operator.keystore.clone()));
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig::new(keystore.clone()),
+    );
+
+    // The keystore signs the bundle with a key other than the operator signing key, the slot is
+    // skipped without error
+    keystore.sign_with_wrong_key.store(true, Ordering::SeqCst);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .expect("Invalid signature must not be a hard error");
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::InvalidSignature)
+    ));
+
+    // Bundle is produced again once the keystore signs with the right key
+    keystore.sign_with_wrong_key.store(false, Ordering::SeqCst);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(matches!(bundle_outcome, BundleOutcome::Produced(_)));
+}
+
+/// Target and fields of the captured log events.
+type CapturedLogEvent = (String, BTreeMap<String, String>);
+
+/// `tracing` layer that captures all log events.
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<parking_lot::Mutex<Vec<CapturedLogEvent>>>);
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for CapturedLogs {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        struct FieldVisitor(BTreeMap<String, String>);
+
+        impl tracing::field::Visit for FieldVisitor {
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .insert(field.name().to_string(), format!("{value:?}"));
+            }
+        }
+
+        let mut visitor = FieldVisitor(BTreeMap::new());
+        event.record(&mut visitor);
+        self.0
+            .lock()
+            .push((event.metadata().target().to_string(), visitor.0));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_log_fields() {
+    let (_directory, mut ferdie, mut alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig::new(alice.operator.keystore.clone()),
+    );
+
+    alice.send_system_remark().await;
+    let captured_logs = CapturedLogs::default();
+    let bundle = {
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(captured_logs.clone()),
+        );
+        let (slot, proof_of_time) = ferdie.produce_slot();
+        bundle_producer
+            .produce_bundle(
+                0,
+                OperatorSlotInfo {
+                    slot,
+                    proof_of_time,
+                },
+            )
+            .await
+            .unwrap()
+            .into_opaque_bundle()
+            .unwrap()
+    };
+    let slot = bundle.sealed_header.slot_number().to_string();
+
+    let captured_logs = captured_logs.0.lock();
+    let log_fields = |message: &str| {
+        captured_logs
+            .iter()
+            .find(|(target, fields)| {
+                target == LOG_TARGET
+                    && fields
+                        .get("message")
+                        .is_some_and(|logged_message| logged_message.contains(message))
+            })
+            .map(|(_, fields)| fields.clone())
+            .unwrap_or_else(|| panic!("Log \"{message}\" must be captured"))
+    };
+
+    let claimed_log_fields = log_fields("Claimed bundle");
+    assert_eq!(claimed_log_fields["domain_id"], EVM_DOMAIN_ID.to_string());
+    assert_eq!(claimed_log_fields["slot"], slot);
+    assert_eq!(claimed_log_fields["operator_id"], "0");
+
+    let producing_log_fields = log_fields("Producing bundle");
+    assert_eq!(producing_log_fields["domain_id"], EVM_DOMAIN_ID.to_string());
+    assert_eq!(producing_log_fields["slot"], slot);
+    assert_eq!(producing_log_fields["operator_id"], "0");
+    assert_eq!(producing_log_fields["extrinsics_count"], "1");
+    assert_eq!(
+        producing_log_fields["bundle_header_hash"],
+        format!("{:?}", bundle.sealed_header.pre_hash())
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_outcome() {
+    let (_directory, mut ferdie, mut alice) = run_ferdie_and_alice().await;
+
+    // Wait for `BlockTreePruningDepth + 1` blocks which is 10 + 1 in test
+    // to enure the genesis ER is confirmed
+    produce_blocks!(ferdie, alice, 11).await.unwrap();
+
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig {
+            skip_empty_bundle_production: true,
+            ..BundleProducerTestConfig::new(alice.operator.keystore.clone())
+        },
+    );
+    // Operator 1 is not registered thus can't win the election
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            1,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(matches!(bundle_outcome, BundleOutcome::ElectionLost));
+
+    // Neither extrinsic nor receipt to submit, the empty bundle is skipped
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::EmptyBundle)
+    ));
+
+    alice.send_system_remark().await;
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    let BundleOutcome::Produced(bundle) = bundle_outcome else {
+        panic!("Bundle must be produced");
+    };
+    assert_eq!(bundle.extrinsics.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_production_cancellation() {
+    let (_directory, mut ferdie, mut alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig::new(alice.operator.keystore.clone()),
+    );
+    let cancellation = bundle_producer.cancellation();
+
+    alice.send_system_remark().await;
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let (next_slot, next_proof_of_time) = ferdie.produce_slot();
+
+    // A newer slot arrived before the bundle of the previous slot is produced
+    cancellation.notify_new_slot(next_slot);
+    assert!(cancellation.is_cancelled(slot));
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::Cancelled)
+    ));
+
+    // The bundle is produced for the newer slot, including the tx of the cancelled one
+    assert!(!cancellation.is_cancelled(next_slot));
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot: next_slot,
+                proof_of_time: next_proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    let BundleOutcome::Produced(bundle) = bundle_outcome else {
+        panic!("Bundle must be produced");
+    };
+    assert_eq!(bundle.extrinsics.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_produce_bundle_stream() {
+    let (_directory, mut ferdie, mut alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig::new(alice.operator.keystore.clone()),
+    );
+    let (slot_sender, slot_receiver) = tracing_unbounded("domain_slot_stream", 100);
+    let mut bundle_outcome_stream = pin!(bundle_producer.produce_bundle_stream(0, slot_receiver));
+    let send_slot = |(slot, proof_of_time)| {
+        slot_sender
+            .unbounded_send(OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            })
+            .unwrap();
+    };
+
+    alice.send_system_remark().await;
+    send_slot(ferdie.produce_slot());
+    let bundle_outcome = bundle_outcome_stream.next().await.unwrap().unwrap();
+    let BundleOutcome::Produced(bundle) = bundle_outcome else {
+        panic!("Bundle must be produced");
+    };
+    assert_eq!(bundle.extrinsics.len(), 1);
+
+    // The bundle production of the first slot is cancelled by the second one
+    alice.send_system_remark().await;
+    send_slot(ferdie.produce_slot());
+    send_slot(ferdie.produce_slot());
+    let bundle_outcome = bundle_outcome_stream.next().await.unwrap().unwrap();
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::Cancelled)
+    ));
+    let bundle_outcome = bundle_outcome_stream.next().await.unwrap().unwrap();
+    let BundleOutcome::Produced(bundle) = bundle_outcome else {
+        panic!("Bundle must be produced");
+    };
+    assert_eq!(bundle.extrinsics.len(), 1);
+
+    // The stream ends with the slots
+    drop(slot_sender);
+    assert!(bundle_outcome_stream.next().await.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_multi_domain_bundle_producer() {
+    let (directory, mut ferdie, mut alice) = run_ferdie_and_alice().await;
+
+    // Run Bob (a auto-id domain authority node)
+    let mut bob = domain_test_service::DomainNodeBuilder::new(
+        tokio::runtime::Handle::current(),
+        BasePath::new(directory.path().join("bob")),
+    )
+    .build_auto_id_node(Role::Authority, Sr25519Keyring::Bob, &mut ferdie)
+    .await;
+
+    produce_blocks!(ferdie, alice, 3, bob).await.unwrap();
+
+    // Alice is the operator 0 of the evm domain and Bob is the operator 1 of the auto-id domain,
+    // both domains are driven by the same slots
+    let mut multi_domain_bundle_producer = MultiDomainBundleProducer::default();
+    assert!(multi_domain_bundle_producer
+        .add_domain(
+            0,
+            create_bundle_producer(
+                EVM_DOMAIN_ID,
+                ferdie.client.clone(),
+                alice.client.clone(),
+                alice.operator.transaction_pool.clone(),
+                BundleProducerTestConfig::new(alice.operator.keystore.clone()),
+            ),
+        )
+        .is_none());
+    assert!(multi_domain_bundle_producer
+        .add_domain(
+            1,
+            create_bundle_producer(
+                AUTO_ID_DOMAIN_ID,
+                ferdie.client.clone(),
+                bob.client.clone(),
+                bob.operator.transaction_pool.clone(),
+                BundleProducerTestConfig::new(bob.operator.keystore.clone()),
+            ),
+        )
+        .is_none());
+
+    // Each domain produces a bundle of its own transactions
+    alice.send_system_remark().await;
+    bob.send_system_remark().await;
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let mut bundle_outcomes = multi_domain_bundle_producer
+        .produce_bundles(OperatorSlotInfo {
+            slot,
+            proof_of_time,
+        })
+        .await;
+    assert_eq!(bundle_outcomes.len(), 2);
+
+    let BundleOutcome::Produced(evm_bundle) =
+        bundle_outcomes.remove(&EVM_DOMAIN_ID).unwrap().unwrap()
+    else {
+        panic!("Bundle must be produced for the evm domain");
+    };
+    assert_eq!(evm_bundle.domain_id(), EVM_DOMAIN_ID);
+    assert_eq!(evm_bundle.operator_id(), 0);
+    assert_eq!(evm_bundle.extrinsics.len(), 1);
+
+    let BundleOutcome::Produced(auto_id_bundle) =
+        bundle_outcomes.remove(&AUTO_ID_DOMAIN_ID).unwrap().unwrap()
+    else {
+        panic!("Bundle must be produced for the auto-id domain");
+    };
+    assert_eq!(auto_id_bundle.domain_id(), AUTO_ID_DOMAIN_ID);
+    assert_eq!(auto_id_bundle.operator_id(), 1);
+    assert_eq!(auto_id_bundle.extrinsics.len(), 1);
+
+    // No bundle is produced for the removed domain
+    assert!(multi_domain_bundle_producer
+        .remove_domain(EVM_DOMAIN_ID)
+        .is_some());
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcomes = multi_domain_bundle_producer
+        .produce_bundles(OperatorSlotInfo {
+            slot,
+            proof_of_time,
+        })
+        .await;
+    assert_eq!(
+        bundle_outcomes.into_keys().collect::<Vec<_>>(),
+        vec![AUTO_ID_DOMAIN_ID]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_skips_slot_on_consensus_chain_reorg() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let keystore = Arc::new(TestKeystore::new(alice.operator.keystore.clone()));
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig::new(keystore.clone()),
+    );
+
+    let (vrf_sign_requested_sender, vrf_sign_requested_receiver) =
+        futures::channel::oneshot::channel();
+    let (resume_sender, resume_receiver) = std::sync::mpsc::channel();
+    *keystore.vrf_sign_pause.lock() = Some((vrf_sign_requested_sender, resume_receiver));
+
+    let elected_consensus_hash = ferdie.client.info().best_hash;
+    let fork_parent_hash = *ferdie
+        .client
+        .header(elected_consensus_hash)
+        .unwrap()
+        .unwrap()
+        .parent_hash();
+
+    // Produce the bundle in a blocking task as the election is paused by the keystore
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_production = tokio::task::spawn_blocking(move || {
+        futures::executor::block_on(bundle_producer.produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        ))
+    });
+    vrf_sign_requested_receiver.await.unwrap();
+
+    // Reorg the consensus chain to a heavier fork right after the election
+    let mut fork_hash = fork_parent_hash;
+    for _ in 0..2 {
+        let slot = ferdie.produce_slot();
+        fork_hash = ferdie
+            .produce_block_with_slot_at(slot, fork_hash, Some(vec![]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(ferdie.client.info().best_hash, fork_hash);
+    let elected_consensus_number = ferdie
+        .client
+        .number(elected_consensus_hash)
+        .unwrap()
+        .unwrap();
+    assert_ne!(
+        ferdie.client.hash(elected_consensus_number).unwrap(),
+        Some(elected_consensus_hash)
+    );
+    resume_sender.send(()).unwrap();
+
+    let bundle_outcome = bundle_production.await.unwrap().unwrap();
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::ConsensusChainReorg)
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_metrics() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let registry = Registry::new();
+    let metrics = BundleProducerMetrics::new(&registry).unwrap();
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig {
+            metrics: Some(metrics),
+            skip_out_of_order_slot: true,
+            ..BundleProducerTestConfig::new(alice.operator.keystore.clone())
+        },
+    );
+    // metrics of the domain, there are none until the first time the metric is recorded
+    let metric = |name: &str| {
+        registry
+            .gather()
+            .into_iter()
+            .find(|metric_family| metric_family.get_name() == name)?
+            .get_metric()
+            .iter()
+            .find(|metric| {
+                metric.get_label().iter().any(|label| {
+                    label.get_name() == "domain_id"
+                        && label.get_value() == EVM_DOMAIN_ID.to_string()
+                })
+            })
+            .cloned()
+    };
+    let metric_value = |name: &str| {
+        metric(name)
+            .map(|metric| metric.get_counter().get_value() as u64)
+            .unwrap_or_default()
+    };
+
+    // Alice wins the election of every slot while operator 1 is not registered
+    let mut last_slot_info = None;
+    for operator_id in [0, 1, 0] {
+        let (slot, proof_of_time) = ferdie.produce_slot();
+        let slot_info = OperatorSlotInfo {
+            slot,
+            proof_of_time,
+        };
+        bundle_producer
+            .produce_bundle(operator_id, slot_info.clone())
+            .await
+            .unwrap();
+        last_slot_info.replace(slot_info);
+    }
+    assert_eq!(metric_value("domain_bundle_producer_slots_claimed"), 2);
+    assert_eq!(metric_value("domain_bundle_producer_bundles_produced"), 2);
+    assert_eq!(
+        metric("domain_bundle_producer_bundle_extrinsic_count")
+            .unwrap()
+            .get_histogram()
+            .get_sample_count(),
+        2
+    );
+    assert_eq!(metric_value("domain_bundle_producer_slots_skipped"), 0);
+
+    // Out of order slot is skipped
+    let bundle_outcome = bundle_producer
+        .produce_bundle(0, last_slot_info.unwrap())
+        .await
+        .unwrap();
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::OutOfOrderSlot)
+    ));
+    assert_eq!(metric_value("domain_bundle_producer_slots_claimed"), 2);
+    assert_eq!(metric_value("domain_bundle_producer_bundles_produced"), 2);
+    assert_eq!(metric_value("domain_bundle_producer_slots_skipped"), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_proposal_timeout() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let bundle_producer = |proposal_timeout| {
+        create_bundle_producer(
+            EVM_DOMAIN_ID,
+            ferdie.client.clone(),
+            alice.client.clone(),
+            alice.operator.transaction_pool.clone(),
+            BundleProducerTestConfig::new(alice.operator.keystore.clone()),
+        )
+        .with_proposal_timeout(proposal_timeout)
+    };
+
+    let nonce = alice.account_nonce();
+    for i in 0..3 {
+        alice
+            .construct_and_send_extrinsic_with(
+                nonce + i,
+                0u32.into(),
+                frame_system::Call::remark { remark: vec![] },
+            )
+            .await
+            .expect("Failed to send extrinsic");
+    }
+
+    // The deadline is already reached before any transaction is collected, the bundle is still
+    // produced in time but without any extrinsic
+    let mut timed_out_bundle_producer = bundle_producer(Duration::ZERO);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = timed_out_bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert!(bundle.extrinsics.is_empty());
+
+    // Transactions are left in the pool and collected once there is enough time
+    let mut bundle_producer = bundle_producer(DEFAULT_PROPOSAL_TIMEOUT);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert_eq!(bundle.extrinsics.len(), 3);
+}
+
+/// Iterator of the ready transactions of a transaction pool.
+type ReadyIteratorFor<Pool> =
+    Box<dyn ReadyTransactions<Item = Arc<<Pool as TransactionPool>::InPoolTransaction>> + Send>;
+
+/// Transaction pool that takes `delay` to hash a transaction, the bundle proposer hashes every
+/// transaction it collects thus the proposal is artificially slow.
+struct SlowTransactionPool<Pool> {
+    inner: Arc<Pool>,
+    delay: Duration,
+}
+
+impl<Pool: TransactionPool> TransactionPool for SlowTransactionPool<Pool> {
+    type Block = Pool::Block;
+    type Hash = Pool::Hash;
+    type InPoolTransaction = Pool::InPoolTransaction;
+    type Error = Pool::Error;
+
+    fn submit_at(
+        &self,
+        at: <Self::Block as BlockT>::Hash,
+        source: TransactionSource,
+        xts: Vec<TransactionFor<Self>>,
+    ) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+        self.inner.submit_at(at, source, xts)
+    }
+
+    fn submit_one(
+        &self,
+        at: <Self::Block as BlockT>::Hash,
+        source: TransactionSource,
+        xt: TransactionFor<Self>,
+    ) -> PoolFuture<TxHash<Self>, Self::Error> {
+        self.inner.submit_one(at, source, xt)
+    }
+
+    fn submit_and_watch(
+        &self,
+        at: <Self::Block as BlockT>::Hash,
+        source: TransactionSource,
+        xt: TransactionFor<Self>,
+    ) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+        self.inner.submit_and_watch(at, source, xt)
+    }
+
+    fn ready_at(
+        &self,
+        at: NumberFor<Self::Block>,
+    ) -> Pin<Box<dyn Future<Output = ReadyIteratorFor<Self>> + Send>> {
+        self.inner.ready_at(at)
+    }
+
+    fn ready(&self) -> ReadyIteratorFor<Self> {
+        self.inner.ready()
+    }
+
+    fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+        self.inner.remove_invalid(hashes)
+    }
+
+    fn futures(&self) -> Vec<Self::InPoolTransaction> {
+        self.inner.futures()
+    }
+
+    fn status(&self) -> PoolStatus {
+        self.inner.status()
+    }
+
+    fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+        self.inner.import_notification_stream()
+    }
+
+    fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+        self.inner.on_broadcasted(propagations)
+    }
+
+    fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+        std::thread::sleep(self.delay);
+        self.inner.hash_of(xt)
+    }
+
+    fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+        self.inner.ready_transaction(hash)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundle_producer_proposal_timeout_with_slow_proposer() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let tx_count = 10;
+    let nonce = alice.account_nonce();
+    for i in 0..tx_count {
+        alice
+            .construct_and_send_extrinsic_with(
+                nonce + i,
+                0u32.into(),
+                frame_system::Call::remark { remark: vec![] },
+            )
+            .await
+            .expect("Failed to send extrinsic");
+    }
+
+    // Collecting a transaction takes at least `hash_delay`, thus collecting all the transactions
+    // takes way longer than the proposal timeout
+    let hash_delay = Duration::from_millis(250);
+    let proposal_timeout = Duration::from_secs(1);
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        Arc::new(SlowTransactionPool {
+            inner: alice.operator.transaction_pool.clone(),
+            delay: hash_delay,
+        }),
+        BundleProducerTestConfig::new(alice.operator.keystore.clone()),
+    )
+    .with_proposal_timeout(proposal_timeout);
+
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+
+    // The proposal stops once the deadline is reached and the bundle is produced from the
+    // transactions collected so far. The deadline is checked before each transaction and every
+    // collected transaction delays the next check by at least `hash_delay`, so no more than
+    // `proposal_timeout / hash_delay` transactions fit in however slow the machine is.
+    let max_collected = (proposal_timeout.as_millis() / hash_delay.as_millis()) as usize;
+    assert!(!bundle.extrinsics.is_empty());
+    assert!(
+        bundle.extrinsics.len() <= max_collected,
+        "Proposal must stop at the deadline, collected {} extrinsics",
+        bundle.extrinsics.len()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reinclude_tx_of_unproduced_bundle() {
+    let (_directory, mut ferdie, alice) = run_ferdie_and_alice().await;
+
+    produce_blocks!(ferdie, alice, 3).await.unwrap();
+
+    let keystore = Arc::new(TestKeystore::new(alice.operator.keystore.clone()));
+    let mut bundle_producer = create_bundle_producer(
+        EVM_DOMAIN_ID,
+        ferdie.client.clone(),
+        alice.client.clone(),
+        alice.operator.transaction_pool.clone(),
+        BundleProducerTestConfig::new(keystore.clone()),
+    );
+
+    alice
+        .construct_and_send_extrinsic_with(
+            alice.account_nonce(),
+            0u32.into(),
+            frame_system::Call::remark { remark: vec![] },
+        )
+        .await
+        .expect("Failed to send extrinsic");
+
+    // The tx is proposed but the bundle is not produced since the signing key is lost
+    keystore.lose_keys.store(true, Ordering::SeqCst);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle_outcome = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        bundle_outcome,
+        BundleOutcome::Skipped(BundleSkipReason::MissingSigningKey)
+    ));
+
+    // The tx is included in the next bundle even though the consensus chain tip is not changed
+    keystore.lose_keys.store(false, Ordering::SeqCst);
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert_eq!(bundle.extrinsics.len(), 1);
+
+    // The tx is not included again in the following bundle
+    let (slot, proof_of_time) = ferdie.produce_slot();
+    let bundle = bundle_producer
+        .produce_bundle(
+            0,
+            OperatorSlotInfo {
+                slot,
+                proof_of_time,
+            },
+        )
+        .await
+        .unwrap()
+        .into_opaque_bundle()
+        .unwrap();
+    assert!(bundle.extrinsics.is_empty());
+}
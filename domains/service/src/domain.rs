@@ -4,7 +4,7 @@ use crate::{FullBackend, FullClient};
 use cross_domain_message_gossip::ChainMsg;
 use domain_client_block_preprocessor::inherents::CreateInherentDataProvider;
 use domain_client_message_relayer::GossipMessageSink;
-use domain_client_operator::{Operator, OperatorParams, OperatorStreams};
+use domain_client_operator::{BundleLimits, Operator, OperatorParams, OperatorStreams};
 use domain_runtime_primitives::opaque::{Block, Header};
 use domain_runtime_primitives::{Balance, Hash};
 use futures::channel::mpsc;
@@ -238,6 +238,7 @@ where
     pub gossip_message_sink: GossipMessageSink,
     pub domain_message_receiver: TracingUnboundedReceiver<ChainMsg>,
     pub provider: Provider,
+    pub bundle_limits: BundleLimits,
     pub skip_empty_bundle_production: bool,
     pub consensus_state_pruning: PruningMode,
     pub skip_out_of_order_slot: bool,
@@ -327,6 +328,7 @@ where
         gossip_message_sink,
         domain_message_receiver,
         provider,
+        bundle_limits,
         skip_empty_bundle_production,
         consensus_state_pruning,
         skip_out_of_order_slot,
@@ -373,6 +375,7 @@ where
         })?;
 
     let is_authority = domain_config.role.is_authority();
+    let prometheus_registry = domain_config.prometheus_registry().cloned();
     let domain_state_pruning = domain_config.state_pruning.clone().unwrap_or_default();
     domain_config.rpc_id_provider = provider.rpc_id();
     let rpc_builder = {
@@ -385,7 +388,7 @@ where
             network: network_service.clone(),
             sync: sync_service.clone(),
             is_authority,
-            prometheus_registry: domain_config.prometheus_registry().cloned(),
+            prometheus_registry: prometheus_registry.clone(),
             database_source: domain_config.database.clone(),
             task_spawner: task_manager.spawn_handle(),
             backend: backend.clone(),
@@ -431,7 +434,16 @@ where
     })?;
 
     let spawn_essential = task_manager.spawn_essential_handle();
-    let (bundle_sender, _bundle_receiver) = tracing_unbounded("domain_bundle_stream", 100);
+    let (bundle_sender, bundle_receiver) = tracing_unbounded("domain_bundle_stream", 100);
+    task_manager.spawn_handle().spawn(
+        "domain-compact-bundle-receiver",
+        None,
+        domain_client_operator::reconstruct_compact_bundles(
+            domain_id,
+            transaction_pool.clone(),
+            bundle_receiver,
+        ),
+    );
 
     // let domain_confirmation_depth = consensus_client
     // .runtime_api()
@@ -459,6 +471,8 @@ where
             operator_streams,
             domain_confirmation_depth,
             block_import,
+            bundle_limits,
+            prometheus_registry,
             skip_empty_bundle_production,
             skip_out_of_order_slot,
         },
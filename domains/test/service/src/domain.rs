@@ -7,7 +7,9 @@ use crate::{
     Sr25519Keyring, UncheckedExtrinsicFor, AUTO_ID_DOMAIN_ID, EVM_DOMAIN_ID,
 };
 use cross_domain_message_gossip::ChainMsg;
-use domain_client_operator::{fetch_domain_bootstrap_info, BootstrapResult, OperatorStreams};
+use domain_client_operator::{
+    fetch_domain_bootstrap_info, BootstrapResult, BundleLimits, OperatorStreams,
+};
 use domain_runtime_primitives::opaque::Block;
 use domain_runtime_primitives::Balance;
 use domain_service::providers::DefaultProvider;
@@ -209,6 +211,7 @@ where
             gossip_message_sink: gossip_msg_sink,
             domain_message_receiver,
             provider: DefaultProvider,
+            bundle_limits: BundleLimits::default(),
             skip_empty_bundle_production,
             skip_out_of_order_slot: true,
             maybe_operator_id,
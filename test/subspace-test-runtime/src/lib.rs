@@ -55,10 +55,10 @@ use sp_core::crypto::{ByteArray, KeyTypeId};
 use sp_core::{OpaqueMetadata, H256};
 use sp_domains::bundle_producer_election::BundleProducerElectionParams;
 use sp_domains::{
-    DomainAllowlistUpdates, DomainId, DomainInstanceData, DomainsHoldIdentifier,
+    DomainAllowlistUpdates, DomainId, DomainInstanceData, DomainsHoldIdentifier, EpochIndex,
     ExecutionReceiptFor, MessengerHoldIdentifier, OpaqueBundle, OpaqueBundles, OperatorId,
-    OperatorPublicKey, StakingHoldIdentifier, DOMAIN_STORAGE_FEE_MULTIPLIER,
-    INITIAL_DOMAIN_TX_RANGE,
+    OperatorPublicKey, OperatorStatusInfo, PendingWithdrawal, StakingHoldIdentifier,
+    DOMAIN_STORAGE_FEE_MULTIPLIER, INITIAL_DOMAIN_TX_RANGE,
 };
 use sp_domains_fraud_proof::fraud_proof::FraudProof;
 use sp_domains_fraud_proof::storage_proof::{
@@ -1470,6 +1470,30 @@ impl_runtime_apis! {
         fn domain_sudo_call(domain_id: DomainId) -> Option<Vec<u8>> {
             Domains::domain_sudo_call(domain_id)
         }
+
+        fn operator_rankings(domain_id: DomainId) -> Vec<(OperatorId, Balance, Perbill)> {
+            Domains::operator_rankings(domain_id)
+        }
+
+        fn operator_stake_at(operator_id: OperatorId, epoch_index: EpochIndex) -> Option<Balance> {
+            Domains::operator_stake_at(operator_id, epoch_index)
+        }
+
+        fn min_stake_to_enter(domain_id: DomainId) -> Balance {
+            Domains::min_stake_to_enter(domain_id)
+        }
+
+        fn pending_deposit(operator_id: OperatorId, nominator_id: AccountId) -> Option<Balance> {
+            Domains::pending_deposit(operator_id, nominator_id)
+        }
+
+        fn pending_withdrawal(operator_id: OperatorId, nominator_id: AccountId) -> Option<PendingWithdrawal<Balance, Balance>> {
+            Domains::pending_withdrawal(operator_id, nominator_id)
+        }
+
+        fn operator_status(operator_id: OperatorId) -> Option<OperatorStatusInfo> {
+            Domains::operator_status(operator_id)
+        }
     }
 
     impl sp_domains::BundleProducerElectionApi<Block, Balance> for Runtime {